@@ -156,6 +156,7 @@ fn impl_iterators<'a>(
              key
          }| {
             let fun_name = quote::format_ident!("iterby_{}", format!("{}", primary_field));
+            let get_fun_name = quote::format_ident!("getby_{}", format!("{}", primary_field));
             let ty_name = quote::format_ident!("IterBy_{}_Tuple", format!("{}", primary_field));
             let ty_fields = fields.iter().zip(rows.iter()).map(|(f, r)| {
                 quote! {
@@ -167,6 +168,8 @@ fn impl_iterators<'a>(
                     let #f = self.#f.get(id)
                 }
             });
+            let get_gets = gets.clone();
+            let get_fields = fields.iter();
             quote! {
                #[allow(non_camel_case_types)]
                #[derive(serde::Serialize)]
@@ -188,6 +191,16 @@ fn impl_iterators<'a>(
                             }
                         } )
                     }
+
+                    /// Returns `None` if the primary table does not contain `id`
+                    pub fn #get_fun_name <'boi> (&'boi self, id: #key) -> Option<#ty_name <'boi>> {
+                        self.#primary_field.get(id)?;
+                        #(#get_gets);*;
+                        Some(#ty_name {
+                            __id: id,
+                            #(#get_fields),*
+                        })
+                    }
                 }
             }
         },
@@ -0,0 +1,175 @@
+//! Pit scripts loaded from files against each other
+//!
+//! ```txt
+//! caolo-sim-run arena --ticks 500 --output result.json winnie.yaml pooh.json
+//! ```
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+use caolo_sim::arena::{run_arena, ArenaConfig, ArenaParticipant, ArenaResult};
+use caolo_sim::prelude::*;
+use tracing::info;
+
+use crate::{parse_script, value, ArgsError};
+
+pub const USAGE: &str = "Usage: caolo-sim-run arena [OPTIONS] <SCRIPT> <SCRIPT>...
+
+Every script file (.json or .yaml) is a participant, named after the file.
+
+Options:
+    --ticks <N>             Maximum number of ticks to run [default: 1000]
+    --world-radius <N>      [default: 1]
+    --room-radius <N>       [default: 8]
+    --seed <N>              Seed of the world generation, random if not set
+    --output <FILE>         Write the result as JSON to the file instead of stdout
+    -h, --help              Print this message";
+
+#[derive(Debug)]
+pub struct ArenaArgs {
+    pub scripts: Vec<PathBuf>,
+    pub output: Option<PathBuf>,
+    pub config: ArenaConfig,
+}
+
+impl ArenaArgs {
+    /// Returns None if help was requested
+    pub fn parse(mut args: impl Iterator<Item = String>) -> Result<Option<Self>, ArgsError> {
+        let mut res = Self {
+            scripts: Vec::new(),
+            output: None,
+            config: ArenaConfig::default(),
+        };
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--ticks" => res.config.max_ticks = value(&arg, &mut args)?,
+                "--world-radius" => res.config.world_radius = value(&arg, &mut args)?,
+                "--room-radius" => res.config.room_radius = value(&arg, &mut args)?,
+                "--seed" => res.config.world_seed = value(&arg, &mut args)?,
+                "--output" => res.output = Some(value(&arg, &mut args)?),
+                "-h" | "--help" => return Ok(None),
+                _ if arg.starts_with('-') => return Err(ArgsError::Unrecognized(arg)),
+                _ => res.scripts.push(PathBuf::from(arg)),
+            }
+        }
+        Ok(Some(res))
+    }
+}
+
+/// Load the participants from the script files, named after the file stems
+pub fn load_participants(paths: &[PathBuf]) -> anyhow::Result<Vec<ArenaParticipant>> {
+    paths
+        .iter()
+        .map(|path| {
+            let script = std::fs::read_to_string(path)
+                .with_context(|| format!("Failed to read script {:?}", path))?;
+            let script = parse_script(path, &script)
+                .with_context(|| format!("Failed to parse script {:?}", path))?;
+            Ok(ArenaParticipant {
+                name: participant_name(path),
+                script,
+            })
+        })
+        .collect()
+}
+
+fn participant_name(path: &Path) -> String {
+    path.file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_else(|| path.display().to_string())
+}
+
+pub fn run(args: ArenaArgs) -> anyhow::Result<ArenaResult> {
+    let participants = load_participants(&args.scripts)?;
+    info!("Using world seed {}", args.config.world_seed);
+
+    let mut executor = SimpleExecutor::default();
+    futures_lite::future::block_on(run_arena(&mut executor, args.config, participants))
+        .map_err(|err| anyhow::anyhow!("{}", err))
+}
+
+pub fn main(args: impl Iterator<Item = String>) -> anyhow::Result<()> {
+    let args = match ArenaArgs::parse(args) {
+        Ok(Some(args)) => args,
+        Ok(None) => {
+            println!("{}", USAGE);
+            return Ok(());
+        }
+        Err(err) => {
+            eprintln!("{}\n\n{}", err, USAGE);
+            std::process::exit(1);
+        }
+    };
+    info!("Running arena with {:#?}", args);
+
+    let output = args.output.clone();
+    let result = run(args)?;
+    match output {
+        Some(path) => {
+            let out = std::fs::File::create(&path)
+                .with_context(|| format!("Failed to create output file {:?}", path))?;
+            serde_json::to_writer_pretty(std::io::BufWriter::new(out), &result)?;
+            info!("Wrote the arena result to {:?}", path);
+        }
+        None => println!("{}", serde_json::to_string_pretty(&result)?),
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const IDLE: &str = r#"
+lanes:
+    - cards:
+        - ty: StringLiteral
+          val: "idle"
+        - ty: CallNative
+          val: "console_log"
+"#;
+
+    #[test]
+    fn runs_scripts_loaded_from_files() {
+        let dir = std::env::temp_dir().join(format!("caolo-arena-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let scripts = ["winnie.yaml", "pooh.yaml"]
+            .iter()
+            .map(|name| {
+                let path = dir.join(name);
+                std::fs::write(&path, IDLE).unwrap();
+                path
+            })
+            .collect::<Vec<_>>();
+
+        let args = ArenaArgs::parse(
+            ["--ticks", "10", "--seed", "42"]
+                .iter()
+                .map(|s| s.to_string())
+                .chain(scripts.iter().map(|p| p.display().to_string())),
+        )
+        .unwrap()
+        .unwrap();
+        let result = run(args).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(result.ticks, 10);
+        assert_eq!(result.stats.len(), 2);
+        assert_eq!(result.stats[0].name, "winnie");
+        assert_eq!(result.stats[1].name, "pooh");
+        for stats in result.stats.iter() {
+            assert!(stats.eliminated_at.is_none());
+            assert!(stats.structures_alive > 0);
+            assert_eq!(stats.damage_dealt, 0);
+            assert_eq!(stats.energy_mined, 0);
+        }
+        // idle participants in identical rooms score the same
+        assert_eq!(result.stats[0].score(), result.stats[1].score());
+        assert_eq!(result.winner, None);
+    }
+
+    #[test]
+    fn unknown_options_are_rejected() {
+        let res = ArenaArgs::parse(["--winnie"].iter().map(|s| s.to_string()));
+        assert!(matches!(res, Err(ArgsError::Unrecognized(_))));
+    }
+}
@@ -0,0 +1,228 @@
+//! Run the simulation headless, as fast as possible, and record per-tick metrics
+//!
+//! ```txt
+//! caolo-sim-run --config preset.yaml --ticks 10000 --users 50 --scripts dir/ --output metrics.csv
+//! ```
+//!
+//! The `arena` subcommand pits scripts against each other, see [arena].
+mod arena;
+mod metrics;
+
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+use anyhow::Context;
+use cao_lang::compiler::CaoIr;
+use caolo_sim::prelude::*;
+use thiserror::Error;
+use tracing::info;
+
+use crate::metrics::{OutputFormat, TickMetrics};
+
+const USAGE: &str = "Usage: caolo-sim-run [OPTIONS]
+       caolo-sim-run arena [OPTIONS] <SCRIPT> <SCRIPT>...
+
+Options:
+    --config <FILE>         YAML file of map generation presets
+    --preset <NAME>         Name of the preset to use from the config file [default: default]
+    --ticks <N>             Number of ticks to run [default: 1000]
+    --users <N>             Number of users to initialize [default: 10]
+    --scripts <DIR>         Directory of cao-lang programs (.json or .yaml), assigned to the users round-robin
+    --output <FILE>         Metrics output, JSON if the extension is .json, CSV otherwise [default: metrics.csv]
+    --world-radius <N>      Radius of the overworld, derived from the number of users by default
+    --room-radius <N>       [default: 16]
+    --seed <N>              Seed of the world generation, random if not set
+    -h, --help              Print this message";
+
+#[derive(Debug, Error)]
+enum ArgsError {
+    #[error("Unrecognized argument {0}")]
+    Unrecognized(String),
+    #[error("Missing value of {0}")]
+    MissingValue(String),
+    #[error("Invalid value of {0}: {1}")]
+    InvalidValue(String, String),
+}
+
+#[derive(Debug)]
+struct Args {
+    config: Option<PathBuf>,
+    preset: String,
+    ticks: u64,
+    users: usize,
+    scripts: Option<PathBuf>,
+    output: PathBuf,
+    world_radius: Option<u32>,
+    room_radius: u32,
+    seed: Option<u64>,
+}
+
+impl Default for Args {
+    fn default() -> Self {
+        Self {
+            config: None,
+            preset: "default".to_string(),
+            ticks: 1000,
+            users: 10,
+            scripts: None,
+            output: PathBuf::from("metrics.csv"),
+            world_radius: None,
+            room_radius: 16,
+            seed: None,
+        }
+    }
+}
+
+impl Args {
+    /// Returns None if help was requested
+    fn parse(mut args: impl Iterator<Item = String>) -> Result<Option<Self>, ArgsError> {
+        let mut res = Self::default();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--config" => res.config = Some(value(&arg, &mut args)?),
+                "--preset" => res.preset = value(&arg, &mut args)?,
+                "--ticks" => res.ticks = value(&arg, &mut args)?,
+                "--users" => res.users = value(&arg, &mut args)?,
+                "--scripts" => res.scripts = Some(value(&arg, &mut args)?),
+                "--output" => res.output = value(&arg, &mut args)?,
+                "--world-radius" => res.world_radius = Some(value(&arg, &mut args)?),
+                "--room-radius" => res.room_radius = value(&arg, &mut args)?,
+                "--seed" => res.seed = Some(value(&arg, &mut args)?),
+                "-h" | "--help" => return Ok(None),
+                _ => return Err(ArgsError::Unrecognized(arg)),
+            }
+        }
+        Ok(Some(res))
+    }
+}
+
+/// Parse the next argument as the value of the option `name`
+fn value<T: std::str::FromStr>(
+    name: &str,
+    args: &mut impl Iterator<Item = String>,
+) -> Result<T, ArgsError> {
+    let value = args
+        .next()
+        .ok_or_else(|| ArgsError::MissingValue(name.to_string()))?;
+    value
+        .parse()
+        .map_err(|_| ArgsError::InvalidValue(name.to_string(), value))
+}
+
+/// Path and contents of the script files in `dir`
+fn load_scripts(dir: &Path) -> anyhow::Result<Vec<(PathBuf, String)>> {
+    let mut paths = std::fs::read_dir(dir)
+        .with_context(|| format!("Failed to read scripts directory {:?}", dir))?
+        .map(|entry| entry.map(|e| e.path()))
+        .collect::<Result<Vec<_>, _>>()?;
+    paths.sort();
+
+    let mut scripts = Vec::with_capacity(paths.len());
+    for path in paths {
+        if !matches!(
+            path.extension().and_then(|ext| ext.to_str()),
+            Some("json") | Some("yaml") | Some("yml")
+        ) {
+            continue;
+        }
+        let script = std::fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read script {:?}", path))?;
+        info!("Loaded script {:?}", path);
+        scripts.push((path, script));
+    }
+    Ok(scripts)
+}
+
+fn parse_script(path: &Path, script: &str) -> anyhow::Result<CaoIr> {
+    let ir = match path.extension().and_then(|ext| ext.to_str()) {
+        Some("json") => serde_json::from_str(script)?,
+        _ => serde_yaml::from_str(script)?,
+    };
+    Ok(ir)
+}
+
+fn main() -> anyhow::Result<()> {
+    let collector = tracing_subscriber::fmt()
+        .without_time()
+        .with_env_filter(tracing_subscriber::filter::EnvFilter::from_default_env())
+        .finish();
+    tracing::subscriber::set_global_default(collector).unwrap();
+
+    let mut args = std::env::args().skip(1).peekable();
+    if args.peek().map(|arg| arg == "arena").unwrap_or(false) {
+        return arena::main(args.skip(1));
+    }
+    let args = match Args::parse(args) {
+        Ok(Some(args)) => args,
+        Ok(None) => {
+            println!("{}", USAGE);
+            return Ok(());
+        }
+        Err(err) => {
+            eprintln!("{}\n\n{}", err, USAGE);
+            std::process::exit(1);
+        }
+    };
+    info!("Running with {:#?}", args);
+
+    let map_generation = match args.config.as_ref() {
+        Some(path) => caolo_sim::map_generation::presets::load_preset(path, args.preset.as_str())
+            .context("Failed to load map generation preset")?,
+        None => Default::default(),
+    };
+    let world_radius = args.world_radius.unwrap_or_else(|| {
+        let a = args.users as f32;
+        ((a * 1.0 / (3.0 * 3.0f32.sqrt())).powf(0.33)).ceil() as u32
+    });
+    let mut game_config = GameConfig {
+        world_radius,
+        room_radius: args.room_radius,
+        target_tick_ms: 0,
+        map_generation,
+        ..Default::default()
+    };
+    if let Some(seed) = args.seed {
+        game_config.world_seed = seed;
+    }
+    info!("Using world seed {}", game_config.world_seed);
+
+    let mut executor = SimpleExecutor::default();
+    let mut world = futures_lite::future::block_on(executor.initialize(game_config));
+    caolo_sim::init::init_world_entities(&mut world, args.users);
+
+    if let Some(dir) = args.scripts.as_ref() {
+        let scripts = load_scripts(dir)?;
+        anyhow::ensure!(!scripts.is_empty(), "No scripts found in {:?}", dir);
+        let users = world
+            .view::<UserId, EntityScript>()
+            .iter()
+            .map(|(id, _)| id)
+            .collect::<Vec<_>>();
+        for (user, (path, script)) in users.into_iter().zip(scripts.iter().cycle()) {
+            let ir = parse_script(path, script)
+                .with_context(|| format!("Failed to parse script {:?}", path))?;
+            caolo_sim::init::set_user_script(&mut world, user, ir)
+                .with_context(|| format!("Failed to compile script {:?}", path))?;
+        }
+    }
+
+    let mut metrics = Vec::with_capacity(args.ticks as usize);
+    let start = Instant::now();
+    for _ in 0..args.ticks {
+        let tick_start = Instant::now();
+        let intents = futures_lite::future::block_on(executor.forward_bots(&world)).unwrap();
+        futures_lite::future::block_on(executor.apply_intents(&mut world, intents)).unwrap();
+        metrics.push(TickMetrics::collect(&world, tick_start.elapsed()));
+    }
+    info!("Ran {} ticks in {:.2?}", args.ticks, start.elapsed());
+
+    let out = std::fs::File::create(&args.output)
+        .with_context(|| format!("Failed to create output file {:?}", args.output))?;
+    metrics::write_metrics(
+        &metrics,
+        OutputFormat::from_path(&args.output),
+        std::io::BufWriter::new(out),
+    )?;
+    info!("Wrote metrics to {:?}", args.output);
+    Ok(())
+}
@@ -0,0 +1,115 @@
+use std::io::Write;
+use std::time::Duration;
+
+use caolo_sim::prelude::*;
+use serde::Serialize;
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TickMetrics {
+    pub tick: u64,
+    pub bots: usize,
+    pub structures: usize,
+    pub resources: usize,
+    /// Energy mined during the tick
+    pub resources_mined: u64,
+    pub deaths: u64,
+    pub tick_ms: f64,
+}
+
+impl TickMetrics {
+    /// Collect the metrics of the last tick. Call after the world update.
+    pub fn collect(world: &World, tick_time: Duration) -> Self {
+        let mut metrics = Self {
+            tick: world.time(),
+            bots: world.view::<EntityId, Bot>().iter().count(),
+            structures: world.view::<EntityId, Structure>().iter().count(),
+            resources: world.view::<EntityId, ResourceComponent>().len(),
+            tick_ms: tick_time.as_secs_f64() * 1000.0,
+            ..Default::default()
+        };
+        for event in world.view::<EmptyKey, WorldEvents>().unwrap_value().iter() {
+            match event {
+                WorldEvent::Mine { amount, .. } => metrics.resources_mined += *amount as u64,
+                WorldEvent::Death { .. } => metrics.deaths += 1,
+                _ => {}
+            }
+        }
+        metrics
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    Csv,
+    Json,
+}
+
+impl OutputFormat {
+    /// Guess the format from the file extension, defaults to CSV
+    pub fn from_path(path: &std::path::Path) -> Self {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("json") => OutputFormat::Json,
+            _ => OutputFormat::Csv,
+        }
+    }
+}
+
+pub fn write_metrics(
+    metrics: &[TickMetrics],
+    format: OutputFormat,
+    mut out: impl Write,
+) -> anyhow::Result<()> {
+    match format {
+        OutputFormat::Json => serde_json::to_writer_pretty(out, metrics)?,
+        OutputFormat::Csv => {
+            writeln!(
+                out,
+                "tick,bots,structures,resources,resourcesMined,deaths,tickMs"
+            )?;
+            for m in metrics {
+                writeln!(
+                    out,
+                    "{},{},{},{},{},{},{:.3}",
+                    m.tick,
+                    m.bots,
+                    m.structures,
+                    m.resources,
+                    m.resources_mined,
+                    m.deaths,
+                    m.tick_ms
+                )?;
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn writes_csv_rows() {
+        let metrics = vec![TickMetrics {
+            tick: 1,
+            bots: 2,
+            structures: 3,
+            resources: 4,
+            resources_mined: 5,
+            deaths: 6,
+            tick_ms: 7.0,
+        }];
+        let mut out = Vec::new();
+        write_metrics(&metrics, OutputFormat::Csv, &mut out).unwrap();
+        let out = String::from_utf8(out).unwrap();
+
+        let mut lines = out.lines();
+        assert_eq!(
+            lines.next(),
+            Some("tick,bots,structures,resources,resourcesMined,deaths,tickMs")
+        );
+        assert_eq!(lines.next(), Some("1,2,3,4,5,6,7.000"));
+        assert_eq!(lines.next(), None);
+    }
+}
@@ -23,8 +23,7 @@ fn contains_rand(c: &mut Criterion) {
                     r: rng.gen_range(0..=8000),
                 };
                 (p, i)
-            }))
-            .unwrap();
+            }));
 
             b.iter(|| {
                 let p = Axial {
@@ -51,8 +50,7 @@ fn get_entities_in_range_sparse(c: &mut Criterion) {
                     r: rng.gen_range(0..=3900 * 2),
                 };
                 (p, EntityComponent(EntityId::new(rng.gen(), 0)))
-            }))
-            .unwrap();
+            }));
 
             let radius = 512;
             b.iter(|| {
@@ -83,8 +81,7 @@ fn get_entities_in_range_dense(c: &mut Criterion) {
                     r: rng.gen_range(0..=200 * 2),
                 };
                 (p, EntityComponent(EntityId::new(rng.gen(), 0)))
-            }))
-            .unwrap();
+            }));
 
             let radius = 50;
             b.iter(|| {
@@ -118,8 +115,7 @@ fn make_morton_table(c: &mut Criterion) {
                         },
                         rng.next_u32(),
                     )
-                }))
-                .unwrap();
+                }));
                 table
             });
         });
@@ -140,17 +136,15 @@ fn rebuild_morton_table(c: &mut Criterion) {
             b.iter(|| {
                 table.clear();
 
-                table
-                    .extend((0..size).map(|_| {
-                        (
-                            Axial {
-                                q: rng.gen_range(0..=3900 * 2),
-                                r: rng.gen_range(0..=3900 * 2),
-                            },
-                            rng.next_u32(),
-                        )
-                    }))
-                    .unwrap();
+                table.extend((0..size).map(|_| {
+                    (
+                        Axial {
+                            q: rng.gen_range(0..=3900 * 2),
+                            r: rng.gen_range(0..=3900 * 2),
+                        },
+                        rng.next_u32(),
+                    )
+                }));
             });
         });
     }
@@ -170,8 +164,7 @@ fn at_rand(c: &mut Criterion) {
                     r: rng.gen_range(0..=3900 * 2),
                 };
                 (pos, rng.next_u32())
-            }))
-            .unwrap();
+            }));
 
             b.iter(|| {
                 let pos = Axial {
@@ -200,8 +193,7 @@ fn at_in_table_rand(c: &mut Criterion) {
                 };
                 points.push(pos.clone());
                 (pos, rng.next_u32())
-            }))
-            .unwrap();
+            }));
 
             b.iter(|| {
                 let i = rng.gen_range(0..points.len());
@@ -226,7 +218,7 @@ fn random_insert(c: &mut Criterion) {
                 let r = rng.gen_range(0..=29000);
                 let p = Axial::new(q, r);
 
-                table.insert(p, 420).unwrap();
+                table.insert(p, 420);
             }
 
             b.iter(|| {
@@ -234,7 +226,7 @@ fn random_insert(c: &mut Criterion) {
                 let r = rng.gen_range(0..=29000);
                 let p = Axial::new(q, r);
 
-                table.insert(p, 420).unwrap()
+                table.insert(p, 420);
             });
         });
     }
@@ -256,7 +248,7 @@ fn random_update(c: &mut Criterion) {
                 let p = Axial::new(q, r);
                 memory.push(p);
 
-                table.insert(p, 420).unwrap();
+                table.insert(p, 420);
             }
 
             let memory = &memory;
@@ -2,7 +2,7 @@ use caolo_sim::{
     components::{EntityComponent, TerrainComponent},
     executor::{GameConfig, SimpleExecutor},
     indices::WorldPosition,
-    pathfinding::find_path,
+    pathfinding::{find_path, PathOptions},
     prelude::{FromWorld, World},
 };
 use criterion::{criterion_group, Criterion};
@@ -14,7 +14,7 @@ fn get_rand() -> impl rand::Rng {
 }
 
 fn create_world(room_radius: u32) -> World {
-    let mut exc = SimpleExecutor;
+    let mut exc = SimpleExecutor::default();
     let world = futures_lite::future::block_on(exc.initialize(GameConfig {
         world_radius: 6,
         room_radius,
@@ -45,15 +45,16 @@ fn bench_find_path_in_room(c: &mut Criterion) {
     {
         let mut positions = world.unsafe_view::<WorldPosition, EntityComponent>();
         if positions.table.at(room.0).is_none() {
-            positions
-                .table
-                .insert(room.0, Default::default())
-                .expect("Failed to init entites table");
+            positions.table.insert(room.0, Default::default());
         }
     }
 
     let mut path = Vec::new();
     let mut rooms2visit = None;
+    let options = PathOptions {
+        max_ops: 2000,
+        ..Default::default()
+    };
 
     c.bench_function("find_path_in_room", move |b| {
         let mut rng = get_rand();
@@ -71,7 +72,7 @@ fn bench_find_path_in_room(c: &mut Criterion) {
                 WorldPosition { room, pos: to },
                 1,
                 FromWorld::from_world(&world),
-                2000,
+                &options,
                 &mut path,
                 &mut rooms2visit,
             )
@@ -1,6 +1,7 @@
 mod btree_table;
 mod morton_table;
 mod pathfinding_benches;
+mod sparse_set_table;
 mod table_join;
 
 use criterion::criterion_main;
@@ -9,5 +10,6 @@ criterion_main!(
     morton_table::morton_benches,
     btree_table::btree_benches,
     table_join::join_benches,
-    pathfinding_benches::pathfinding_benches
+    pathfinding_benches::pathfinding_benches,
+    sparse_set_table::sparse_set_benches
 );
@@ -0,0 +1,121 @@
+use caolo_sim::indices::EntityId;
+use caolo_sim::tables::page_table::PageTable;
+use caolo_sim::tables::sparse_set_table::SparseSetTable;
+use criterion::{black_box, criterion_group, BenchmarkId, Criterion};
+use rand::{rngs::SmallRng, Rng, SeedableRng};
+
+/// Number of entities in the World
+const ENTITIES: u32 = 1 << 16;
+
+fn get_rand() -> impl rand::Rng {
+    SmallRng::seed_from_u64(0xdeadbeef)
+}
+
+fn random_ids(len: usize) -> Vec<EntityId> {
+    let mut rng = get_rand();
+    (0..len)
+        .map(|_| EntityId::new(rng.gen_range(0..ENTITIES), 0))
+        .collect()
+}
+
+/// Insert a few components, iterate them, then clear the table, like events of a tick
+fn insert_iter_clear(c: &mut Criterion) {
+    let mut group = c.benchmark_group("insert_iter_clear");
+    for len in [16, 256, 4096].iter().copied() {
+        let ids = random_ids(len);
+        group.bench_with_input(BenchmarkId::new("page_table", len), &ids, |b, ids| {
+            let mut table = PageTable::<u64>::new(ENTITIES as usize);
+            b.iter(|| {
+                for (i, id) in ids.iter().enumerate() {
+                    table.insert(*id, i as u64);
+                }
+                let sum: u64 = table.iter().map(|(_, x)| *x).sum();
+                table.clear();
+                black_box(sum)
+            });
+        });
+        group.bench_with_input(BenchmarkId::new("sparse_set_table", len), &ids, |b, ids| {
+            let mut table = SparseSetTable::<u64>::with_capacity(len);
+            b.iter(|| {
+                for (i, id) in ids.iter().enumerate() {
+                    table.insert(*id, i as u64);
+                }
+                let sum: u64 = table.iter().map(|(_, x)| *x).sum();
+                table.clear();
+                black_box(sum)
+            });
+        });
+    }
+    group.finish();
+}
+
+/// Insert and remove random components of a table holding 1024 rows
+fn insert_remove_random(c: &mut Criterion) {
+    let mut group = c.benchmark_group("insert_remove_random");
+    let ids = random_ids(1024);
+
+    group.bench_function("page_table", |b| {
+        let mut rng = get_rand();
+        let mut table = PageTable::<u64>::new(ENTITIES as usize);
+        for id in ids.iter() {
+            table.insert(*id, 0);
+        }
+        b.iter(|| {
+            let id = EntityId::new(rng.gen_range(0..ENTITIES), 0);
+            table.insert(id, 1);
+            black_box(table.remove(ids[rng.gen_range(0..ids.len())]))
+        });
+    });
+    group.bench_function("sparse_set_table", |b| {
+        let mut rng = get_rand();
+        let mut table = SparseSetTable::<u64>::new();
+        for id in ids.iter() {
+            table.insert(*id, 0);
+        }
+        b.iter(|| {
+            let id = EntityId::new(rng.gen_range(0..ENTITIES), 0);
+            table.insert(id, 1);
+            black_box(table.remove(ids[rng.gen_range(0..ids.len())]))
+        });
+    });
+    group.finish();
+}
+
+/// Iterate over a table holding a few components of many entities
+fn iter_sparse(c: &mut Criterion) {
+    let mut group = c.benchmark_group("iter_sparse_256");
+    let ids = random_ids(256);
+
+    group.bench_function("page_table", |b| {
+        let mut table = PageTable::<u64>::new(ENTITIES as usize);
+        for (i, id) in ids.iter().enumerate() {
+            table.insert(*id, i as u64);
+        }
+        b.iter(|| {
+            table.iter_mut().for_each(|(_, x)| {
+                *x += 1;
+                black_box(x);
+            });
+        });
+    });
+    group.bench_function("sparse_set_table", |b| {
+        let mut table = SparseSetTable::<u64>::new();
+        for (i, id) in ids.iter().enumerate() {
+            table.insert(*id, i as u64);
+        }
+        b.iter(|| {
+            table.iter_mut().for_each(|(_, x)| {
+                *x += 1;
+                black_box(x);
+            });
+        });
+    });
+    group.finish();
+}
+
+criterion_group!(
+    sparse_set_benches,
+    insert_iter_clear,
+    insert_remove_random,
+    iter_sparse
+);
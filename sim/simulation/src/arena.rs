@@ -0,0 +1,317 @@
+//! Pit the scripts of two or more users against each other in a small world
+//!
+//! Every participant gets a room with a spawn and an energy resource. The arena runs until a
+//! single participant has entities left, or `max_ticks` is reached.
+use std::collections::HashMap;
+
+use cao_lang::{compiler::CaoIr, prelude::CompilationError};
+use rand::{rngs::SmallRng, SeedableRng};
+use serde::Serialize;
+use thiserror::Error;
+use tracing::{debug, info};
+
+use crate::init::{init_user_room, random_uuid, set_user_script};
+use crate::prelude::*;
+
+#[derive(Debug, Error)]
+pub enum ArenaError {
+    #[error("At least 2 participants are required, got {0}")]
+    NotEnoughParticipants(usize),
+    #[error("The arena has {rooms} rooms, which is not enough for {participants} participants")]
+    NotEnoughRooms { rooms: usize, participants: usize },
+    #[error("Failed to compile the script of {name}: {error}")]
+    CompilationError {
+        name: String,
+        error: CompilationError,
+    },
+}
+
+pub struct ArenaParticipant {
+    pub name: String,
+    pub script: CaoIr,
+}
+
+#[derive(Debug, Clone)]
+pub struct ArenaConfig {
+    pub max_ticks: u64,
+    pub world_radius: u32,
+    pub room_radius: u32,
+    pub world_seed: u64,
+}
+
+impl Default for ArenaConfig {
+    fn default() -> Self {
+        Self {
+            max_ticks: 1000,
+            world_radius: 1,
+            room_radius: 8,
+            world_seed: rand::random(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ParticipantStats {
+    pub name: String,
+    pub user_id: UserId,
+    pub bots_alive: usize,
+    pub structures_alive: usize,
+    pub bots_spawned: u64,
+    pub entities_lost: u64,
+    pub damage_dealt: u64,
+    pub energy_mined: u64,
+    /// Tick the participant lost their last entity
+    pub eliminated_at: Option<u64>,
+}
+
+impl ParticipantStats {
+    /// Used to rank the participants surviving until the end
+    pub fn score(&self) -> u64 {
+        self.energy_mined
+            + 2 * self.damage_dealt
+            + 10 * self.bots_spawned
+            + 20 * self.bots_alive as u64
+            + 100 * self.structures_alive as u64
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ArenaResult {
+    pub ticks: u64,
+    /// Name of the winner, None on a draw
+    pub winner: Option<String>,
+    pub stats: Vec<ParticipantStats>,
+}
+
+pub async fn run_arena(
+    executor: &mut SimpleExecutor,
+    config: ArenaConfig,
+    participants: Vec<ArenaParticipant>,
+) -> Result<ArenaResult, ArenaError> {
+    if participants.len() < 2 {
+        return Err(ArenaError::NotEnoughParticipants(participants.len()));
+    }
+    let mut world = executor
+        .initialize(GameConfig {
+            world_radius: config.world_radius,
+            room_radius: config.room_radius,
+            world_seed: config.world_seed,
+            ..Default::default()
+        })
+        .await;
+    let mut stats = init_participants(&mut world, config.world_seed, participants)?;
+
+    let mut tick = 0;
+    while tick < config.max_ticks {
+        // the owners of the entities dying during the tick are gone by the end of it
+        let owners = entity_owners(&world, &stats);
+
+        let intents = executor.forward_bots(&world).await.unwrap();
+        executor.apply_intents(&mut world, intents).await.unwrap();
+        tick += 1;
+
+        record_events(&world, &owners, &mut stats);
+        let remaining = count_entities(&world, &mut stats, tick);
+        debug!(
+            "Arena tick {} done, {} participants remaining",
+            tick, remaining
+        );
+        if remaining <= 1 {
+            break;
+        }
+    }
+
+    let winner = find_winner(&stats).map(|i| stats[i].name.clone());
+    info!("Arena finished in {} ticks, winner: {:?}", tick, winner);
+    Ok(ArenaResult {
+        ticks: tick,
+        winner,
+        stats,
+    })
+}
+
+fn init_participants(
+    world: &mut World,
+    seed: u64,
+    participants: Vec<ArenaParticipant>,
+) -> Result<Vec<ParticipantStats>, ArenaError> {
+    let rooms = world
+        .view::<Axial, RoomComponent>()
+        .iter()
+        .map(|(room, _)| room)
+        .collect::<Vec<_>>();
+    if rooms.len() < participants.len() {
+        return Err(ArenaError::NotEnoughRooms {
+            rooms: rooms.len(),
+            participants: participants.len(),
+        });
+    }
+    crate::init::init_world_entities(world, 0);
+
+    let radius = UnwrapView::<ConfigKey, GameConfig>::from_world(world).room_radius as i32;
+    let bounds = Hexagon {
+        center: Axial::new(radius, radius),
+        radius,
+    };
+    let mut rng = SmallRng::seed_from_u64(seed);
+    let mut stats = Vec::with_capacity(participants.len());
+    for (participant, room) in participants.into_iter().zip(rooms) {
+        let user_id = UserId(random_uuid(&mut rng));
+        let name = participant.name;
+        set_user_script(world, user_id, participant.script).map_err(|error| {
+            ArenaError::CompilationError {
+                name: name.clone(),
+                error,
+            }
+        })?;
+        init_user_room(user_id, Room(room), &bounds, &mut rng, world);
+        stats.push(ParticipantStats {
+            name,
+            user_id,
+            structures_alive: 1,
+            ..Default::default()
+        });
+    }
+    Ok(stats)
+}
+
+/// Map the entities to the index of their owner in `stats`
+fn entity_owners(world: &World, stats: &[ParticipantStats]) -> HashMap<EntityId, usize> {
+    let participants = stats
+        .iter()
+        .enumerate()
+        .map(|(i, s)| (s.user_id, i))
+        .collect::<HashMap<_, _>>();
+    world
+        .view::<EntityId, OwnedEntity>()
+        .iter()
+        .filter_map(|(id, owner)| Some((id, *participants.get(&owner.owner_id)?)))
+        .collect()
+}
+
+fn record_events(world: &World, owners: &HashMap<EntityId, usize>, stats: &mut [ParticipantStats]) {
+    let new_owners = entity_owners(world, stats);
+    let owner = |id: &EntityId| owners.get(id).or_else(|| new_owners.get(id)).copied();
+    for event in world.view::<EmptyKey, WorldEvents>().unwrap_value().iter() {
+        match event {
+            WorldEvent::Death { entity, .. } => {
+                if let Some(i) = owner(entity) {
+                    stats[i].entities_lost += 1;
+                }
+            }
+            WorldEvent::Attack {
+                attacker, damage, ..
+            } => {
+                if let Some(i) = owner(attacker) {
+                    stats[i].damage_dealt += *damage as u64;
+                }
+            }
+            WorldEvent::Spawn { spawn, .. } => {
+                if let Some(i) = owner(spawn) {
+                    stats[i].bots_spawned += 1;
+                }
+            }
+            WorldEvent::Mine { bot, amount, .. } => {
+                if let Some(i) = owner(bot) {
+                    stats[i].energy_mined += *amount as u64;
+                }
+            }
+            WorldEvent::Dropoff { .. } => {}
+        }
+    }
+}
+
+/// Update the number of living entities and eliminate the participants without any.
+/// Returns the number of participants remaining.
+fn count_entities(world: &World, stats: &mut [ParticipantStats], tick: u64) -> usize {
+    for s in stats.iter_mut() {
+        s.bots_alive = 0;
+        s.structures_alive = 0;
+    }
+    let bots = world.view::<EntityId, Bot>();
+    let structures = world.view::<EntityId, Structure>();
+    // controllers of the starting rooms can not be destroyed
+    let controllers = world.view::<EntityId, RoomControllerComponent>();
+    for (id, i) in entity_owners(world, stats) {
+        if bots.contains(&id) {
+            stats[i].bots_alive += 1;
+        } else if structures.contains(&id) && !controllers.contains(id) {
+            stats[i].structures_alive += 1;
+        }
+    }
+    let mut remaining = 0;
+    for s in stats.iter_mut() {
+        if s.bots_alive + s.structures_alive > 0 {
+            remaining += 1;
+        } else if s.eliminated_at.is_none() {
+            s.eliminated_at = Some(tick);
+        }
+    }
+    remaining
+}
+
+/// The last participant standing wins, otherwise the survivor with the highest score.
+/// Returns None on a draw.
+fn find_winner(stats: &[ParticipantStats]) -> Option<usize> {
+    let mut ranking = stats
+        .iter()
+        .enumerate()
+        .filter(|(_, s)| s.eliminated_at.is_none())
+        .map(|(i, s)| (s.score(), i))
+        .collect::<Vec<_>>();
+    ranking.sort_unstable_by(|a, b| b.cmp(a));
+    match ranking.as_slice() {
+        [] => None,
+        [(_, i)] => Some(*i),
+        [(a, i), (b, _), ..] => {
+            if a != b {
+                Some(*i)
+            } else {
+                None
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stats(name: &str, energy_mined: u64, eliminated_at: Option<u64>) -> ParticipantStats {
+        ParticipantStats {
+            name: name.to_string(),
+            energy_mined,
+            eliminated_at,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn last_participant_standing_wins() {
+        let stats = vec![
+            stats("winnie", 1000, Some(12)),
+            stats("pooh", 10, None),
+            stats("tigger", 500, Some(20)),
+        ];
+        assert_eq!(find_winner(&stats), Some(1));
+    }
+
+    #[test]
+    fn highest_score_wins_among_survivors() {
+        let stats = vec![stats("winnie", 10, None), stats("pooh", 20, None)];
+        assert_eq!(find_winner(&stats), Some(1));
+
+        let stats = vec![stats("winnie", 20, None), stats("pooh", 20, None)];
+        assert_eq!(find_winner(&stats), None);
+    }
+
+    #[test]
+    fn arena_requires_two_participants() {
+        let mut exc = SimpleExecutor::default();
+        let res =
+            futures_lite::future::block_on(run_arena(&mut exc, ArenaConfig::default(), vec![]));
+        assert!(matches!(res, Err(ArenaError::NotEnoughParticipants(0))));
+    }
+}
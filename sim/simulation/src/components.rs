@@ -1,17 +1,21 @@
 pub mod game_config;
 
 mod bot_components;
+mod events;
+mod relations;
 mod resources;
 mod rooms;
 mod script_components;
 pub use bot_components::*;
+pub use events::*;
+pub use relations::*;
 pub use resources::*;
 pub use rooms::*;
 pub use script_components::*;
 
 use crate::indices::{EntityId, Room, UserId, WorldPosition};
 use serde::{Deserialize, Serialize};
-use std::collections::VecDeque;
+use std::collections::{BTreeMap, VecDeque};
 
 /// For tables that store entity ids as values
 #[derive(Debug, Clone, Serialize, Deserialize, Copy, Default, Ord, PartialOrd, Eq, PartialEq)]
@@ -75,14 +79,99 @@ pub struct SpawnBotComponent {
     pub bot: Bot,
 }
 
-// TODO:
-// maximize number of logs stored
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 #[serde(rename_all = "camelCase")]
 pub struct LogEntry {
     pub payload: String,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ArchivedLog {
+    pub time: u64,
+    pub payload: String,
+}
+
+/// Ring buffer of the latest logs of an entity.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct LogArchive(pub VecDeque<ArchivedLog>);
+
+impl LogArchive {
+    /// Append the payload to the log of `time`, dropping the oldest entries if the archive holds
+    /// more than `capacity` ticks
+    pub fn push(&mut self, time: u64, payload: &str, capacity: usize) {
+        match self.0.back_mut() {
+            Some(last) if last.time == time => last.payload.push_str(payload),
+            _ => self.0.push_back(ArchivedLog {
+                time,
+                payload: payload.to_string(),
+            }),
+        }
+        while self.0.len() > capacity {
+            self.0.pop_front();
+        }
+    }
+
+    /// Logs between `from` and `to`, inclusive
+    pub fn range(&self, from: u64, to: u64) -> impl Iterator<Item = &ArchivedLog> {
+        self.0
+            .iter()
+            .skip_while(move |log| log.time < from)
+            .take_while(move |log| log.time <= to)
+    }
+}
+
+/// Archived logs of every entity.
+///
+/// Unlike `LogEntry`s these outlive the entities, so logs can be read after the entity was
+/// deleted. Logs are dropped once they are older than `GameConfig::log_archive_age`.
+// serialized as a list, because EntityIds are not valid keys in every format
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(
+    from = "Vec<(EntityId, LogArchive)>",
+    into = "Vec<(EntityId, LogArchive)>"
+)]
+pub struct LogArchives(pub BTreeMap<EntityId, LogArchive>);
+
+impl From<Vec<(EntityId, LogArchive)>> for LogArchives {
+    fn from(archives: Vec<(EntityId, LogArchive)>) -> Self {
+        Self(archives.into_iter().collect())
+    }
+}
+
+impl From<LogArchives> for Vec<(EntityId, LogArchive)> {
+    fn from(LogArchives(archives): LogArchives) -> Self {
+        archives.into_iter().collect()
+    }
+}
+
+impl LogArchives {
+    pub fn get(&self, id: EntityId) -> Option<&LogArchive> {
+        self.0.get(&id)
+    }
+
+    /// See [LogArchive::push]
+    pub fn push(&mut self, id: EntityId, time: u64, payload: &str, capacity: usize) {
+        self.0.entry(id).or_default().push(time, payload, capacity);
+    }
+
+    /// Drop the logs older than `before`, and the archives left empty
+    pub fn prune(&mut self, before: u64) {
+        self.0.retain(|_, archive| {
+            while archive
+                .0
+                .front()
+                .map(|log| log.time < before)
+                .unwrap_or(false)
+            {
+                archive.0.pop_front();
+            }
+            !archive.0.is_empty()
+        });
+    }
+}
+
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, Default)]
 #[serde(rename_all = "camelCase")]
 pub struct UserComponent;
@@ -104,3 +193,45 @@ pub struct Rooms(pub Vec<Room>);
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct RespawnTimer(pub i32);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn log_archive_is_bounded() {
+        let mut archive = LogArchive::default();
+        for time in 0..10 {
+            archive.push(time, "winnie", 4);
+            archive.push(time, "pooh", 4);
+        }
+
+        assert_eq!(archive.0.len(), 4);
+        assert_eq!(archive.0[0].time, 6);
+        assert_eq!(archive.0[0].payload, "winniepooh");
+
+        let times: Vec<_> = archive.range(7, 8).map(|log| log.time).collect();
+        assert_eq!(times, vec![7, 8]);
+    }
+
+    #[test]
+    fn log_archives_are_pruned_by_age() {
+        let mut archives = LogArchives::default();
+        let a = EntityId::new(1, 0);
+        let b = EntityId::new(2, 0);
+        archives.push(a, 1, "winnie", 4);
+        archives.push(a, 5, "pooh", 4);
+        archives.push(b, 2, "tigger", 4);
+
+        archives.prune(3);
+        assert!(archives.get(b).is_none());
+        let times: Vec<_> = archives
+            .get(a)
+            .unwrap()
+            .0
+            .iter()
+            .map(|log| log.time)
+            .collect();
+        assert_eq!(times, vec![5]);
+    }
+}
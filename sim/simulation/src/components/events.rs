@@ -0,0 +1,73 @@
+use crate::indices::{EntityId, WorldPosition};
+use serde::{Deserialize, Serialize};
+
+/// Notable things that happened during the last tick
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Eq, PartialEq)]
+#[serde(rename_all = "camelCase", tag = "ty")]
+pub enum WorldEvent {
+    #[serde(rename_all = "camelCase")]
+    Death {
+        entity: EntityId,
+        pos: WorldPosition,
+    },
+    #[serde(rename_all = "camelCase")]
+    Attack {
+        attacker: EntityId,
+        defender: EntityId,
+        damage: u16,
+        /// Position of the defender
+        pos: WorldPosition,
+    },
+    #[serde(rename_all = "camelCase")]
+    Spawn {
+        spawn: EntityId,
+        bot: EntityId,
+        pos: WorldPosition,
+    },
+    #[serde(rename_all = "camelCase")]
+    Mine {
+        bot: EntityId,
+        resource: EntityId,
+        amount: u16,
+        /// Position of the bot
+        pos: WorldPosition,
+    },
+    #[serde(rename_all = "camelCase")]
+    Dropoff {
+        bot: EntityId,
+        structure: EntityId,
+        amount: u16,
+        /// Position of the bot
+        pos: WorldPosition,
+    },
+}
+
+impl WorldEvent {
+    pub fn pos(&self) -> WorldPosition {
+        match self {
+            WorldEvent::Death { pos, .. }
+            | WorldEvent::Attack { pos, .. }
+            | WorldEvent::Spawn { pos, .. }
+            | WorldEvent::Mine { pos, .. }
+            | WorldEvent::Dropoff { pos, .. } => *pos,
+        }
+    }
+}
+
+/// Events of the last tick. Cleared at the start of every world update.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct WorldEvents(pub Vec<WorldEvent>);
+
+impl std::ops::Deref for WorldEvents {
+    type Target = Vec<WorldEvent>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl std::ops::DerefMut for WorldEvents {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
@@ -1,8 +1,15 @@
 use serde::{Deserialize, Serialize};
 
+use crate::geometry::Axial;
+use crate::map_generation::presets::MapGenerationPreset;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GameConfig {
     pub world_radius: u32,
+    /// Center room of the overworld, set when the map is generated.
+    /// Growing the overworld adds rings around it.
+    #[serde(default)]
+    pub overworld_center: Option<Axial>,
     pub room_radius: u32,
     pub execution_limit: u32,
     pub target_tick_ms: u64,
@@ -10,6 +17,38 @@ pub struct GameConfig {
     pub queen_tag: String,
     /// maximum number of steps pathfinding can test
     pub path_finding_limit: u32,
+    /// Seed of the map generation and world initialization.
+    /// Worlds generated with the same seed and config are identical.
+    #[serde(default = "default_world_seed")]
+    pub world_seed: u64,
+    #[serde(default)]
+    pub map_generation: MapGenerationPreset,
+    /// Number of ticks entity logs are kept for, in addition to the archive
+    #[serde(default = "default_log_retention")]
+    pub log_retention: u64,
+    /// Number of ticks of logs archived per entity
+    #[serde(default = "default_log_archive_size")]
+    pub log_archive_size: usize,
+    /// Number of ticks archived logs are kept for, including the logs of deleted entities
+    #[serde(default = "default_log_archive_age")]
+    pub log_archive_age: u64,
+}
+
+/// Configs saved before the seed was introduced get a random one, like new configs
+fn default_world_seed() -> u64 {
+    rand::random()
+}
+
+fn default_log_retention() -> u64 {
+    5
+}
+
+fn default_log_archive_size() -> usize {
+    100
+}
+
+fn default_log_archive_age() -> u64 {
+    1000
 }
 
 impl Default for GameConfig {
@@ -19,8 +58,14 @@ impl Default for GameConfig {
             target_tick_ms: 100,
             queen_tag: uuid::Uuid::new_v4().to_string(),
             world_radius: 4,
+            overworld_center: None,
             room_radius: 8,
             path_finding_limit: 1000,
+            world_seed: default_world_seed(),
+            map_generation: Default::default(),
+            log_retention: default_log_retention(),
+            log_archive_size: default_log_archive_size(),
+            log_archive_age: default_log_archive_age(),
         }
     }
 }
@@ -0,0 +1,13 @@
+use crate::indices::EntityId;
+use serde::{Deserialize, Serialize};
+
+/// Links an entity to its parent, e.g. a bot waiting for spawn to its spawn.
+/// Deleting the parent deletes its children.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct Parent(pub EntityId);
+
+/// Children of an entity, in the order they were linked
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct Children(pub Vec<EntityId>);
@@ -1,4 +1,5 @@
 use crate::geometry::Axial;
+use crate::map_generation::biome::Biome;
 use crate::terrain::TileTerrainType;
 use serde::{Deserialize, Serialize};
 
@@ -36,4 +37,62 @@ pub struct RoomComponent {
     /// Offset coordinates in world space
     pub offset: Axial,
     pub seed: u64,
+    #[serde(default)]
+    pub biome: Biome,
+}
+
+pub const CONTROLLER_MAX_LEVEL: u8 = 8;
+/// Ticks a controller keeps its level without being upgraded
+pub const CONTROLLER_DOWNGRADE_TICKS: u32 = 2000;
+
+/// Determines the ownership of the room it's in. Every room has a single controller.
+/// Bots claim unowned controllers and upgrade their own by transferring energy to them.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct RoomControllerComponent {
+    /// 0 if the room is not owned
+    pub level: u8,
+    /// Energy invested towards the next level
+    pub progress: u32,
+    /// Ticks left until the controller loses a level
+    pub downgrade_timer: u32,
+}
+
+impl RoomControllerComponent {
+    /// Energy required to advance to the next level
+    pub fn progress_max(&self) -> u32 {
+        1000 * self.level as u32
+    }
+
+    pub fn is_max_level(&self) -> bool {
+        self.level >= CONTROLLER_MAX_LEVEL
+    }
+
+    /// Level an unowned controller up to 1 and reset its downgrade timer
+    pub fn claim(&mut self) {
+        if self.level == 0 {
+            self.level = 1;
+            self.progress = 0;
+        }
+        self.downgrade_timer = CONTROLLER_DOWNGRADE_TICKS;
+    }
+
+    /// Invest energy into the controller, leveling it up as needed.
+    /// Returns the amount of energy used.
+    pub fn upgrade(&mut self, amount: u32) -> u32 {
+        let mut remaining = amount;
+        while remaining > 0 && !self.is_max_level() {
+            let needed = self.progress_max() - self.progress;
+            if remaining < needed {
+                self.progress += remaining;
+                remaining = 0;
+            } else {
+                remaining -= needed;
+                self.level += 1;
+                self.progress = 0;
+            }
+        }
+        self.downgrade_timer = CONTROLLER_DOWNGRADE_TICKS;
+        amount - remaining
+    }
 }
@@ -43,6 +43,20 @@ pub fn init_structure_spawn(id: EntityId, owner_id: Uuid, pos: WorldPosition, wo
     );
 }
 
+/// Initialize an unowned room controller at the given position
+pub fn init_room_controller(id: EntityId, pos: WorldPosition, world: &mut World) {
+    query!(
+        mutate world
+        {
+            EntityId, Structure, .insert(id);
+            EntityId, RoomControllerComponent, .insert(id, RoomControllerComponent::default());
+            EntityId, PositionComponent, .insert(id, PositionComponent(pos));
+            WorldPosition, EntityComponent, .insert(pos, EntityComponent(id))
+                .expect("entities_by_pos insert failed");
+        }
+    );
+}
+
 type InitBotTables = (
     UnsafeView<EntityId, Bot>,
     UnsafeView<EntityId, HpComponent>,
@@ -145,6 +159,5 @@ pub fn init_resource_energy(
         .table
         .at_mut(room.0)
         .expect("expected room to be in entities_by_pos table")
-        .insert(pos.pos, EntityComponent(id))
-        .expect("entities_by_pos insert");
+        .insert(pos.pos, EntityComponent(id));
 }
@@ -1,16 +1,24 @@
 use std::convert::Infallible;
 
+use rand::{rngs::SmallRng, SeedableRng};
 use tracing::debug;
 
 use crate::{
     components::EntityScript,
+    init::init_room_controller,
     intents,
-    map_generation::room::RoomGenerationParams,
     map_generation::MapGenError,
-    map_generation::{generate_full_map, overworld::OverworldGenerationParams},
-    prelude::{EntityId, FromWorldMut},
+    map_generation::{
+        expand_seed, extend_full_map, generate_full_map, overworld::overworld_center, MapExtension,
+    },
+    prelude::{
+        Axial, EntityComponent, EntityId, FromWorldMut, Hexagon, OwnedEntity, Room, RoomComponent,
+        WorldPosition,
+    },
     profile,
-    systems::{execute_world_update, script_execution::execute_scripts},
+    scheduler::{SchedulerError, Stage, SystemDescriptor, SystemRegistry},
+    storage::hooks::{default_hooks, ComponentHooks},
+    systems::{default_systems, execute_world_update, script_execution::execute_scripts},
     world::World,
 };
 
@@ -19,9 +27,41 @@ pub use crate::components::game_config::GameConfig;
 /// The simplest executor.
 ///
 /// Just runs a world update
-pub struct SimpleExecutor;
+#[derive(Debug)]
+pub struct SimpleExecutor {
+    systems: SystemRegistry,
+    hooks: ComponentHooks,
+}
+
+impl Default for SimpleExecutor {
+    fn default() -> Self {
+        Self {
+            systems: default_systems(),
+            hooks: default_hooks(),
+        }
+    }
+}
 
 impl SimpleExecutor {
+    /// Register a custom system, run in every world update
+    pub fn add_system(
+        &mut self,
+        stage: Stage,
+        system: SystemDescriptor,
+    ) -> Result<&mut Self, SchedulerError> {
+        self.systems.add_system(stage, system)?;
+        Ok(self)
+    }
+
+    pub fn systems(&self) -> &SystemRegistry {
+        &self.systems
+    }
+
+    /// Component hooks of the Worlds created by `initialize`
+    pub fn hooks_mut(&mut self) -> &mut ComponentHooks {
+        &mut self.hooks
+    }
+
     pub async fn forward_bots(
         &self,
         world: &World,
@@ -60,53 +100,110 @@ impl SimpleExecutor {
         intents::move_into_storage(world, intents);
 
         debug!("Executing systems update");
-        execute_world_update(world);
+        execute_world_update(world, &self.systems);
 
         debug!("Executing post-processing");
         world.post_process();
+        self.systems.run_stage(Stage::PostProcess, world);
 
         debug!("Done");
 
         Ok(())
     }
 
-    pub async fn initialize(&mut self, config: GameConfig) -> World {
-        let mut world = World::new();
+    pub async fn initialize(&mut self, mut config: GameConfig) -> World {
+        let mut world = World::with_hooks(self.hooks.clone());
 
         execute_map_generation(&mut world, &config)
             .await
             .expect("Failed to generate world map");
+        config.overworld_center = Some(overworld_center(config.world_radius));
 
         world.config.game_config.value = Some(config);
 
         world
     }
+
+    /// Add `rings` new rings of rooms to the overworld
+    pub async fn grow_overworld(
+        &mut self,
+        world: &mut World,
+        rings: u32,
+    ) -> Result<MapExtension, MapGenError> {
+        let mut config = world
+            .config
+            .game_config
+            .value
+            .clone()
+            .expect("expected the world to be initialized");
+        let old_radius = config.world_radius;
+        let center = *config
+            .overworld_center
+            .get_or_insert_with(|| overworld_center(old_radius));
+        config.world_radius += rings;
+
+        let s = tracing::error_span!(
+            "grow-overworld",
+            old_radius = old_radius,
+            new_radius = config.world_radius
+        );
+        let _e = s.enter();
+        debug!("Growing the overworld");
+
+        let extension = execute_map_extension(world, center, old_radius, &config).await?;
+
+        let mut positions = world.unsafe_view::<WorldPosition, EntityComponent>();
+        for room in extension.new_rooms.iter().copied() {
+            if !positions.table.contains_key(room) {
+                positions.table.insert(room, Default::default());
+            }
+        }
+
+        let bounds = Hexagon::from_radius(config.room_radius as i32);
+        let mut rng = SmallRng::seed_from_u64(config.world_seed ^ u64::from(config.world_radius));
+        for room in extension.new_rooms.iter().copied() {
+            init_room_controller(Room(room), &bounds, &mut rng, world);
+        }
+
+        world.config.game_config.value = Some(config);
+        debug!("Added {} rooms", extension.new_rooms.len());
+
+        Ok(extension)
+    }
+}
+
+/// Number of rooms without an owner
+pub fn count_free_rooms(world: &World) -> usize {
+    let owners = world.view::<Axial, OwnedEntity>();
+    world
+        .view::<Axial, RoomComponent>()
+        .iter()
+        .filter(|(room, _)| !owners.contains_key(*room))
+        .count()
 }
 
 async fn execute_map_generation(world: &mut World, config: &GameConfig) -> Result<(), MapGenError> {
     let world_radius = config.world_radius;
     let room_radius = config.room_radius;
     assert!(room_radius > 6);
-    let params = OverworldGenerationParams::builder()
-        .with_radius(world_radius as u32)
-        .with_room_radius(room_radius)
-        .with_min_bridge_len(3)
-        .with_max_bridge_len(room_radius - 3)
+    let preset = &config.map_generation;
+    let params = preset
+        .overworld_params(world_radius, room_radius)
+        .map_err(|err| MapGenError::OverworldGenerationParamsError { err })?;
+    let room_params = preset
+        .room_params_builder(room_radius)
+        .with_world_seed(config.world_seed)
         .build()
-        .unwrap();
-    let room_params = RoomGenerationParams::builder()
-        .with_radius(room_radius)
-        .with_chance_plain(0.13)
-        .with_chance_wall(1.0 - 0.13)
-        .with_plain_dilation(2)
-        .build()
-        .unwrap();
-    debug!("generating map {:#?} {:#?}", params, room_params);
+        .map_err(|err| MapGenError::RoomGenerationParamsError { err })?;
+    debug!(
+        "generating map with seed {} {:#?} {:#?}",
+        config.world_seed, params, room_params
+    );
 
     generate_full_map(
         &params,
         &room_params,
-        None,
+        Some(expand_seed(config.world_seed)),
         FromWorldMut::from_world_mut(world),
     )
     .await?;
@@ -114,3 +211,33 @@ async fn execute_map_generation(world: &mut World, config: &GameConfig) -> Resul
     debug!("world generation done");
     Ok(())
 }
+
+async fn execute_map_extension(
+    world: &mut World,
+    center: Axial,
+    old_radius: u32,
+    config: &GameConfig,
+) -> Result<MapExtension, MapGenError> {
+    let preset = &config.map_generation;
+    let params = preset
+        .overworld_params(config.world_radius, config.room_radius)
+        .map_err(|err| MapGenError::OverworldGenerationParamsError { err })?;
+    let room_params = preset
+        .room_params_builder(config.room_radius)
+        .with_world_seed(config.world_seed)
+        .build()
+        .map_err(|err| MapGenError::RoomGenerationParamsError { err })?;
+
+    // derive the seed from the radius so growing the same world the same way is reproducible
+    let seed = expand_seed(config.world_seed ^ u64::from(config.world_radius));
+
+    extend_full_map(
+        center,
+        old_radius,
+        &params,
+        &room_params,
+        seed,
+        FromWorldMut::from_world_mut(world),
+    )
+    .await
+}
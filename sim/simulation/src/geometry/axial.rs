@@ -36,14 +36,16 @@ impl Axial {
     /// Return the "Manhattan" distance between two points in a hexagonal coordinate space
     /// Interprets points as axial coordiantes
     /// See https://www.redblobgames.com/grids/hexagons/#distances for more information
+    ///
+    /// Computed in 64 bits, so any two points may be compared. Distances not fitting into 32
+    /// bits saturate to `u32::MAX`.
     #[inline]
     pub fn hex_distance(self, rhs: Axial) -> u32 {
-        let [ax, ay, az] = self.hex_axial_to_cube();
-        let [bx, by, bz] = rhs.hex_axial_to_cube();
-        let x = (ax - bx).abs();
-        let y = (ay - by).abs();
-        let z = (az - bz).abs();
-        x.max(y).max(z) as u32
+        let dq = self.q as i64 - rhs.q as i64;
+        let dr = self.r as i64 - rhs.r as i64;
+        let ds = -dq - dr;
+        let d = dq.abs().max(dr.abs()).max(ds.abs());
+        d.min(u32::MAX as i64) as u32
     }
 
     /// Convert self from a hexagonal axial vector to a hexagonal cube vector
@@ -251,6 +253,24 @@ mod tests {
         }
     }
 
+    #[test]
+    fn distance_of_extreme_points() {
+        let min = Axial::new(i32::MIN, i32::MIN);
+        let max = Axial::new(i32::MAX, i32::MAX);
+
+        assert_eq!(
+            Axial::new(i32::MAX, 0).hex_distance(Axial::new(0, 0)),
+            i32::MAX as u32
+        );
+        assert_eq!(
+            Axial::new(i32::MIN, i32::MAX).hex_distance(Axial::new(i32::MAX, i32::MIN)),
+            u32::MAX
+        );
+        // 2 * u32::MAX saturates
+        assert_eq!(min.hex_distance(max), u32::MAX);
+        assert_eq!(max.hex_distance(min), u32::MAX);
+    }
+
     #[test]
     fn neighbour_indices() {
         let p = Axial::new(13, 42);
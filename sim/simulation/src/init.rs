@@ -1,6 +1,6 @@
 use crate::prelude::*;
 use cao_lang::{compiler::CompileOptions, prelude::*};
-use rand::Rng;
+use rand::{rngs::SmallRng, seq::SliceRandom, Rng, SeedableRng};
 use tracing::{debug, trace};
 use uuid::Uuid;
 
@@ -8,9 +8,10 @@ use uuid::Uuid;
 pub fn init_world_entities(storage: &mut World, n_fake_users: usize) {
     debug!("initializing world");
 
-    let mut rng = rand::thread_rng();
+    let world_seed = UnwrapView::<ConfigKey, GameConfig>::from_world(storage).world_seed;
+    let mut rng = SmallRng::seed_from_u64(world_seed);
 
-    let mining_script_id = ScriptId(Uuid::new_v4());
+    let mining_script_id = ScriptId(random_uuid(&mut rng));
     let script: CaoIr = serde_yaml::from_str(include_str!("./programs/mining_program.yaml"))
         .expect("deserialize example program");
     debug!("compiling default program");
@@ -32,18 +33,16 @@ pub fn init_world_entities(storage: &mut World, n_fake_users: usize) {
     let config = UnwrapView::<ConfigKey, GameConfig>::from_world(storage);
 
     let radius = config.room_radius;
+    let resource_density = config.map_generation.resource_density;
     debug!("Reset position storage");
     let mut entities_by_pos = storage.unsafe_view::<WorldPosition, EntityComponent>();
     entities_by_pos.clear();
-    entities_by_pos
-        .table
-        .extend(
-            storage
-                .view::<Axial, RoomComponent>()
-                .iter()
-                .map(|(roomid, _)| (roomid, Default::default())),
-        )
-        .expect("entities_by_pos init");
+    entities_by_pos.table.extend(
+        storage
+            .view::<Axial, RoomComponent>()
+            .iter()
+            .map(|(roomid, _)| (roomid, Default::default())),
+    );
     let bounds = Hexagon {
         center: Axial::new(radius as i32, radius as i32),
         radius: radius as i32,
@@ -54,42 +53,239 @@ pub fn init_world_entities(storage: &mut World, n_fake_users: usize) {
         .map(|a| a.0)
         .collect::<Vec<_>>();
 
+    // users claim the controllers of their starting rooms
+    debug!("Placing room controllers");
+    for room in rooms.iter().copied() {
+        init_room_controller(Room(room), &bounds, &mut rng, storage);
+    }
+
     let mut taken_rooms = Vec::with_capacity(n_fake_users as usize);
     for i in 0..n_fake_users {
         trace!("initializing room #{}", i);
-        let spawnid = storage.insert_entity();
-
         let room = rng.gen_range(0..rooms.len());
         let room = rooms[room];
         taken_rooms.push(room);
 
         trace!("initializing room #{} in room {:?}", i, room);
-        let user_id = Uuid::new_v4();
-        init_spawn(&bounds, spawnid, user_id, Room(room), &mut rng, storage);
-        trace!("spawning entities");
+        let user_id = random_uuid(&mut rng);
+        init_user_room(UserId(user_id), Room(room), &bounds, &mut rng, storage);
         storage
             .unsafe_view::<UserId, EntityScript>()
             .insert(UserId(user_id), EntityScript(mining_script_id));
-        let id = storage.insert_entity();
-        let pos = uncontested_pos(
-            Room(room),
-            &bounds,
-            &*storage.view::<WorldPosition, EntityComponent>(),
-            &*storage.view::<WorldPosition, TerrainComponent>(),
-            &mut rng,
-        );
+        trace!("initializing room #{} done", i);
+    }
+
+    if resource_density > 0.0 {
+        debug!("Placing resources with base density {}", resource_density);
+        let biomes = storage
+            .view::<Axial, RoomComponent>()
+            .iter()
+            .map(|(room, room_component)| (room, room_component.biome))
+            .collect::<Vec<_>>();
+        for (room, biome) in biomes {
+            init_room_resources(
+                Room(room),
+                &bounds,
+                biome.resource_density(resource_density),
+                &mut rng,
+                storage,
+            );
+        }
+    }
+
+    debug!("init done");
+}
+
+/// Place the controller of `room`
+pub(crate) fn init_room_controller(
+    room: Room,
+    bounds: &Hexagon,
+    rng: &mut impl Rng,
+    world: &mut World,
+) {
+    let id = world.insert_entity();
+    let pos = uncontested_pos(
+        room,
+        bounds,
+        &*world.view::<WorldPosition, EntityComponent>(),
+        &*world.view::<WorldPosition, TerrainComponent>(),
+        rng,
+    );
+    crate::entity_archetypes::init_room_controller(id, pos, world);
+}
 
+/// Place a spawn of `user` and an energy resource in `room`, and claim the room if it's free
+pub(crate) fn init_user_room(
+    user: UserId,
+    room: Room,
+    bounds: &Hexagon,
+    rng: &mut impl Rng,
+    world: &mut World,
+) {
+    let spawnid = world.insert_entity();
+    init_spawn(bounds, spawnid, user.0, room, rng, world);
+    trace!("spawning entities");
+    let id = world.insert_entity();
+    let pos = uncontested_pos(
+        room,
+        bounds,
+        &*world.view::<WorldPosition, EntityComponent>(),
+        &*world.view::<WorldPosition, TerrainComponent>(),
+        rng,
+    );
+
+    crate::entity_archetypes::init_resource_energy(
+        id,
+        room,
+        pos,
+        FromWorldMut::from_world_mut(world),
+        FromWorld::from_world(world),
+    );
+
+    if world.view::<Axial, OwnedEntity>().contains_key(room.0) {
+        debug!("Starting room {:?} is owned already", room);
+    } else {
+        claim_room(user, room, world);
+    }
+}
+
+/// Make `user` the owner of `room` and claim the controller of the room, if any.
+/// Does not check if the room has an owner already.
+pub fn claim_room(user: UserId, room: Room, world: &mut World) {
+    world
+        .unsafe_view::<Axial, OwnedEntity>()
+        .insert(room.0, OwnedEntity { owner_id: user });
+    let mut rooms = world
+        .view::<UserId, Rooms>()
+        .get(user)
+        .cloned()
+        .unwrap_or_default();
+    rooms.0.push(room);
+    world.unsafe_view::<UserId, Rooms>().insert(user, rooms);
+
+    let controller = {
+        let positions = world.view::<EntityId, PositionComponent>();
+        world
+            .view::<EntityId, RoomControllerComponent>()
+            .iter()
+            .map(|(id, _)| id)
+            .find(|id| {
+                positions
+                    .get(*id)
+                    .map(|PositionComponent(pos)| pos.room == room.0)
+                    .unwrap_or(false)
+            })
+    };
+    if let Some(controller) = controller {
+        trace!("Claiming controller {:?}", controller);
+        world
+            .unsafe_view::<EntityId, OwnedEntity>()
+            .insert(controller, OwnedEntity { owner_id: user });
+        if let Some(c) = world
+            .unsafe_view::<EntityId, RoomControllerComponent>()
+            .get_mut(controller)
+        {
+            c.claim();
+        }
+    }
+}
+
+/// Compile `script` and set it as the script of every entity of `user`, including the bots
+/// spawned later
+pub fn set_user_script(
+    world: &mut World,
+    user: UserId,
+    script: CaoIr,
+) -> Result<ScriptId, CompilationError> {
+    let compiled = compile(&script, CompileOptions::new())?;
+    let script_id = ScriptId(Uuid::new_v4());
+    crate::query!(
+        mutate
+        world
+        {
+            ScriptId, CompiledScriptComponent,
+                .insert(script_id, CompiledScriptComponent(compiled));
+            ScriptId, CaoIrComponent,
+                .insert(script_id, CaoIrComponent(script));
+            UserId, EntityScript,
+                .insert(user, EntityScript(script_id));
+        }
+    );
+
+    let mut entity_scripts = world.unsafe_view::<EntityId, EntityScript>();
+    let owned = world.view::<EntityId, OwnedEntity>();
+    let join = crate::tables::JoinIterator::new(
+        owned.iter().filter(|(_, owner)| owner.owner_id == user),
+        entity_scripts.iter_mut(),
+    );
+    for (_, (_, entity_script)) in join {
+        entity_script.0 = script_id;
+    }
+    Ok(script_id)
+}
+
+fn init_room_resources(
+    room: Room,
+    bounds: &Hexagon,
+    resource_density: f32,
+    rng: &mut impl Rng,
+    world: &mut World,
+) {
+    trace!("init_room_resources {:?}", room);
+    let walkable = world
+        .view::<WorldPosition, TerrainComponent>()
+        .table
+        .at(room.0)
+        .map(|terrain| {
+            terrain
+                .iter()
+                .filter(|(pos, t)| t.0.is_walkable() && bounds.contains(*pos))
+                .map(|(pos, _)| pos)
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+    let n_resources = (walkable.len() as f32 * resource_density).round() as usize;
+    // pick from the free tiles, dense rooms may not have room for every resource
+    let mut free = {
+        let entities = world.view::<WorldPosition, EntityComponent>();
+        let room_entities = entities.table.at(room.0);
+        walkable
+            .into_iter()
+            .filter(|pos| {
+                room_entities
+                    .map(|entities| !entities.contains_key(*pos))
+                    .unwrap_or(true)
+            })
+            .collect::<Vec<_>>()
+    };
+    free.shuffle(rng);
+    if free.len() < n_resources {
+        debug!(
+            "Room {:?} has {} free tiles for {} resources",
+            room,
+            free.len(),
+            n_resources
+        );
+    }
+    for pos in free.into_iter().take(n_resources) {
+        let id = world.insert_entity();
         crate::entity_archetypes::init_resource_energy(
             id,
-            Room(room),
-            pos,
-            FromWorldMut::from_world_mut(storage),
-            FromWorld::from_world(storage),
+            room,
+            WorldPosition { room: room.0, pos },
+            FromWorldMut::from_world_mut(world),
+            FromWorld::from_world(world),
         );
-        trace!("initializing room #{} done", i);
     }
+    trace!("init_room_resources {:?} done", room);
+}
 
-    debug!("init done");
+/// Generate a v4 UUID using the given rng, so the ids are reproducible
+pub(crate) fn random_uuid(rng: &mut impl Rng) -> Uuid {
+    uuid::Builder::from_bytes(rng.gen())
+        .set_variant(uuid::Variant::RFC4122)
+        .set_version(uuid::Version::Random)
+        .build()
 }
 
 #[allow(clippy::too_many_arguments)] // its just a helper function let it be
@@ -166,7 +362,7 @@ mod tests {
 
     #[test]
     fn can_init_the_game() {
-        let mut exc = SimpleExecutor;
+        let mut exc = SimpleExecutor::default();
         let mut world =
             futures_lite::future::block_on(exc.initialize(crate::executor::GameConfig {
                 world_radius: 2,
@@ -177,4 +373,136 @@ mod tests {
         // smoke test: can the game be even initialized?
         init_world_entities(&mut world, 12);
     }
+
+    #[test]
+    fn resources_are_placed_by_density() {
+        let mut exc = SimpleExecutor::default();
+        let mut world =
+            futures_lite::future::block_on(exc.initialize(crate::executor::GameConfig {
+                world_radius: 2,
+                room_radius: 10,
+                map_generation: crate::map_generation::presets::MapGenerationPreset {
+                    resource_density: 0.05,
+                    ..Default::default()
+                },
+                ..Default::default()
+            }));
+
+        init_world_entities(&mut world, 0);
+
+        let n_resources = world.view::<EntityId, ResourceComponent>().iter().count();
+        assert!(n_resources > 0);
+    }
+
+    #[test]
+    fn dense_resources_are_capped_by_the_free_tiles() {
+        let mut exc = SimpleExecutor::default();
+        let mut world =
+            futures_lite::future::block_on(exc.initialize(crate::executor::GameConfig {
+                world_radius: 1,
+                room_radius: 6,
+                map_generation: crate::map_generation::presets::MapGenerationPreset {
+                    resource_density: 1.0,
+                    ..Default::default()
+                },
+                ..Default::default()
+            }));
+
+        init_world_entities(&mut world, 1);
+
+        let positions = world.view::<EntityId, PositionComponent>();
+        let resources = world
+            .view::<EntityId, ResourceComponent>()
+            .iter()
+            .map(|(id, _)| positions.get(id).unwrap().0)
+            .collect::<Vec<_>>();
+        let tiles = resources.iter().collect::<std::collections::HashSet<_>>();
+        assert!(!resources.is_empty());
+        assert_eq!(tiles.len(), resources.len(), "resources share a tile");
+    }
+
+    #[test]
+    fn resources_are_placed_by_biome() {
+        let count_resources = |biome| {
+            let mut exc = SimpleExecutor::default();
+            let mut world =
+                futures_lite::future::block_on(exc.initialize(crate::executor::GameConfig {
+                    world_radius: 2,
+                    room_radius: 10,
+                    world_seed: 0xdeadbeef,
+                    map_generation: crate::map_generation::presets::MapGenerationPreset {
+                        resource_density: 0.02,
+                        biome: Some(biome),
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                }));
+            init_world_entities(&mut world, 0);
+            world.view::<EntityId, ResourceComponent>().iter().count()
+        };
+
+        let desert = count_resources(crate::map_generation::biome::Biome::Desert);
+        let forest = count_resources(crate::map_generation::biome::Biome::Forest);
+        assert!(
+            desert < forest,
+            "expected forests to hold more resources than deserts {} {}",
+            desert,
+            forest
+        );
+    }
+
+    #[test]
+    fn same_seed_produces_the_same_world() {
+        let init = || {
+            let mut exc = SimpleExecutor::default();
+            let mut world =
+                futures_lite::future::block_on(exc.initialize(crate::executor::GameConfig {
+                    world_radius: 2,
+                    room_radius: 10,
+                    world_seed: 0xdeadbeef,
+                    ..Default::default()
+                }));
+            init_world_entities(&mut world, 4);
+            world
+        };
+
+        let a = init();
+        let b = init();
+
+        let rooms_a: Vec<_> = a
+            .view::<Axial, RoomComponent>()
+            .iter()
+            .map(|(id, r)| (id, *r))
+            .collect();
+        let rooms_b: Vec<_> = b
+            .view::<Axial, RoomComponent>()
+            .iter()
+            .map(|(id, r)| (id, *r))
+            .collect();
+        assert_eq!(rooms_a, rooms_b);
+
+        let terrain_a: Vec<_> = a
+            .view::<WorldPosition, TerrainComponent>()
+            .iter_rooms()
+            .flat_map(|(room, terrain)| terrain.iter().map(move |(pos, t)| (room, pos, t.0)))
+            .collect();
+        let terrain_b: Vec<_> = b
+            .view::<WorldPosition, TerrainComponent>()
+            .iter_rooms()
+            .flat_map(|(room, terrain)| terrain.iter().map(move |(pos, t)| (room, pos, t.0)))
+            .collect();
+        assert_eq!(terrain_a, terrain_b);
+
+        let positions_a: Vec<_> = a
+            .view::<EntityId, PositionComponent>()
+            .iter()
+            .map(|(id, p)| (id, *p))
+            .collect();
+        let positions_b: Vec<_> = b
+            .view::<EntityId, PositionComponent>()
+            .iter()
+            .map(|(id, p)| (id, *p))
+            .collect();
+        assert_eq!(positions_a, positions_b);
+    }
 }
@@ -1,6 +1,7 @@
 //! Actions, world updates the clients _intend_ to execute.
 //!
 mod attack_intent;
+mod controller_intent;
 mod dropoff_intent;
 mod log_intent;
 mod mine_intent;
@@ -9,6 +10,7 @@ mod pathcache_intent;
 mod spawn_intent;
 
 pub use self::attack_intent::*;
+pub use self::controller_intent::*;
 pub use self::dropoff_intent::*;
 pub use self::log_intent::*;
 pub use self::mine_intent::*;
@@ -107,4 +109,5 @@ intents!(
     script_history_intent: ScriptHistoryEntry,
     melee_attack_intent: MeleeIntent,
     say_intent: SayIntent,
+    upgrade_controller_intent: UpgradeControllerIntent,
 );
@@ -0,0 +1,104 @@
+use crate::components::{
+    Bot, CarryComponent, OwnedEntity, PositionComponent, RoomControllerComponent, Rooms,
+    UserProperties,
+};
+use crate::indices::{EntityId, UserId};
+use crate::scripting_api::OperationResult;
+use crate::storage::views::View;
+use crate::tables::traits::Table;
+use serde::{Deserialize, Serialize};
+use tracing::debug;
+
+pub const CONTROLLER_RANGE: u32 = 1;
+
+/// Transfer all carried energy of the bot into the controller. Claims the room if the
+/// controller has no owner.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct UpgradeControllerIntent {
+    pub bot: EntityId,
+    pub controller: EntityId,
+}
+
+type CheckInput<'a> = (
+    View<'a, EntityId, Bot>,
+    View<'a, EntityId, OwnedEntity>,
+    View<'a, EntityId, PositionComponent>,
+    View<'a, EntityId, CarryComponent>,
+    View<'a, EntityId, RoomControllerComponent>,
+    View<'a, UserId, Rooms>,
+    View<'a, UserId, UserProperties>,
+);
+
+/// A valid upgrade intent has the following characteristics:
+/// - the bot is owned by the user
+/// - the bot is carrying energy
+/// - the target is a controller within range
+/// - the controller is owned by the user, or has no owner and the user may own more rooms
+/// - the controller is not at max level
+pub fn check_upgrade_controller_intent(
+    intent: &UpgradeControllerIntent,
+    userid: UserId,
+    (bots, owners, positions, carry, controllers, user_rooms, user_props): CheckInput,
+) -> OperationResult {
+    let id = intent.bot;
+    match bots.get(id) {
+        Some(_) => {
+            let owner_id = owners.get(id);
+            if owner_id.map(|id| id.owner_id != userid).unwrap_or(true) {
+                return OperationResult::NotOwner;
+            }
+        }
+        None => return OperationResult::InvalidInput,
+    };
+
+    if carry.get(id).map(|carry| carry.carry == 0).unwrap_or(true) {
+        return OperationResult::Empty;
+    }
+
+    let target = intent.controller;
+    let controller = match controllers.get(target) {
+        Some(c) => c,
+        None => return OperationResult::InvalidTarget,
+    };
+
+    let nearby = positions.get(id).and_then(|botpos| {
+        positions.get(target).map(|targetpos| {
+            targetpos.0.room == botpos.0.room
+                && targetpos.0.pos.hex_distance(botpos.0.pos) <= CONTROLLER_RANGE
+        })
+    });
+    match nearby {
+        None => {
+            debug!("Bot or target has no position components {:?}", intent);
+            return OperationResult::InvalidInput;
+        }
+        Some(false) => return OperationResult::NotInRange,
+        Some(true) => {}
+    }
+
+    match owners.get(target) {
+        Some(owner) if owner.owner_id != userid => return OperationResult::NotOwner,
+        Some(_) => {}
+        None => {
+            if !can_claim_room(userid, user_rooms, user_props) {
+                return OperationResult::LimitReached;
+            }
+        }
+    }
+
+    if controller.is_max_level() {
+        return OperationResult::Full;
+    }
+    OperationResult::Ok
+}
+
+/// Users may own as many rooms as their level
+pub fn can_claim_room(
+    userid: UserId,
+    user_rooms: View<UserId, Rooms>,
+    user_props: View<UserId, UserProperties>,
+) -> bool {
+    let num_rooms = user_rooms.get(userid).map(|r| r.0.len()).unwrap_or(0);
+    let level = user_props.get(userid).cloned().unwrap_or_default().level;
+    num_rooms < level as usize
+}
@@ -1,3 +1,4 @@
+pub mod arena;
 pub mod components;
 pub mod entity_archetypes;
 pub mod executor;
@@ -8,6 +9,7 @@ pub mod map_generation;
 pub mod noise;
 pub mod pathfinding;
 pub mod prelude;
+pub mod scheduler;
 pub mod scripting_api;
 pub mod storage;
 pub mod tables;
@@ -29,4 +31,10 @@ impl<'a> storage::views::FromWorld<'a> for Time {
     fn from_world(w: &'a prelude::World) -> Self {
         Time(w.time())
     }
+
+    fn access(_: &mut storage::views::ViewAccess) {}
+}
+
+impl storage::views::FromWorldFamily for Time {
+    type View<'w> = Self;
 }
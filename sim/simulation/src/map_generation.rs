@@ -2,12 +2,21 @@
 //! - overworld: the large-scale overview of the map.
 //! - room: a self-contained slice of the map. Hexagon shaped.
 //!
+pub mod biome;
 pub mod overworld;
+pub mod presets;
 pub mod room;
 
-use self::overworld::{generate_room_layout, OverworldGenerationError, OverworldGenerationParams};
-use self::room::{generate_room, RoomGenerationError, RoomGenerationParams};
-use crate::storage::views::UnsafeView;
+use self::biome::Biome;
+use self::overworld::{
+    extend_room_layout, generate_room_layout, OverworldGenerationError, OverworldGenerationParams,
+    OverworldGenerationParamsError,
+};
+use self::room::{
+    add_room_edge, generate_room, RoomGenerationError, RoomGenerationParams,
+    RoomGenerationParamsError,
+};
+use crate::storage::views::{UnsafeView, View};
 use crate::{
     components::{RoomComponent, RoomConnections, RoomProperties, TerrainComponent},
     prelude::Axial,
@@ -31,6 +40,12 @@ pub enum MapGenError {
 
     #[error("Failed to generate overworld: {err}")]
     OverworldGenerationError { err: OverworldGenerationError },
+
+    #[error("Invalid room generation parameters: {err}")]
+    RoomGenerationParamsError { err: RoomGenerationParamsError },
+
+    #[error("Invalid overworld generation parameters: {err}")]
+    OverworldGenerationParamsError { err: OverworldGenerationParamsError },
 }
 
 pub type MapGenerationTables = (
@@ -40,11 +55,20 @@ pub type MapGenerationTables = (
     UnsafeView<Axial, RoomConnections>,
 );
 
+/// Expand a 64 bit seed into a seed usable by `generate_full_map`
+pub fn expand_seed(seed: u64) -> [u8; 32] {
+    let mut bytes = [0; 32];
+    SmallRng::seed_from_u64(seed).fill_bytes(&mut bytes);
+    bytes
+}
+
+/// The overworld layout and the seeds of the rooms are derived from `seed`. If `None` is passed a
+/// random seed is used.
 pub async fn generate_full_map(
     overworld_params: &OverworldGenerationParams,
     room_params: &RoomGenerationParams,
     seed: Option<[u8; 32]>,
-    (mut terrain, rooms, mut room_props, room_connections): MapGenerationTables,
+    (mut terrain, mut rooms, mut room_props, room_connections): MapGenerationTables,
 ) -> Result<(), MapGenError> {
     let seed = seed.unwrap_or_else(|| {
         let mut bytes = [0; 32];
@@ -55,8 +79,6 @@ pub async fn generate_full_map(
     generate_room_layout(overworld_params, &mut rng, (rooms, room_connections))
         .map_err(|err| MapGenError::OverworldGenerationError { err })?;
 
-    let radius = room_params.radius as usize;
-
     // setup properties table
     {
         use std::convert::TryInto;
@@ -68,56 +90,154 @@ pub async fn generate_full_map(
         });
     }
 
-    let terrain_tables = rooms
-        .iter()
+    for (room, room_component) in rooms.iter_mut() {
+        room_component.biome = room_biome(room_params, room);
+    }
+
+    let terrain_tables = generate_room_terrains(
+        rooms
+            .iter()
+            .map(|(room, room_component)| (room, *room_component)),
+        room_params,
+        View::from_table(&*room_connections),
+    )?;
+    terrain.table.extend(terrain_tables.into_iter());
+    Ok(())
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct MapExtension {
+    pub new_rooms: Vec<Axial>,
+    /// Existing rooms that received new bridges, each listed once
+    pub modified_rooms: Vec<Axial>,
+}
+
+/// Grow the overworld generated by [generate_full_map], centered on `center`, from `old_radius`
+/// to `overworld_params.radius`, generating the terrain of the new rooms and adding bridges to the
+/// existing rooms they connect to.
+///
+pub async fn extend_full_map(
+    center: Axial,
+    old_radius: u32,
+    overworld_params: &OverworldGenerationParams,
+    room_params: &RoomGenerationParams,
+    seed: [u8; 32],
+    (mut terrain, mut rooms, room_connections): (
+        UnsafeView<WorldPosition, TerrainComponent>,
+        UnsafeView<Axial, RoomComponent>,
+        UnsafeView<Axial, RoomConnections>,
+    ),
+) -> Result<MapExtension, MapGenError> {
+    let mut rng = SmallRng::from_seed(seed);
+    let layout = extend_room_layout(
+        center,
+        old_radius,
+        overworld_params,
+        &mut rng,
+        (rooms, room_connections),
+    )
+    .map_err(|err| MapGenError::OverworldGenerationError { err })?;
+
+    for room in layout.new_rooms.iter().copied() {
+        rooms
+            .at_mut(room)
+            .expect("Expected just built room to have a RoomComponent")
+            .biome = room_biome(room_params, room);
+    }
+
+    let terrain_tables = generate_room_terrains(
+        layout.new_rooms.iter().map(|room| {
+            let room_component = rooms
+                .at(*room)
+                .expect("Expected just built room to have a RoomComponent");
+            (*room, *room_component)
+        }),
+        room_params,
+        View::from_table(&*room_connections),
+    )?;
+
+    let mut modified_rooms = Vec::with_capacity(layout.new_edges.len());
+    for (room, edge) in layout.new_edges.iter() {
+        let room_terrain = terrain
+            .table
+            .at_mut(*room)
+            .expect("Expected existing rooms to have terrain");
+        add_room_edge(edge, &mut rng, (UnsafeView::from_table(room_terrain),)).map_err(|err| {
+            MapGenError::RoomGenerationError {
+                err,
+                room: Room(*room),
+            }
+        })?;
+        modified_rooms.push(*room);
+    }
+    modified_rooms.sort_unstable();
+    modified_rooms.dedup();
+
+    for (room, room_terrain) in terrain_tables {
+        terrain.table.insert(room, room_terrain);
+    }
+    Ok(MapExtension {
+        new_rooms: layout.new_rooms,
+        modified_rooms,
+    })
+}
+
+fn room_biome(room_params: &RoomGenerationParams, room: Axial) -> Biome {
+    room_params
+        .biome
+        .unwrap_or_else(|| Biome::of_room(room_params.world_seed, room))
+}
+
+/// Generate the terrain of the given rooms in parallel
+fn generate_room_terrains(
+    rooms: impl Iterator<Item = (Axial, RoomComponent)> + Send,
+    room_params: &RoomGenerationParams,
+    room_connections: View<Axial, RoomConnections>,
+) -> Result<Vec<(Axial, HexGrid<TerrainComponent>)>, MapGenError> {
+    let radius = room_params.radius as usize;
+    rooms
         .par_bridge()
-        .try_fold(
-            || Vec::with_capacity(rooms.len()),
-            |mut terrain_tables, (room, _)| {
-                let mut terrain_table = HexGrid::new(radius as usize);
-                let room_connections = room_connections
-                    .at(room)
-                    .expect("Expected just built room to have room_connections");
-                let room_connections = room_connections
-                    .0
-                    .iter()
-                    .filter_map(|c| c.as_ref())
-                    .cloned()
-                    .collect::<ArrayVec<_, 6>>();
-                let room_params = RoomGenerationParams {
-                    room: Room(room),
-                    ..room_params.clone()
-                };
-                let s = tracing::span!(
-                    tracing::Level::INFO,
-                    "generate_room",
-                    q = room.q,
-                    r = room.r
-                );
-                let _e = s.enter();
-                generate_room(
-                    &room_params,
-                    room_connections.as_slice(),
-                    (UnsafeView::from_table(&mut terrain_table),),
-                )
-                .map_err(|err| MapGenError::RoomGenerationError {
-                    err,
-                    room: Room(room),
-                })?;
-                terrain_tables.push((room, terrain_table));
-                Ok(terrain_tables)
-            },
-        )
+        .try_fold(Vec::new, |mut terrain_tables, (room, room_component)| {
+            let mut terrain_table = HexGrid::new(radius as usize);
+            let room_connections = room_connections
+                .at(room)
+                .expect("Expected just built room to have room_connections");
+            let room_connections = room_connections
+                .0
+                .iter()
+                .filter_map(|c| c.as_ref())
+                .cloned()
+                .collect::<ArrayVec<_, 6>>();
+            let room_params = RoomGenerationParams {
+                room: Room(room),
+                seed: room_component.seed,
+                biome: Some(room_component.biome),
+                ..room_params.clone()
+            };
+            let s = tracing::span!(
+                tracing::Level::INFO,
+                "generate_room",
+                q = room.q,
+                r = room.r
+            );
+            let _e = s.enter();
+            generate_room(
+                &room_params,
+                room_connections.as_slice(),
+                (UnsafeView::from_table(&mut terrain_table),),
+            )
+            .map_err(|err| MapGenError::RoomGenerationError {
+                err,
+                room: Room(room),
+            })?;
+            terrain_tables.push((room, terrain_table));
+            Ok(terrain_tables)
+        })
         .try_reduce(
             || Vec::with_capacity(10_000),
             |mut a, b| {
                 a.extend_from_slice(b.as_slice());
                 Ok(a)
             },
-        )?;
-    terrain
-        .table
-        .extend(terrain_tables.into_iter())
-        .expect("expected to be able to insert the room terrain tables");
-    Ok(())
+        )
 }
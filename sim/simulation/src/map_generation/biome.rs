@@ -0,0 +1,115 @@
+//! Terrain themes of generated rooms
+//!
+//! Biomes are assigned to rooms by a world-scale noise, so neighbouring rooms tend to share their
+//! biome.
+//!
+use serde::{Deserialize, Serialize};
+
+use crate::{geometry::Axial, noise::PerlinNoise};
+
+/// Size of the biome regions, in rooms
+const BIOME_SCALE: f32 = 8.0;
+/// Decorrelate the biome noise from the height map, which uses the same world seed
+const BIOME_SEED_SALT: u64 = 0x5eed_b10e;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Biome {
+    /// Use the tile probabilities as given
+    Temperate,
+    /// Open terrain, fewer walls
+    Desert,
+    /// More empty (impassable) tiles in place of walls
+    Swamp,
+    /// Dense terrain, more walls
+    Forest,
+}
+
+impl Default for Biome {
+    fn default() -> Self {
+        Biome::Temperate
+    }
+}
+
+impl Biome {
+    /// Biome of `room`, derived from `world_seed`
+    pub fn of_room(world_seed: u64, room: Axial) -> Self {
+        let noise = PerlinNoise::new(world_seed ^ BIOME_SEED_SALT);
+        Self::from_noise(noise.axial_perlin(room, BIOME_SCALE))
+    }
+
+    /// Map a noise value in the interval [-1, 1] to a biome
+    pub fn from_noise(value: f32) -> Self {
+        if value < -0.2 {
+            Biome::Desert
+        } else if value < 0.05 {
+            Biome::Temperate
+        } else if value < 0.25 {
+            Biome::Forest
+        } else {
+            Biome::Swamp
+        }
+    }
+
+    /// Adjust the resource density of the world to this biome.
+    ///
+    /// The result is clamped to [0, 1].
+    pub fn resource_density(self, resource_density: f32) -> f32 {
+        let multiplier = match self {
+            Biome::Temperate => 1.0,
+            Biome::Desert => 0.5,
+            Biome::Swamp => 1.5,
+            Biome::Forest => 2.0,
+        };
+        (resource_density * multiplier).max(0.0).min(1.0)
+    }
+
+    /// Adjust the tile probabilities of the height map transformation.
+    ///
+    /// Returns `[chance_plain, chance_wall]`. If the inputs are valid probabilities the outputs
+    /// will be as well.
+    pub fn tile_chances(self, chance_plain: f32, chance_wall: f32) -> [f32; 2] {
+        match self {
+            Biome::Temperate => [chance_plain, chance_wall],
+            Biome::Desert => [chance_plain + chance_wall * 0.3, chance_wall * 0.7],
+            Biome::Swamp => [chance_plain, chance_wall * 0.6],
+            Biome::Forest => [chance_plain * 0.7, chance_wall + chance_plain * 0.3],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn biomes_are_coherent_across_neighbouring_rooms() {
+        let world_seed = 0xdeadbeef;
+        let rooms = crate::geometry::Hexagon::from_radius(6)
+            .iter_points()
+            .collect::<Vec<_>>();
+
+        let mut same = 0;
+        let mut total = 0;
+        let mut biomes = std::collections::HashSet::new();
+        for room in rooms.iter().copied() {
+            let biome = Biome::of_room(world_seed, room);
+            biomes.insert(biome);
+            for n in room.hex_neighbours().iter().copied() {
+                total += 1;
+                if Biome::of_room(world_seed, n) == biome {
+                    same += 1;
+                }
+            }
+        }
+
+        assert!(biomes.len() > 1, "expected multiple biomes {:?}", biomes);
+        // 4 uniformly random biomes would put this around 1/4
+        assert!(
+            same * 5 > total * 2,
+            "expected most neighbours to share their biome: {}/{}",
+            same,
+            total
+        );
+    }
+}
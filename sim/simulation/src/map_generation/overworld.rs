@@ -7,7 +7,7 @@ use crate::components::{RoomComponent, RoomConnection, RoomConnections};
 use crate::geometry::{Axial, Hexagon};
 use crate::prelude::hex_round;
 use crate::storage::views::UnsafeView;
-use crate::tables::morton_table::{ExtendFailure, MortonTable};
+use crate::tables::morton_table::MortonTable;
 use rand::Rng;
 use thiserror::Error;
 use tracing::{debug, error};
@@ -16,12 +16,6 @@ use tracing::{debug, error};
 pub enum OverworldGenerationError {
     #[error("Can not place {number_of_rooms} rooms in an area with radius of {radius}")]
     BadRadius { number_of_rooms: u32, radius: u32 },
-
-    #[error("Failed to build Room table: {0:?}")]
-    ExtendFail(ExtendFailure),
-
-    #[error("Failed to build Room weight table: {0:?}")]
-    WeightMapInitFail(ExtendFailure),
 }
 
 /// Transform the room_id into absolute 'room tile' space
@@ -40,6 +34,13 @@ fn room_id_to_axial(room_id: Axial, grid_size: i32) -> Axial {
     hex_round([q as f32, r as f32])
 }
 
+/// Center room of an overworld generated by [generate_room_layout] with a radius of `radius`.
+/// Growing the overworld keeps the original center.
+pub fn overworld_center(radius: u32) -> Axial {
+    let radius = radius as i32;
+    Axial::new(radius, radius)
+}
+
 /// Insert the given number of rooms in the given radius (where the unit is a room).
 ///
 /// [ ] TODO: remove some nodes to produce less dense maps?
@@ -60,29 +61,26 @@ pub fn generate_room_layout(
         UnsafeView<Axial, RoomConnections>,
     ),
 ) -> Result<(), OverworldGenerationError> {
+    let center = overworld_center(*radius);
     let radius = *radius as i32;
     let room_radius = *room_radius as i32;
-    let center = Axial::new(radius, radius);
     let bounds = Hexagon { center, radius };
 
     // Init the grid
     rooms.clear();
-    rooms
-        .extend(bounds.iter_points().map(|room_id| {
-            (
-                room_id,
-                RoomComponent {
-                    offset: room_id_to_axial(room_id, room_radius),
-                    seed: rng.gen(),
-                },
-            )
-        }))
-        .map_err(OverworldGenerationError::ExtendFail)?;
+    rooms.extend(bounds.iter_points().map(|room_id| {
+        (
+            room_id,
+            RoomComponent {
+                offset: room_id_to_axial(room_id, room_radius),
+                seed: rng.gen(),
+                biome: Default::default(),
+            },
+        )
+    }));
 
     room_connections.clear();
-    room_connections
-        .extend(bounds.iter_points().map(|p| (p, Default::default())))
-        .map_err(OverworldGenerationError::ExtendFail)?;
+    room_connections.extend(bounds.iter_points().map(|p| (p, Default::default())));
 
     debug!("Building room_connections");
 
@@ -91,8 +89,7 @@ pub fn generate_room_layout(
         let weight = rng.gen_range(-4.0..=6.0);
         let weight = sigmoid(weight);
         (p, weight)
-    }))
-    .map_err(OverworldGenerationError::WeightMapInitFail)?;
+    }));
 
     for point in bounds.iter_points() {
         update_room_connections(
@@ -112,6 +109,115 @@ pub fn generate_room_layout(
     Ok(())
 }
 
+/// Rooms added by [extend_room_layout]
+#[derive(Debug, Clone, Default)]
+pub struct ExtendedLayout {
+    pub new_rooms: Vec<Axial>,
+    /// Connections added to rooms that existed before the extension
+    pub new_edges: Vec<(Axial, RoomConnection)>,
+}
+
+/// Add new rings of rooms to an overworld centered on `center`, with a radius of `old_radius`,
+/// growing it to `params.radius`.
+///
+/// The new rooms are connected to each other and to the previous outer ring.
+pub fn extend_room_layout(
+    center: Axial,
+    old_radius: u32,
+    OverworldGenerationParams {
+        radius,
+        room_radius,
+        min_bridge_len,
+        max_bridge_len,
+    }: &OverworldGenerationParams,
+    rng: &mut impl Rng,
+    (mut rooms, mut room_connections): (
+        UnsafeView<Axial, RoomComponent>,
+        UnsafeView<Axial, RoomConnections>,
+    ),
+) -> Result<ExtendedLayout, OverworldGenerationError> {
+    let old_radius = old_radius as i32;
+    let radius = *radius as i32;
+    let room_radius = *room_radius as i32;
+    let bounds = Hexagon { center, radius };
+
+    let new_rooms = bounds
+        .iter_points()
+        .filter(|p| p.hex_distance(center) as i32 > old_radius)
+        .collect::<Vec<_>>();
+    let outer_ring = Hexagon {
+        center,
+        radius: old_radius,
+    }
+    .iter_edge()
+    .filter(|p| room_connections.contains_key(*p))
+    .collect::<Vec<_>>();
+
+    debug!(
+        "Extending room layout from radius {} to {} with {} rooms",
+        old_radius,
+        radius,
+        new_rooms.len()
+    );
+
+    for room_id in new_rooms.iter().copied() {
+        rooms.insert(
+            room_id,
+            RoomComponent {
+                offset: room_id_to_axial(room_id, room_radius),
+                seed: rng.gen(),
+                biome: Default::default(),
+            },
+        );
+        room_connections.insert(room_id, Default::default());
+    }
+
+    let old_connections = outer_ring
+        .iter()
+        .map(|p| room_connections.at(*p).cloned().unwrap_or_default())
+        .collect::<Vec<_>>();
+
+    let connection_weights =
+        MortonTable::from_iterator(new_rooms.iter().chain(outer_ring.iter()).copied().map(|p| {
+            let weight = rng.gen_range(-4.0..=6.0);
+            let weight = sigmoid(weight);
+            (p, weight)
+        }));
+
+    for point in new_rooms.iter().copied() {
+        update_room_connections(
+            room_radius as u32,
+            *min_bridge_len,
+            *max_bridge_len,
+            point,
+            &connection_weights,
+            rng,
+            room_connections,
+        );
+    }
+
+    let mut new_edges = Vec::new();
+    for (room, RoomConnections(old)) in outer_ring.iter().copied().zip(old_connections) {
+        let RoomConnections(current) = room_connections
+            .at(room)
+            .expect("expected outer ring rooms to have connections");
+        for (old, current) in old.iter().zip(current.iter()) {
+            if let (None, Some(current)) = (old, current) {
+                new_edges.push((room, *current));
+            }
+        }
+    }
+    debug!(
+        "Extending room layout done, {} rooms were connected to the new ring",
+        new_edges.len()
+    );
+
+    Ok(ExtendedLayout {
+        new_rooms,
+        new_edges,
+    })
+}
+
 fn sigmoid(f: f32) -> f32 {
     1.0 / (1.0 + std::f32::consts::E.powf(-f))
 }
@@ -234,6 +340,119 @@ mod tests {
 
         assert_eq!(rooms.len(), room_connections.len());
 
+        assert_connections_are_valid(&room_connections);
+    }
+
+    #[test]
+    fn extended_overworld_connections_are_valid() {
+        let mut rooms = MortonTable::new();
+        let mut room_connections = MortonTable::new();
+
+        let params = OverworldGenerationParams::builder()
+            .with_radius(3)
+            .with_room_radius(16)
+            .with_min_bridge_len(3)
+            .with_max_bridge_len(12)
+            .build()
+            .unwrap();
+        let mut rng = rand::thread_rng();
+        generate_room_layout(
+            &params,
+            &mut rng,
+            (
+                UnsafeView::from_table(&mut rooms),
+                UnsafeView::from_table(&mut room_connections),
+            ),
+        )
+        .unwrap();
+        let n_rooms = rooms.len();
+
+        let params = OverworldGenerationParams {
+            radius: 4,
+            ..params
+        };
+        let layout = extend_room_layout(
+            overworld_center(3),
+            3,
+            &params,
+            &mut rng,
+            (
+                UnsafeView::from_table(&mut rooms),
+                UnsafeView::from_table(&mut room_connections),
+            ),
+        )
+        .unwrap();
+
+        assert!(!layout.new_rooms.is_empty());
+        assert_eq!(rooms.len(), n_rooms + layout.new_rooms.len());
+        assert_eq!(rooms.len(), room_connections.len());
+
+        assert_connections_are_valid(&room_connections);
+    }
+
+    #[test]
+    fn growing_repeatedly_adds_full_rings_around_the_original_center() {
+        let mut rooms = MortonTable::new();
+        let mut room_connections = MortonTable::new();
+
+        let params = OverworldGenerationParams::builder()
+            .with_radius(2)
+            .with_room_radius(16)
+            .with_min_bridge_len(3)
+            .with_max_bridge_len(12)
+            .build()
+            .unwrap();
+        let mut rng = rand::thread_rng();
+        generate_room_layout(
+            &params,
+            &mut rng,
+            (
+                UnsafeView::from_table(&mut rooms),
+                UnsafeView::from_table(&mut room_connections),
+            ),
+        )
+        .unwrap();
+        let center = overworld_center(2);
+
+        for radius in 3..=5 {
+            let params = OverworldGenerationParams {
+                radius,
+                ..params.clone()
+            };
+            let layout = extend_room_layout(
+                center,
+                radius - 1,
+                &params,
+                &mut rng,
+                (
+                    UnsafeView::from_table(&mut rooms),
+                    UnsafeView::from_table(&mut room_connections),
+                ),
+            )
+            .unwrap();
+
+            // a full ring of 6 * radius rooms
+            assert_eq!(layout.new_rooms.len(), 6 * radius as usize);
+            assert_eq!(rooms.len(), 1 + 3 * radius as usize * (radius as usize + 1));
+            for room in layout.new_rooms.iter().copied() {
+                assert_eq!(room.hex_distance(center), radius);
+                assert!(
+                    Axial::NEIGHBOURS
+                        .iter()
+                        .map(|n| room + *n)
+                        .any(|n| n.hex_distance(center) == radius - 1 && rooms.contains_key(n)),
+                    "room {:?} does not border the previous ring",
+                    room
+                );
+            }
+            for (room, _) in layout.new_edges.iter() {
+                assert_eq!(room.hex_distance(center), radius - 1);
+            }
+            assert_connections_are_valid(&room_connections);
+        }
+    }
+
+    fn assert_connections_are_valid(room_connections: &MortonTable<RoomConnections>) {
         // for each connection of the room test if the corresponding connection of the neighbour
         // is valid.
         for (room, RoomConnections(ref room_conn)) in room_connections.iter() {
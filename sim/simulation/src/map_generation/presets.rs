@@ -0,0 +1,201 @@
+//! Named map generation presets
+//!
+//! Presets are loaded from YAML files mapping preset names to [MapGenerationPreset]s:
+//!
+//! ```yaml
+//! default:
+//!     chancePlain: 0.13
+//!     chanceWall: 0.87
+//!     plainDilation: 2
+//! open:
+//!     chancePlain: 0.3
+//!     chanceWall: 0.5
+//!     biome: desert
+//!     resourceDensity: 0.01
+//! ```
+//!
+//! Missing fields are set to their default values.
+use std::{collections::HashMap, path::Path};
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use super::{
+    biome::Biome,
+    overworld::{
+        OverworldGenerationParams, OverworldGenerationParamsBuilder, OverworldGenerationParamsError,
+    },
+    room::{RoomGenerationParams, RoomGenerationParamsBuilder, RoomGenerationParamsError},
+};
+
+#[derive(Debug, Error)]
+pub enum PresetError {
+    #[error("Failed to read the preset file: {0}")]
+    Io(std::io::Error),
+
+    #[error("Failed to parse the preset file: {0}")]
+    Parse(serde_yaml::Error),
+
+    #[error("Preset {0} was not found")]
+    NotFound(String),
+
+    #[error("Preset {name} is invalid: {err}")]
+    Invalid {
+        name: String,
+        err: RoomGenerationParamsError,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct MapGenerationPreset {
+    pub min_bridge_len: u32,
+    /// Defaults to `room_radius - 3`
+    pub max_bridge_len: Option<u32>,
+    pub plain_dilation: u32,
+    pub chance_plain: f32,
+    pub chance_wall: f32,
+    /// Ratio of walkable tiles that hold a resource after world initialization
+    pub resource_density: f32,
+    /// Biome of every room. If not set the rooms' biomes are derived from the world seed
+    pub biome: Option<Biome>,
+}
+
+impl Default for MapGenerationPreset {
+    fn default() -> Self {
+        Self {
+            min_bridge_len: 3,
+            max_bridge_len: None,
+            plain_dilation: 2,
+            chance_plain: 0.13,
+            chance_wall: 1.0 - 0.13,
+            resource_density: 0.0,
+            biome: None,
+        }
+    }
+}
+
+impl MapGenerationPreset {
+    pub fn overworld_params_builder(
+        &self,
+        world_radius: u32,
+        room_radius: u32,
+    ) -> OverworldGenerationParamsBuilder {
+        OverworldGenerationParams::builder()
+            .with_radius(world_radius)
+            .with_room_radius(room_radius)
+            .with_min_bridge_len(self.min_bridge_len)
+            .with_max_bridge_len(
+                self.max_bridge_len
+                    .unwrap_or_else(|| room_radius.saturating_sub(3)),
+            )
+    }
+
+    pub fn room_params_builder(&self, room_radius: u32) -> RoomGenerationParamsBuilder {
+        RoomGenerationParams::builder()
+            .with_radius(room_radius)
+            .with_chance_plain(self.chance_plain)
+            .with_chance_wall(self.chance_wall)
+            .with_plain_dilation(self.plain_dilation)
+            .with_resource_density(self.resource_density)
+            .with_biome(self.biome)
+    }
+
+    pub fn overworld_params(
+        &self,
+        world_radius: u32,
+        room_radius: u32,
+    ) -> Result<OverworldGenerationParams, OverworldGenerationParamsError> {
+        self.overworld_params_builder(world_radius, room_radius)
+            .build()
+    }
+
+    pub fn room_params(
+        &self,
+        room_radius: u32,
+    ) -> Result<RoomGenerationParams, RoomGenerationParamsError> {
+        self.room_params_builder(room_radius).build()
+    }
+
+    /// Validate the room independent parameters
+    pub fn validate(&self) -> Result<(), RoomGenerationParamsError> {
+        RoomGenerationParams::builder()
+            .with_chance_plain(self.chance_plain)
+            .with_chance_wall(self.chance_wall)
+            .with_resource_density(self.resource_density)
+            .build()
+            .map(|_| ())
+    }
+}
+
+/// Parse and validate every preset in the input
+pub fn parse_presets(input: &str) -> Result<HashMap<String, MapGenerationPreset>, PresetError> {
+    let presets: HashMap<String, MapGenerationPreset> =
+        serde_yaml::from_str(input).map_err(PresetError::Parse)?;
+    for (name, preset) in presets.iter() {
+        preset.validate().map_err(|err| PresetError::Invalid {
+            name: name.clone(),
+            err,
+        })?;
+    }
+    Ok(presets)
+}
+
+/// Load the preset by the name `name` from the YAML file at `path`
+pub fn load_preset(path: impl AsRef<Path>, name: &str) -> Result<MapGenerationPreset, PresetError> {
+    let input = std::fs::read_to_string(path).map_err(PresetError::Io)?;
+    let mut presets = parse_presets(input.as_str())?;
+    presets
+        .remove(name)
+        .ok_or_else(|| PresetError::NotFound(name.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn can_parse_presets() {
+        let presets = parse_presets(
+            r#"
+default:
+    chancePlain: 0.13
+    chanceWall: 0.87
+    plainDilation: 2
+open:
+    chancePlain: 0.3
+    chanceWall: 0.5
+    biome: desert
+    resourceDensity: 0.01
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(presets.len(), 2);
+        let open = &presets["open"];
+        assert_eq!(open.biome, Some(Biome::Desert));
+        assert_eq!(open.min_bridge_len, 3);
+        open.room_params(12).unwrap();
+        open.overworld_params(4, 12).unwrap();
+    }
+
+    #[test]
+    fn invalid_presets_are_rejected() {
+        let err = parse_presets(
+            r#"
+bad:
+    chancePlain: 0.8
+    chanceWall: 0.8
+"#,
+        )
+        .unwrap_err();
+
+        assert!(matches!(
+            err,
+            PresetError::Invalid {
+                err: RoomGenerationParamsError::BadProbabilities { .. },
+                ..
+            }
+        ));
+    }
+}
@@ -102,7 +102,7 @@ pub fn generate_room(
     let mut max_grad = -1e15f32;
 
     trace!("Generating heightmap");
-    let noise = PerlinNoise::new(params.seed);
+    let noise = PerlinNoise::new(params.world_seed);
     for pos in Hexagon::from_radius(dsides).iter_points() {
         let grad = noise.world_perlin(WorldPosition { pos, room: room.0 }, radius as f32);
         gradient.insert(pos, grad).unwrap();
@@ -111,14 +111,18 @@ pub fn generate_room(
     }
     trace!("Generating heightmap done");
 
+    let [chance_plain, chance_wall] = params
+        .biome
+        .unwrap_or_default()
+        .tile_chances(params.chance_plain, params.chance_wall);
     let heightmap_props = transform_heightmap_into_terrain(
         HeightMapTransformParams {
             max_grad,
             min_grad,
             dsides,
             radius: radius - 1,
-            chance_plain: params.chance_plain,
-            chance_wall: params.chance_wall,
+            chance_plain,
+            chance_wall,
         },
         &gradient,
         terrain,
@@ -164,6 +168,47 @@ pub fn generate_room(
     Ok(heightmap_props)
 }
 
+/// Add a new bridge to an already generated room.
+///
+/// Only turns tiles walkable, so entities already in the room remain on walkable tiles.
+pub fn add_room_edge(
+    edge: &RoomConnection,
+    rng: &mut impl Rng,
+    (terrain,): MapTables,
+) -> Result<(), RoomGenerationError> {
+    trace!("Adding edge {:?}", edge);
+    let Hexagon { center, radius } = terrain.bounds();
+    let chunk_metadata = calculate_plain_chunks(View::from_table(&*terrain));
+    let mainland = chunk_metadata.chunks.into_iter().next().ok_or_else(|| {
+        error!("Expected at least 1 chunk when adding an edge, intead got 0",);
+        RoomGenerationError::ExpectedSingleChunk(0)
+    })?;
+
+    let mut inner_edge = *edge;
+    // offset - 1 but at least 0
+    inner_edge.offset_start = 1.max(inner_edge.offset_start) - 1;
+    inner_edge.offset_end = 1.max(inner_edge.offset_end) - 1;
+    let mut edge_chunk = HashSet::with_capacity(radius as usize);
+    fill_edge(
+        center,
+        radius - 1,
+        TileTerrainType::Plain,
+        &inner_edge,
+        terrain,
+        &mut edge_chunk,
+    )?;
+    connect_chunks(&radius - 2, rng, &[mainland, edge_chunk], terrain);
+    fill_edge(
+        center,
+        radius,
+        TileTerrainType::Bridge,
+        edge,
+        terrain,
+        &mut HashSet::new(),
+    )?;
+    Ok(())
+}
+
 fn erase_lone_walls(mut terrain: UnsafeView<Axial, TerrainComponent>) {
     let mut to_remove = smallvec::SmallVec::<[Axial; 64]>::new();
     for pos in terrain
@@ -715,6 +760,7 @@ mod tests {
                 *b,
                 0,
                 (View::from_table(&positions), View::from_table(&terrain)),
+                &Default::default(),
                 10240,
                 &mut path,
             ) {
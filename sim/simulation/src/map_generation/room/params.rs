@@ -1,6 +1,7 @@
 use thiserror::Error;
 
 use crate::indices::Room;
+use crate::map_generation::biome::Biome;
 
 #[derive(Debug, Clone, Error)]
 pub enum RoomGenerationParamsError {
@@ -9,16 +10,26 @@ pub enum RoomGenerationParamsError {
 
     #[error("Radius must be at least 4, got {radius}")]
     BadRadius { radius: u32 },
+
+    #[error("Resource density must be in interval [0, 1], got {resource_density}")]
+    BadResourceDensity { resource_density: f32 },
 }
 
 #[derive(Debug, Clone)]
 pub struct RoomGenerationParams {
+    /// Seed of the room's random number generator
     pub seed: u64,
+    /// Seed of the world-scale noise. Shared by all rooms, so neighbouring rooms are coherent
+    pub world_seed: u64,
     pub room: Room,
     pub radius: u32,
     pub plain_dilation: u32,
     pub chance_plain: f32,
     pub chance_wall: f32,
+    /// Ratio of walkable tiles that hold a resource after world initialization
+    pub resource_density: f32,
+    /// Biome of every room. If `None` the biome of each room is derived from the `world_seed`
+    pub biome: Option<Biome>,
 }
 
 #[derive(Debug, Clone, Default)]
@@ -27,7 +38,10 @@ pub struct RoomGenerationParamsBuilder {
     pub plain_dilation: u32,
     pub chance_plain: f32,
     pub chance_wall: f32,
+    pub resource_density: f32,
+    pub biome: Option<Biome>,
     pub seed: u64,
+    pub world_seed: u64,
     pub room: Room,
 }
 
@@ -39,6 +53,7 @@ impl RoomGenerationParams {
             chance_plain: 1.0 / 3.0,
             chance_wall: 1.0 / 3.0,
             seed: 0xb00b135,
+            world_seed: 0xb00b135,
             ..Default::default()
         }
     }
@@ -63,13 +78,21 @@ impl RoomGenerationParamsBuilder {
                 radius: self.radius,
             });
         }
+        if !(0.0..=1.0).contains(&self.resource_density) {
+            return Err(RoomGenerationParamsError::BadResourceDensity {
+                resource_density: self.resource_density,
+            });
+        }
         Ok(RoomGenerationParams {
             seed: self.seed,
+            world_seed: self.world_seed,
             room: self.room,
             radius: self.radius,
             plain_dilation: self.plain_dilation,
             chance_plain: self.chance_plain,
             chance_wall: self.chance_wall,
+            resource_density: self.resource_density,
+            biome: self.biome,
         })
     }
 
@@ -78,6 +101,11 @@ impl RoomGenerationParamsBuilder {
         self
     }
 
+    pub fn with_world_seed(mut self, world_seed: u64) -> Self {
+        self.world_seed = world_seed;
+        self
+    }
+
     pub fn with_room(mut self, room_id: crate::prelude::Axial) -> Self {
         self.room = Room(room_id);
         self
@@ -102,4 +130,14 @@ impl RoomGenerationParamsBuilder {
         self.chance_wall = chance_wall;
         self
     }
+
+    pub fn with_resource_density(mut self, resource_density: f32) -> Self {
+        self.resource_density = resource_density;
+        self
+    }
+
+    pub fn with_biome(mut self, biome: impl Into<Option<Biome>>) -> Self {
+        self.biome = biome.into();
+        self
+    }
 }
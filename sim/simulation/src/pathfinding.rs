@@ -20,7 +20,7 @@ use std::collections::HashMap;
 use thiserror::Error;
 use tracing::{debug, error, trace, warn};
 
-use self::pathfinding_room::find_path_in_room;
+use self::pathfinding_room::{find_path_in_room, flee_path_in_room};
 
 const MAX_BRIDGE_LEN: usize = 64;
 
@@ -75,6 +75,53 @@ pub enum PathFindingError {
     EdgeNotExists(Axial),
 }
 
+/// Tunables of the path finding algorithms
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PathOptions {
+    /// Treat tiles occupied by entities as walkable.
+    pub ignore_entities: bool,
+    /// Additional cost of stepping onto a tile occupied by an entity.
+    /// Only used if `ignore_entities` is set.
+    ///
+    /// Use this to avoid other bots while still allowing paths through them if there's no other
+    /// way.
+    pub entity_cost: u32,
+    /// Override the cost of stepping onto a tile. The default cost is 1.
+    /// Tiles with a cost of 0 are impassable.
+    ///
+    /// Only applies to the room the path starts in.
+    pub costs: HashMap<Axial, u32>,
+    /// Maximum number of iterations of the path finding algorithms
+    pub max_ops: u32,
+    /// Maximum number of rooms a path may visit, including the starting room
+    pub max_rooms: u32,
+    /// If not empty, find a path away from these room positions, instead of approaching the
+    /// target.
+    pub flee_from: Vec<Axial>,
+    /// Distance to keep from every position in `flee_from`
+    pub flee_range: u32,
+}
+
+impl Default for PathOptions {
+    fn default() -> Self {
+        Self {
+            ignore_entities: false,
+            entity_cost: 0,
+            costs: HashMap::new(),
+            max_ops: 1000,
+            max_rooms: 16,
+            flee_from: Vec::new(),
+            flee_range: 0,
+        }
+    }
+}
+
+impl PathOptions {
+    pub fn is_flee(&self) -> bool {
+        !self.flee_from.is_empty()
+    }
+}
+
 type FindPathTables<'a> = (
     View<'a, WorldPosition, EntityComponent>,
     View<'a, WorldPosition, TerrainComponent>,
@@ -86,13 +133,17 @@ type FindPathTables<'a> = (
 /// The output' path is in reverse order. Pop the elements to walk the path.
 /// This is a performance consideration, as most callers should not need to reverse the order of
 /// elements.
+///
+/// In flee mode (see [PathOptions::flee_from]) `to` and `distance` are ignored and the path is
+/// confined to the room of `from`.
+///
 /// Returns the remaining steps
 pub fn find_path(
     from: WorldPosition,
     to: WorldPosition,
     distance: u32,
     (positions, terrain, room_connections, room_properties): FindPathTables,
-    max_steps: u32,
+    options: &PathOptions,
     path: &mut Vec<RoomPosition>,
     next_room: &mut Option<Room>,
 ) -> Result<u32, PathFindingError> {
@@ -106,13 +157,22 @@ pub fn find_path(
         warn!("Room of TerrainComponents not found");
         PathFindingError::RoomNotExists(from.room)
     })?);
-    if from.room == to.room {
+    if options.is_flee() {
+        flee_path_in_room(
+            from.pos,
+            (positions, terrain),
+            options,
+            options.max_ops,
+            path,
+        )
+    } else if from.room == to.room {
         find_path_in_room(
             from.pos,
             to.pos,
             distance,
             (positions, terrain),
-            max_steps,
+            options,
+            options.max_ops,
             path,
         )
     } else {
@@ -121,7 +181,7 @@ pub fn find_path(
             to,
             distance,
             (positions, terrain, room_connections, room_properties),
-            max_steps,
+            options,
             path,
             next_room,
         )
@@ -140,18 +200,19 @@ fn find_path_multiroom(
     to: WorldPosition,
     distance: u32,
     (positions, terrain, room_connections, room_properties): FindPathMultiRoomTables,
-    mut max_steps: u32,
+    options: &PathOptions,
     path: &mut Vec<RoomPosition>,
     next_room: &mut Option<Room>,
 ) -> Result<u32, PathFindingError> {
     trace!("find_path_multiroom from {:?} to {:?}", from, to);
 
     let from_room = from.room;
-    max_steps = find_path_overworld(
+    let mut max_steps = find_path_overworld(
         Room(from_room),
         Room(to.room),
         room_connections,
-        max_steps,
+        options.max_ops,
+        options.max_rooms,
         next_room,
     )
     .map_err(|err| {
@@ -189,7 +250,7 @@ fn find_path_multiroom(
                 is_bot_on_bridge = is_bot_on_bridge || pos == from.pos;
                 pos
             })
-            .filter(|p| !positions.contains_key(*p)) // consider only empty spots
+            .filter(|p| options.ignore_entities || !positions.contains_key(*p)) // consider only empty spots
             .take(MAX_BRIDGE_LEN)
             .collect::<ArrayVec<_, MAX_BRIDGE_LEN>>()
     };
@@ -206,6 +267,7 @@ fn find_path_multiroom(
             point,
             distance,
             (positions, terrain),
+            options,
             max_steps,
             path,
         ) {
@@ -227,12 +289,14 @@ fn find_path_multiroom(
 
 /// find the rooms one has to visit to go from room `from` to room `to`
 /// uses the A* algorithm
+/// paths visiting more than `max_rooms` rooms (including `from`) are rejected
 /// return the remaning iterations
 pub fn find_path_overworld(
     Room(from): Room,
     Room(to): Room,
     room_connections: View<Axial, RoomConnections>,
     mut max_steps: u32,
+    max_rooms: u32,
     next_room: &mut Option<Room>,
 ) -> Result<u32, PathFindingError> {
     profile!("find_path_overworld");
@@ -250,6 +314,10 @@ pub fn find_path_overworld(
         current = open_set.pop().unwrap();
         closed_set.insert(current.pos, current.clone());
         let current_pos = current.pos;
+        if current.g_cost as u32 + 1 >= max_rooms {
+            // the neighbours would exceed the room limit
+            continue;
+        }
         // [0, 6] items
         for neighbour in room_connections
             .at(current_pos)
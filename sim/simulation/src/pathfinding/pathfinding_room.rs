@@ -9,12 +9,37 @@ use crate::{
 };
 use tracing::{debug, trace};
 
-use super::{is_walkable, Node, PathFindingError};
+use super::{is_walkable, Node, PathFindingError, PathOptions};
 
 const VISITED_FROM: u8 = 1 << 0;
 const VISITED_TO: u8 = 1 << 1;
 type Bounds = [Axial; 2];
 
+/// Cost of stepping onto `point`. Returns `None` if the point is impassable.
+#[inline]
+fn step_cost(
+    point: Axial,
+    entities: View<Axial, EntityComponent>,
+    terrain: View<Axial, TerrainComponent>,
+    options: &PathOptions,
+) -> Option<i32> {
+    if !is_walkable(point, terrain) {
+        return None;
+    }
+    let mut cost = match options.costs.get(&point) {
+        Some(0) => return None,
+        Some(cost) => *cost as i32,
+        None => 1,
+    };
+    if entities.contains_key(point) {
+        if !options.ignore_entities {
+            return None;
+        }
+        cost += options.entity_cost as i32;
+    }
+    Some(cost)
+}
+
 /// The goal of the pathfinder to approach `end` at a distance of `distance`.
 ///
 /// So we'll initialize a ring of nodes with the center `end` and radius `distance`.
@@ -23,6 +48,7 @@ fn init_end(
     distance: u32,
     entities: View<Axial, EntityComponent>,
     terrain: View<Axial, TerrainComponent>,
+    options: &PathOptions,
     open_set: &mut BinaryHeap<Node>,
     visited: &mut HexGrid<u8>,
     closed_set: &mut HexGrid<Node>,
@@ -38,13 +64,10 @@ fn init_end(
         }
     } else {
         let bounds = Hexagon::new(end, distance as i32);
-        for pos in bounds.iter_edge().filter(|pos| {
-            terrain
-                .at(*pos)
-                .map(|TerrainComponent(t)| t.is_walkable())
-                .unwrap_or(false)
-                && !entities.contains_key(*pos)
-        }) {
+        for pos in bounds
+            .iter_edge()
+            .filter(|pos| step_cost(*pos, entities, terrain, options).is_some())
+        {
             debug_assert_eq!(pos.hex_distance(end), distance);
             if let Some(v) = visited.at_mut(pos) {
                 *v |= VISITED_TO;
@@ -100,6 +123,7 @@ pub fn find_path_in_room(
     to: Axial,
     distance: u32,
     (positions, terrain): (View<Axial, EntityComponent>, View<Axial, TerrainComponent>),
+    options: &PathOptions,
     max_steps: u32,
     path: &mut Vec<RoomPosition>,
 ) -> Result<u32, PathFindingError> {
@@ -130,6 +154,7 @@ pub fn find_path_in_room(
         distance,
         positions,
         terrain,
+        options,
         &mut open_set_t,
         &mut open_set_visited,
         &mut closed_set_t,
@@ -169,8 +194,6 @@ pub fn find_path_in_room(
             for point in &current_f.pos.hex_neighbours() {
                 let point = *point;
                 if open_set_visited.at(point).copied().unwrap_or(VISITED_FROM) & VISITED_FROM != 0
-                    || positions.contains_key(point)
-                    || !is_walkable(point, terrain)
                     || closed_set_f
                         .at(point)
                         .map(|node| node.g_cost != 0)
@@ -178,12 +201,16 @@ pub fn find_path_in_room(
                 {
                     continue;
                 }
+                let cost = match step_cost(point, positions, terrain, options) {
+                    Some(cost) => cost,
+                    None => continue,
+                };
                 open_set_visited[point] |= VISITED_FROM;
                 let node = Node::new(
                     point,
                     current_f.pos,
                     point.hex_distance(end) as i32,
-                    current_f.g_cost + 1,
+                    current_f.g_cost + cost,
                 );
                 open_set_f.push(node);
             }
@@ -216,8 +243,6 @@ pub fn find_path_in_room(
                 let point = *point;
                 if point.hex_distance(end) <= distance
                     || open_set_visited.at(point).copied().unwrap_or(VISITED_TO) & VISITED_TO != 0
-                    || !is_walkable(point, terrain)
-                    || positions.contains_key(point)
                     || closed_set_t
                         .at(point)
                         .map(|node| node.g_cost != 0)
@@ -225,12 +250,16 @@ pub fn find_path_in_room(
                 {
                     continue;
                 }
+                let cost = match step_cost(point, positions, terrain, options) {
+                    Some(cost) => cost,
+                    None => continue,
+                };
                 open_set_visited[point] |= VISITED_TO;
                 let node = Node::new(
                     point,
                     current_t.pos,
                     point.hex_distance(from) as i32,
-                    current_t.g_cost + 1,
+                    current_t.g_cost + cost,
                 );
                 open_set_t.push(node);
             }
@@ -250,3 +279,97 @@ pub fn find_path_in_room(
     }
     Err(PathFindingError::Timeout)
 }
+
+/// Find a path leading away from `options.flee_from`. The path ends at the first position that is
+/// at least `options.flee_range` away from all of them.
+///
+/// Returns the remaining steps.
+pub fn flee_path_in_room(
+    from: Axial,
+    (positions, terrain): (View<Axial, EntityComponent>, View<Axial, TerrainComponent>),
+    options: &PathOptions,
+    max_steps: u32,
+    path: &mut Vec<RoomPosition>,
+) -> Result<u32, PathFindingError> {
+    profile!("flee_path_in_room");
+    trace!("flee_path_in_room from {:?} {:?}", from, options.flee_from);
+
+    let range = options.flee_range as i32;
+    // how many more steps do we need to reach a safe position
+    let danger = |pos: Axial| {
+        options
+            .flee_from
+            .iter()
+            .map(|p| range - p.hex_distance(pos) as i32)
+            .max()
+            .unwrap_or(0)
+            .max(0)
+    };
+
+    if danger(from) == 0 {
+        return Ok(max_steps);
+    }
+
+    let mut remaining_steps = max_steps;
+
+    let room_radius = terrain.bounds().radius;
+    debug_assert!(room_radius >= 0);
+
+    let mut closed_set = HexGrid::<Node>::new(room_radius as usize);
+    let mut visited = HexGrid::<bool>::new(room_radius as usize);
+    let mut open_set = BinaryHeap::with_capacity(remaining_steps as usize);
+
+    if let Some(v) = visited.at_mut(from) {
+        *v = true;
+    }
+    open_set.push(Node::new(from, from, danger(from), 0));
+
+    while remaining_steps > 0 {
+        let current = match open_set.pop() {
+            Some(n) => n,
+            None => break,
+        };
+        remaining_steps -= 1;
+        closed_set[current.pos] = current.clone();
+        if current.h_cost == 0 {
+            let mut pos = current.pos;
+            while pos != from {
+                path.push(RoomPosition(pos));
+                pos = closed_set[pos].parent;
+            }
+            debug!(
+                "flee_path_in_room succeeded, steps taken: {} remaining_steps: {}",
+                max_steps - remaining_steps,
+                remaining_steps,
+            );
+            return Ok(remaining_steps);
+        }
+        for point in &current.pos.hex_neighbours() {
+            let point = *point;
+            if visited.at(point).copied().unwrap_or(true) {
+                continue;
+            }
+            let cost = match step_cost(point, positions, terrain, options) {
+                Some(cost) => cost,
+                None => continue,
+            };
+            visited[point] = true;
+            open_set.push(Node::new(
+                point,
+                current.pos,
+                danger(point),
+                current.g_cost + cost,
+            ));
+        }
+    }
+
+    debug!(
+        "flee_path_in_room failed, steps taken: {} remaining_steps: {}",
+        max_steps - remaining_steps,
+        remaining_steps
+    );
+    if remaining_steps > 0 {
+        return Err(PathFindingError::Unreachable);
+    }
+    Err(PathFindingError::Timeout)
+}
@@ -1,4 +1,4 @@
-use super::pathfinding_room::find_path_in_room;
+use super::pathfinding_room::{find_path_in_room, flee_path_in_room};
 use super::*;
 use crate::{
     prelude::Hexagon,
@@ -36,6 +36,7 @@ fn test_simple_wall() {
         to,
         0,
         (View::from_table(&positions), View::from_table(&terrain)),
+        &PathOptions::default(),
         512,
         &mut path,
     )
@@ -72,6 +73,7 @@ fn test_path_is_continous() {
         to,
         0,
         (View::from_table(&positions), View::from_table(&terrain)),
+        &PathOptions::default(),
         512,
         &mut path,
     )
@@ -108,6 +110,7 @@ fn test_pathfinding_at_distance() {
         to,
         2,
         (View::from_table(&positions), View::from_table(&terrain)),
+        &PathOptions::default(),
         512,
         &mut path,
     )
@@ -125,3 +128,88 @@ fn test_pathfinding_at_distance() {
     }
     assert_eq!(current.hex_distance(to), 2);
 }
+
+#[test]
+fn test_impassable_cost_override() {
+    let from = Axial::new(2, 1);
+    let to = Axial::new(5, 2);
+
+    let positions = MortonTable::new();
+    let mut terrain = HexGrid::new(3);
+    terrain.iter_mut().for_each(|(_, t)| {
+        *t = TerrainComponent(TileTerrainType::Plain);
+    });
+
+    // same layout as `test_simple_wall`, but the wall is provided via the options
+    let options = PathOptions {
+        costs: Hexagon::from_radius(3)
+            .iter_points()
+            .filter(|p| p.q == 3 && p.r <= 4)
+            .map(|p| (p, 0))
+            .collect(),
+        ..Default::default()
+    };
+
+    let mut path = vec![];
+    find_path_in_room(
+        from,
+        to,
+        0,
+        (View::from_table(&positions), View::from_table(&terrain)),
+        &options,
+        512,
+        &mut path,
+    )
+    .expect("Path finding failed");
+    path.reverse();
+
+    let mut current = from;
+    for point in path.iter() {
+        let point = point.0;
+        assert_eq!(point.hex_distance(current), 1);
+        if point.q == 3 {
+            assert!(point.r > 4, "{:?}", point);
+        }
+        current = point;
+    }
+    assert_eq!(current, to);
+}
+
+#[test]
+fn test_flee() {
+    let from = Axial::new(12, 12);
+    let enemy = Axial::new(13, 12);
+
+    let positions = MortonTable::new();
+    let mut terrain = HexGrid::new(12);
+    terrain.iter_mut().for_each(|(_, t)| {
+        *t = TerrainComponent(TileTerrainType::Plain);
+    });
+
+    let options = PathOptions {
+        flee_from: vec![enemy],
+        flee_range: 5,
+        ..Default::default()
+    };
+
+    let mut path = vec![];
+    flee_path_in_room(
+        from,
+        (View::from_table(&positions), View::from_table(&terrain)),
+        &options,
+        512,
+        &mut path,
+    )
+    .expect("Path finding failed");
+
+    assert!(!path.is_empty());
+    let end = path[0].0;
+    assert!(end.hex_distance(enemy) >= 5, "{:?}", end);
+
+    path.reverse();
+    let mut current = from;
+    for point in path.iter() {
+        assert_eq!(point.0.hex_distance(current), 1);
+        current = point.0;
+    }
+}
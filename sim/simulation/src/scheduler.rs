@@ -0,0 +1,509 @@
+//! Run systems in parallel where their data dependencies allow it
+//!
+//! The tables a system accesses are derived from its `(Mut, Const)` view tuples. Systems that
+//! conflict (one writes a table the other accesses) run in the order they were added, others may
+//! run in parallel. Additional ordering constraints can be declared via
+//! [SystemDescriptor::after] and [SystemDescriptor::before]. These must not contradict the order
+//! of conflicting systems.
+//!
+//! ```
+//! use caolo_sim::prelude::*;
+//! use caolo_sim::scheduler::{Scheduler, SystemDescriptor};
+//!
+//! fn regen(
+//!     (mut energy,): (UnsafeView<EntityId, EnergyComponent>,),
+//!     (regen,): (View<EntityId, EnergyRegenComponent>,),
+//! ) {
+//!     // do stuff
+//! }
+//!
+//! fn decay((mut hp,): (UnsafeView<EntityId, HpComponent>,), (): ()) {
+//!     // do stuff
+//! }
+//!
+//! let mut scheduler = Scheduler::default();
+//! scheduler
+//!     .add_system(SystemDescriptor::new("regen", regen))
+//!     .add_system(SystemDescriptor::new("decay", decay).after("regen"));
+//! scheduler.build().unwrap();
+//!
+//! let mut exc = SimpleExecutor::default();
+//! let mut world = futures_lite::future::block_on(exc.initialize(Default::default()));
+//! scheduler.run(&mut world);
+//! ```
+use std::ptr::NonNull;
+
+use rayon::prelude::*;
+use thiserror::Error;
+use tracing::trace;
+
+use crate::prelude::World;
+use crate::profile;
+use crate::storage::borrow_tracker::BorrowTracker;
+use crate::storage::views::{
+    FromWorld, FromWorldFamily, FromWorldMut, FromWorldMutFamily, ViewAccess,
+};
+
+#[derive(Debug, Clone, Error)]
+pub enum SchedulerError {
+    #[error("System {0} was added more than once")]
+    DuplicateSystem(&'static str),
+    #[error("System {system} is ordered relative to {other}, which was not added")]
+    UnknownSystem {
+        system: &'static str,
+        other: &'static str,
+    },
+    #[error("The ordering of systems {0:?} is cyclic")]
+    Cycle(Vec<&'static str>),
+}
+
+#[derive(Clone, Copy)]
+struct WorldPtr(NonNull<World>);
+
+unsafe impl Send for WorldPtr {}
+unsafe impl Sync for WorldPtr {}
+
+type RunFn = Box<dyn Fn(WorldPtr, &BorrowTracker) + Send + Sync>;
+
+pub struct SystemDescriptor {
+    name: &'static str,
+    access: ViewAccess,
+    after: Vec<&'static str>,
+    before: Vec<&'static str>,
+    run: RunFn,
+}
+
+impl std::fmt::Debug for SystemDescriptor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SystemDescriptor")
+            .field("name", &self.name)
+            .field("access", &self.access)
+            .field("after", &self.after)
+            .field("before", &self.before)
+            .finish()
+    }
+}
+
+impl SystemDescriptor {
+    /// `sys` has to accept its views for any lifetime of the World, the views passed to it borrow
+    /// the World for the duration of the call only.
+    pub fn new<M, C, Sys>(name: &'static str, sys: Sys) -> Self
+    where
+        Sys: Fn(M, C) + for<'w> Fn(M::View<'w>, C::View<'w>) + Send + Sync + 'static,
+        M: FromWorldMutFamily,
+        C: FromWorldFamily,
+    {
+        let mut access = ViewAccess::default();
+        <M::View<'static> as FromWorldMut>::access(&mut access);
+        <C::View<'static> as FromWorld>::access(&mut access);
+        let borrows = access.clone();
+        let run = move |world: WorldPtr, tracker: &BorrowTracker| {
+            let _guard = tracker.borrow(name, &borrows);
+            // Safety: the scheduler never runs conflicting systems at the same time and the
+            // views do not outlive the system call
+            let m =
+                <M::View<'_> as FromWorldMut>::from_world_mut(unsafe { &mut *world.0.as_ptr() });
+            let c = <C::View<'_> as FromWorld>::from_world(unsafe { &*world.0.as_ptr() });
+            sys(m, c);
+        };
+        Self {
+            name,
+            access,
+            after: Vec::new(),
+            before: Vec::new(),
+            run: Box::new(run),
+        }
+    }
+
+    /// Run this system after `system`
+    pub fn after(mut self, system: &'static str) -> Self {
+        self.after.push(system);
+        self
+    }
+
+    /// Run this system before `system`
+    pub fn before(mut self, system: &'static str) -> Self {
+        self.before.push(system);
+        self
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    pub fn access(&self) -> &ViewAccess {
+        &self.access
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Scheduler {
+    systems: Vec<SystemDescriptor>,
+    /// Indices of the systems that may run in parallel, in execution order.
+    /// Empty until `build` is called.
+    batches: Vec<Vec<usize>>,
+    borrows: BorrowTracker,
+}
+
+impl Scheduler {
+    pub fn add_system(&mut self, system: SystemDescriptor) -> &mut Self {
+        self.systems.push(system);
+        self.batches.clear();
+        self
+    }
+
+    pub fn systems(&self) -> &[SystemDescriptor] {
+        &self.systems
+    }
+
+    fn pop_system(&mut self) -> Option<SystemDescriptor> {
+        self.batches.clear();
+        self.systems.pop()
+    }
+
+    /// Names of the systems per batch. Systems in the same batch may run in parallel.
+    pub fn batches(&self) -> Vec<Vec<&'static str>> {
+        self.batches
+            .iter()
+            .map(|batch| batch.iter().map(|i| self.systems[*i].name).collect())
+            .collect()
+    }
+
+    /// Compute the execution order of the systems
+    pub fn build(&mut self) -> Result<(), SchedulerError> {
+        let n = self.systems.len();
+        let index = |system: &'static str, other: &'static str| {
+            self.systems
+                .iter()
+                .position(|s| s.name == other)
+                .ok_or(SchedulerError::UnknownSystem { system, other })
+        };
+
+        // predecessors[j] contains i if system i has to finish before j may start
+        let mut predecessors = vec![Vec::new(); n];
+        for (j, system) in self.systems.iter().enumerate() {
+            if self.systems[..j].iter().any(|s| s.name == system.name) {
+                return Err(SchedulerError::DuplicateSystem(system.name));
+            }
+            for (i, other) in self.systems[..j].iter().enumerate() {
+                if other.access.conflicts(&system.access) {
+                    predecessors[j].push(i);
+                }
+            }
+            for other in system.after.iter() {
+                let i = index(system.name, other)?;
+                predecessors[j].push(i);
+            }
+            for other in system.before.iter() {
+                let i = index(system.name, other)?;
+                predecessors[i].push(j);
+            }
+        }
+
+        // a system's batch is one after the last batch of its predecessors
+        let mut level: Vec<Option<usize>> = vec![None; n];
+        let mut remaining = n;
+        while remaining > 0 {
+            let mut progress = false;
+            for (j, preds) in predecessors.iter().enumerate() {
+                if level[j].is_some() {
+                    continue;
+                }
+                let lvl = preds
+                    .iter()
+                    .try_fold(0, |lvl, i| level[*i].map(|l| lvl.max(l + 1)));
+                if let Some(lvl) = lvl {
+                    level[j] = Some(lvl);
+                    remaining -= 1;
+                    progress = true;
+                }
+            }
+            if !progress {
+                let cycle = (0..n)
+                    .filter(|j| level[*j].is_none())
+                    .map(|j| self.systems[j].name)
+                    .collect();
+                return Err(SchedulerError::Cycle(cycle));
+            }
+        }
+
+        let mut batches =
+            vec![Vec::new(); level.iter().flatten().max().map(|l| l + 1).unwrap_or(0)];
+        for (i, lvl) in level.into_iter().enumerate() {
+            batches[lvl.unwrap()].push(i);
+        }
+        trace!("Built system batches {:?}", batches);
+        self.batches = batches;
+        Ok(())
+    }
+
+    /// Run all systems once.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the scheduler was not built. In debug builds, panics if a system borrows a
+    /// table both mutably and immutably.
+    pub fn run(&self, world: &mut World) {
+        profile!("Scheduler::run");
+        assert!(
+            self.systems.is_empty() || !self.batches.is_empty(),
+            "Scheduler::run called before build"
+        );
+
+        let world = WorldPtr(NonNull::from(world));
+        for batch in self.batches.iter() {
+            match batch.as_slice() {
+                [i] => (self.systems[*i].run)(world, &self.borrows),
+                _ => batch
+                    .par_iter()
+                    .for_each(|i| (self.systems[*i].run)(world, &self.borrows)),
+            }
+        }
+    }
+}
+
+/// Stages of a world update, in execution order
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Stage {
+    /// Runs after the intents of the tick were submitted, before they are executed
+    PreIntents,
+    /// Executes the intents of the tick
+    Intents,
+    /// Systems that run regardless of player actions
+    Automated,
+    /// Runs after deferred deletes were executed and the world time was advanced
+    PostProcess,
+}
+
+impl Stage {
+    pub const ALL: [Stage; 4] = [
+        Stage::PreIntents,
+        Stage::Intents,
+        Stage::Automated,
+        Stage::PostProcess,
+    ];
+}
+
+/// Systems of a world update, grouped by [Stage].
+///
+/// Downstream crates may register their own systems on the executor:
+///
+/// ```
+/// use caolo_sim::prelude::*;
+/// use caolo_sim::scheduler::{Stage, SystemDescriptor};
+///
+/// fn sudden_death((mut hp,): (UnsafeView<EntityId, HpComponent>,), (): ()) {
+///     for (_, hp) in hp.iter_mut() {
+///         hp.hp = hp.hp.saturating_sub(1);
+///     }
+/// }
+///
+/// let mut exc = SimpleExecutor::default();
+/// exc.add_system(Stage::Automated, SystemDescriptor::new("sudden_death", sudden_death))
+///     .unwrap();
+/// ```
+#[derive(Debug, Default)]
+pub struct SystemRegistry {
+    pre_intents: Scheduler,
+    intents: Scheduler,
+    automated: Scheduler,
+    post_process: Scheduler,
+}
+
+impl SystemRegistry {
+    /// Add a system to `stage`. The system is not added if it can not be scheduled.
+    pub fn add_system(
+        &mut self,
+        stage: Stage,
+        system: SystemDescriptor,
+    ) -> Result<&mut Self, SchedulerError> {
+        let scheduler = self.stage_mut(stage);
+        scheduler.add_system(system);
+        if let Err(err) = scheduler.build() {
+            scheduler.pop_system();
+            scheduler.build()?;
+            return Err(err);
+        }
+        Ok(self)
+    }
+
+    pub fn stage(&self, stage: Stage) -> &Scheduler {
+        match stage {
+            Stage::PreIntents => &self.pre_intents,
+            Stage::Intents => &self.intents,
+            Stage::Automated => &self.automated,
+            Stage::PostProcess => &self.post_process,
+        }
+    }
+
+    fn stage_mut(&mut self, stage: Stage) -> &mut Scheduler {
+        match stage {
+            Stage::PreIntents => &mut self.pre_intents,
+            Stage::Intents => &mut self.intents,
+            Stage::Automated => &mut self.automated,
+            Stage::PostProcess => &mut self.post_process,
+        }
+    }
+
+    pub fn run_stage(&self, stage: Stage, world: &mut World) {
+        let s = tracing::error_span!("run-stage", stage = ?stage);
+        let _e = s.enter();
+        self.stage(stage).run(world);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prelude::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    fn write_hp(_: (UnsafeView<EntityId, HpComponent>,), _: ()) {}
+    fn read_hp(_: (), _: (View<EntityId, HpComponent>,)) {}
+    fn write_energy(_: (UnsafeView<EntityId, EnergyComponent>,), _: ()) {}
+    fn read_energy(_: (), _: (View<EntityId, EnergyComponent>,)) {}
+
+    #[test]
+    fn conflicting_systems_run_in_order() {
+        let mut scheduler = Scheduler::default();
+        scheduler
+            .add_system(SystemDescriptor::new("write_hp", write_hp))
+            .add_system(SystemDescriptor::new("read_hp", read_hp))
+            .add_system(SystemDescriptor::new("write_energy", write_energy))
+            .add_system(SystemDescriptor::new("read_energy", read_energy));
+        scheduler.build().unwrap();
+
+        assert_eq!(
+            scheduler.batches(),
+            vec![
+                vec!["write_hp", "write_energy"],
+                vec!["read_hp", "read_energy"]
+            ]
+        );
+    }
+
+    #[test]
+    fn explicit_ordering() {
+        let mut scheduler = Scheduler::default();
+        scheduler
+            .add_system(SystemDescriptor::new("read_hp", read_hp).after("read_energy"))
+            .add_system(SystemDescriptor::new("read_energy", read_energy))
+            .add_system(SystemDescriptor::new("write_energy", write_energy).before("read_hp"));
+        scheduler.build().unwrap();
+
+        assert_eq!(
+            scheduler.batches(),
+            vec![vec!["read_energy"], vec!["write_energy"], vec!["read_hp"]]
+        );
+    }
+
+    #[test]
+    fn registry_rejects_invalid_systems() {
+        let mut registry = SystemRegistry::default();
+        registry
+            .add_system(
+                Stage::Automated,
+                SystemDescriptor::new("write_hp", write_hp),
+            )
+            .unwrap();
+        let res = registry.add_system(
+            Stage::Automated,
+            SystemDescriptor::new("read_hp", read_hp).after("read_energy"),
+        );
+        assert!(matches!(res, Err(SchedulerError::UnknownSystem { .. })));
+
+        let automated = registry.stage(Stage::Automated);
+        assert_eq!(automated.batches(), vec![vec!["write_hp"]]);
+        assert!(registry.stage(Stage::Intents).systems().is_empty());
+    }
+
+    #[test]
+    fn cyclic_ordering_is_an_error() {
+        let mut scheduler = Scheduler::default();
+        scheduler
+            .add_system(SystemDescriptor::new("write_hp", write_hp))
+            .add_system(SystemDescriptor::new("read_hp", read_hp).before("write_hp"));
+        let err = scheduler.build().unwrap_err();
+
+        assert!(matches!(err, SchedulerError::Cycle(_)));
+    }
+
+    #[test]
+    #[cfg_attr(debug_assertions, should_panic)]
+    fn aliasing_views_panic() {
+        fn aliasing(_: (UnsafeView<EntityId, HpComponent>,), _: (View<EntityId, HpComponent>,)) {}
+
+        let mut scheduler = Scheduler::default();
+        scheduler.add_system(SystemDescriptor::new("aliasing", aliasing));
+        scheduler.build().unwrap();
+
+        let mut world =
+            futures_lite::future::block_on(SimpleExecutor::default().initialize(GameConfig {
+                world_radius: 1,
+                room_radius: 10,
+                ..Default::default()
+            }));
+        scheduler.run(&mut world);
+    }
+
+    static CALLS: AtomicUsize = AtomicUsize::new(0);
+
+    fn count(_: (), _: (View<EntityId, HpComponent>,)) {
+        CALLS.fetch_add(1, Ordering::Relaxed);
+    }
+
+    #[test]
+    fn runs_the_systems() {
+        let mut scheduler = Scheduler::default();
+        scheduler
+            .add_system(SystemDescriptor::new("a", count))
+            .add_system(SystemDescriptor::new("b", count))
+            .add_system(SystemDescriptor::new("c", write_hp));
+        scheduler.build().unwrap();
+
+        let mut world =
+            futures_lite::future::block_on(SimpleExecutor::default().initialize(GameConfig {
+                world_radius: 1,
+                room_radius: 10,
+                ..Default::default()
+            }));
+        scheduler.run(&mut world);
+
+        assert_eq!(CALLS.load(Ordering::Relaxed), 2);
+    }
+
+    fn heal<'a>(
+        hps: Query<'a, (&'a mut HpComponent,)>,
+        (energy,): (View<'a, EntityId, EnergyComponent>,),
+    ) {
+        for (id, (hp,)) in hps.iter() {
+            if energy.get(id).is_none() {
+                hp.hp = hp.hp_max;
+            }
+        }
+    }
+
+    #[test]
+    fn views_borrow_the_world_for_the_call() {
+        let mut scheduler = Scheduler::default();
+        scheduler.add_system(SystemDescriptor::new("heal", heal));
+        scheduler.build().unwrap();
+
+        let mut world =
+            futures_lite::future::block_on(SimpleExecutor::default().initialize(GameConfig {
+                world_radius: 1,
+                room_radius: 10,
+                ..Default::default()
+            }));
+        let id = world.insert_entity();
+        world
+            .unsafe_view::<EntityId, HpComponent>()
+            .insert(id, HpComponent { hp: 1, hp_max: 100 });
+        scheduler.run(&mut world);
+
+        assert_eq!(
+            world.view::<EntityId, HpComponent>().get(id).unwrap().hp,
+            100
+        );
+    }
+}
@@ -9,6 +9,7 @@ pub mod bots;
 pub mod find_api;
 use crate::geometry::Axial;
 use crate::indices::{EntityId, WorldPosition};
+use crate::pathfinding::PathOptions;
 use crate::profile;
 use crate::systems::script_execution::ScriptExecutionData;
 use crate::{
@@ -35,6 +36,7 @@ pub enum OperationResult {
     Empty = 6,
     Full = 7,
     PathNotFound = 8,
+    LimitReached = 9,
 }
 
 impl TryFrom<Value> for OperationResult {
@@ -51,6 +53,7 @@ impl TryFrom<Value> for OperationResult {
             Value::Integer(6) => OperationResult::Empty,
             Value::Integer(7) => OperationResult::Full,
             Value::Integer(8) => OperationResult::PathNotFound,
+            Value::Integer(9) => OperationResult::LimitReached,
             _ => {
                 return Err(i);
             }
@@ -252,6 +255,96 @@ fn _get_parse_coordinate(point: &FieldTable, key: &str) -> Result<i32, Execution
     })
 }
 
+/// Takes a Cao-Lang Object (FieldTable) and reads PathOptions from the fields.
+/// Missing fields are left at their defaults:
+/// - `ignore_entities` = non-zero to walk through tiles occupied by entities
+/// - `entity_cost`     = additional cost of tiles occupied by entities
+/// - `max_ops`         = maximum iterations, capped by the game config
+/// - `max_rooms`       = maximum number of rooms the path may visit
+/// - `flee_range`      = distance to keep from positions in `flee_from`
+/// - `costs`           = Object of Objects with `q`, `r` and `cost` fields, overriding tile costs
+/// - `flee_from`       = Object of Objects with `q`, `r` fields, positions to flee from
+pub fn parse_path_options(
+    table: &FieldTable,
+    mut options: PathOptions,
+) -> Result<PathOptions, ExecutionError> {
+    if let Some(ignore) = _get_optional_int(table, "ignore_entities")? {
+        options.ignore_entities = ignore != 0;
+    }
+    if let Some(cost) = _get_optional_u32(table, "entity_cost")? {
+        options.entity_cost = cost;
+    }
+    if let Some(max_ops) = _get_optional_u32(table, "max_ops")? {
+        options.max_ops = max_ops;
+    }
+    if let Some(max_rooms) = _get_optional_u32(table, "max_rooms")? {
+        options.max_rooms = max_rooms;
+    }
+    if let Some(range) = _get_optional_u32(table, "flee_range")? {
+        options.flee_range = range;
+    }
+    if let Some(costs) = _get_optional_table(table, "costs")? {
+        for (_, value) in costs.iter() {
+            let item = _value_as_table(value, "costs")?;
+            let q = _get_parse_coordinate(item, "q")?;
+            let r = _get_parse_coordinate(item, "r")?;
+            let cost = _get_optional_u32(item, "cost")?.ok_or_else(|| {
+                ExecutionError::invalid_argument("costs entry is missing `cost`".to_string())
+            })?;
+            options.costs.insert(Axial::new(q, r), cost);
+        }
+    }
+    if let Some(flee) = _get_optional_table(table, "flee_from")? {
+        for (_, value) in flee.iter() {
+            let item = _value_as_table(value, "flee_from")?;
+            let q = _get_parse_coordinate(item, "q")?;
+            let r = _get_parse_coordinate(item, "r")?;
+            options.flee_from.push(Axial::new(q, r));
+        }
+    }
+    Ok(options)
+}
+
+fn _get_optional_int(table: &FieldTable, key: &str) -> Result<Option<i64>, ExecutionError> {
+    match table.get_value(Handle::from_str(key).unwrap()) {
+        None | Some(Value::Nil) => Ok(None),
+        Some(value) => value
+            .try_into()
+            .map(Some)
+            .map_err(|_| ExecutionError::invalid_argument(format!("{} was not an integer", key))),
+    }
+}
+
+fn _get_optional_u32(table: &FieldTable, key: &str) -> Result<Option<u32>, ExecutionError> {
+    _get_optional_int(table, key)?
+        .map(|i| {
+            u32::try_from(i).map_err(|_| {
+                ExecutionError::invalid_argument(format!("{} is not a valid value!", key))
+            })
+        })
+        .transpose()
+}
+
+fn _get_optional_table<'a>(
+    table: &'a FieldTable,
+    key: &str,
+) -> Result<Option<&'a FieldTable>, ExecutionError> {
+    match table.get_value(Handle::from_str(key).unwrap()) {
+        None | Some(Value::Nil) => Ok(None),
+        Some(value) => _value_as_table(value, key).map(Some),
+    }
+}
+
+fn _value_as_table<'a>(value: Value, key: &str) -> Result<&'a FieldTable, ExecutionError> {
+    match value {
+        Value::Object(p) => Ok(unsafe { &*p }),
+        _ => Err(ExecutionError::invalid_argument(format!(
+            "{} was not an object",
+            key
+        ))),
+    }
+}
+
 /// Bootstrap the game API in the Vm
 pub fn make_import() -> Schema {
     Schema {
@@ -300,6 +393,17 @@ pub fn make_import() -> Schema {
                 ),
                 fo: Box::new(into_f1(bots::move_bot_to_position)),
             },
+            FunctionRow {
+                desc: subprogram_description!(
+                    "move_to_position_with_options",
+                    "Move the bot to the given Axial, using the given path finding options",
+                    SubProgramType::Function,
+                    ["Axial coordinate", "PathOptions"],
+                    ["OperationResult"],
+                    []
+                ),
+                fo: Box::new(into_f2(bots::move_bot_to_position_with_options)),
+            },
             FunctionRow {
                 desc: subprogram_description!(
                     "find_closest",
@@ -322,6 +426,17 @@ pub fn make_import() -> Schema {
                 ),
                 fo: Box::new(into_f3(bots::unload)),
             },
+            FunctionRow {
+                desc: subprogram_description!(
+                    "upgrade_controller",
+                    "Transfer the carried energy to the target room controller. Claims the room if it has no owner",
+                    SubProgramType::Function,
+                    ["EntityId"],
+                    ["OperationResult"],
+                    []
+                ),
+                fo: Box::new(into_f1(bots::upgrade_controller)),
+            },
             FunctionRow {
                 desc: subprogram_description!(
                     "parse_find_constant",
@@ -4,10 +4,11 @@ use crate::{
     indices::{EntityId, UserId, WorldPosition},
     intents::{
         check_dropoff_intent, check_melee_intent, check_mine_intent, check_move_intent,
-        CachePathIntent, DropoffIntent, MeleeIntent, MineIntent, MoveIntent, MutPathCacheIntent,
-        PathCacheIntentAction,
+        check_upgrade_controller_intent, CachePathIntent, DropoffIntent, MeleeIntent, MineIntent,
+        MoveIntent, MutPathCacheIntent, PathCacheIntentAction, UpgradeControllerIntent,
     },
-    pathfinding, profile,
+    pathfinding::{self, PathOptions},
+    profile,
     storage::views::FromWorld,
 };
 use crate::{prelude::World, terrain::TileTerrainType};
@@ -92,6 +93,40 @@ pub fn unload(
     Ok(())
 }
 
+pub fn upgrade_controller(
+    vm: &mut Vm<ScriptExecutionData>,
+    target: i64,
+) -> Result<(), ExecutionError> {
+    profile!("upgrade_controller");
+
+    let aux = vm.get_aux();
+    let target: u64 = target.try_into().map_err(|_| {
+        warn!("upgrade_controller called without a valid target");
+        ExecutionError::invalid_argument(
+            "upgrade_controller called without a valid target".to_owned(),
+        )
+    })?;
+    let target: EntityId = EntityId::from(target);
+
+    trace!("upgrade_controller: target: {:?}, {}", target, aux);
+
+    let storage = aux.storage();
+    let user_id = aux.user_id.expect("user_id to be set");
+
+    let intent = UpgradeControllerIntent {
+        bot: aux.entity_id,
+        controller: target,
+    };
+
+    let checkresult =
+        check_upgrade_controller_intent(&intent, user_id, FromWorld::from_world(storage));
+    if let OperationResult::Ok = checkresult {
+        vm.get_aux_mut().intents.upgrade_controller_intent = Some(intent);
+    }
+    vm.stack_push(checkresult)?;
+    Ok(())
+}
+
 pub fn mine_resource(vm: &mut Vm<ScriptExecutionData>, target: i64) -> Result<(), ExecutionError> {
     profile!("mine_resource");
 
@@ -160,7 +195,13 @@ pub fn approach_entity(
         }
     };
 
-    let checkresult = match move_to_pos(entity, targetpos.0, user_id, storage) {
+    let checkresult = match move_to_pos(
+        entity,
+        targetpos.0,
+        user_id,
+        &default_path_options(),
+        storage,
+    ) {
         Ok(Some((move_intent, pop_cache_intent, update_cache_intent))) => {
             let intents = &mut vm.get_aux_mut().intents;
             intents.move_intent = Some(move_intent);
@@ -188,10 +229,38 @@ pub fn move_bot_to_position(
     point: &FieldTable,
 ) -> Result<(), ExecutionError> {
     profile!("move_bot_to_position");
+    trace!("move_bot_to_position");
 
-    let aux = vm.get_aux();
+    move_bot_with_options(vm, point, default_path_options())
+}
 
-    trace!("move_bot_to_position");
+pub fn move_bot_to_position_with_options(
+    vm: &mut Vm<ScriptExecutionData>,
+    point: &FieldTable,
+    options: &FieldTable,
+) -> Result<(), ExecutionError> {
+    profile!("move_bot_to_position_with_options");
+    trace!("move_bot_to_position_with_options");
+
+    let options = parse_path_options(options, default_path_options())?;
+    move_bot_with_options(vm, point, options)
+}
+
+/// Options used by the scripting API. The iteration limit is capped by the GameConfig in
+/// `move_to_pos`.
+fn default_path_options() -> PathOptions {
+    PathOptions {
+        max_ops: u32::MAX,
+        ..Default::default()
+    }
+}
+
+fn move_bot_with_options(
+    vm: &mut Vm<ScriptExecutionData>,
+    point: &FieldTable,
+    options: PathOptions,
+) -> Result<(), ExecutionError> {
+    let aux = vm.get_aux();
 
     let entity = aux.entity_id;
     let storage = aux.storage();
@@ -199,7 +268,7 @@ pub fn move_bot_to_position(
 
     let point: WorldPosition = parse_world_pos(point)?;
 
-    let checkresult = match move_to_pos(entity, point, user_id, storage) {
+    let checkresult = match move_to_pos(entity, point, user_id, &options, storage) {
         Ok(Some((move_intent, pop_cache_intent, update_cache_intent))) => {
             let intents = &mut vm.get_aux_mut().intents;
             intents.move_intent = Some(move_intent);
@@ -231,6 +300,7 @@ fn move_to_pos(
     bot: EntityId,
     to: WorldPosition,
     user_id: UserId,
+    options: &PathOptions,
     storage: &World,
 ) -> Result<Option<MoveToPosIntent>, OperationResult> {
     use crate::prelude::*;
@@ -246,6 +316,9 @@ fn move_to_pos(
             OperationResult::InvalidInput
         })?;
 
+    // paths with custom costs or fleeing are not reusable for other calls
+    let use_cache = options.costs.is_empty() && !options.is_flee();
+
     // attempt to use the cached path
     // which requires non-empty cache with a valid next step
     match storage
@@ -253,7 +326,7 @@ fn move_to_pos(
         .reborrow()
         .get(bot)
     {
-        Some(cache) if cache.target == to => {
+        Some(cache) if use_cache && cache.target == to => {
             if let Some(position) = cache.path.last().cloned() {
                 let intent = MoveIntent {
                     bot,
@@ -283,16 +356,19 @@ fn move_to_pos(
     trace!("Bot path cache miss");
 
     let conf = UnwrapView::<ConfigKey, GameConfig>::from_world(storage);
-    let max_pathfinding_iter = conf.path_finding_limit;
+    let options = PathOptions {
+        max_ops: options.max_ops.min(conf.path_finding_limit),
+        ..options.clone()
+    };
 
-    let mut path = Vec::with_capacity(max_pathfinding_iter as usize);
+    let mut path = Vec::with_capacity(options.max_ops as usize);
     let mut next_room = None;
     if let Err(e) = pathfinding::find_path(
         botpos.0,
         to,
         1,
         FromWorld::from_world(storage),
-        max_pathfinding_iter,
+        &options,
         &mut path,
         &mut next_room,
     ) {
@@ -313,7 +389,7 @@ fn move_to_pos(
             let checkresult = check_move_intent(&intent, user_id, FromWorld::from_world(storage));
             match checkresult {
                 OperationResult::Ok => {
-                    let cache_intent = if !path.is_empty() {
+                    let cache_intent = if use_cache && !path.is_empty() {
                         // skip >= 0
                         let skip = path.len().max(PATH_CACHE_LEN) - PATH_CACHE_LEN;
 
@@ -442,13 +518,11 @@ mod tests {
                     .update(Some(RoomProperties{radius:room_radius as u32, center: room_center}));
 
                 WorldPosition, EntityComponent,
-                    .extend_rooms([Room(from.room),Room(Axial::new(0,1)), Room(to.room)].iter().cloned())
-                    .expect("Failed to add rooms");
+                    .extend_rooms([Room(from.room),Room(Axial::new(0,1)), Room(to.room)].iter().cloned());
 
 
                 WorldPosition, TerrainComponent,
-                    .extend_rooms([Room(from.room),Room(Axial::new(0,1)), Room(to.room)].iter().cloned())
-                    .expect("Failed to add rooms");
+                    .extend_rooms([Room(from.room),Room(Axial::new(0,1)), Room(to.room)].iter().cloned());
                 WorldPosition, TerrainComponent,
                     .iter_rooms_mut().for_each(|(_, room)|room.resize(3));
                 WorldPosition, TerrainComponent,
@@ -475,8 +549,7 @@ mod tests {
                 storage
                 {
                     Axial, RoomConnections,
-                        .insert(from.room, connections )
-                        .expect("Failed to add room connections");
+                        .insert(from.room, connections );
                 }
             );
             let mut connections = RoomConnections::default();
@@ -492,17 +565,17 @@ mod tests {
                 storage
                 {
                 Axial, RoomConnections,
-                    .insert( room, connections )
-                    .expect("Failed to add room connections");
+                    .insert( room, connections );
                 }
             );
         };
         init_connections(next_room);
         init_connections(to.room);
 
-        let (MoveIntent { bot, position }, ..) = move_to_pos(bot_id, to, user_id, &storage)
-            .expect("Expected move to succeed")
-            .expect("Expected a move intent");
+        let (MoveIntent { bot, position }, ..) =
+            move_to_pos(bot_id, to, user_id, &default_path_options(), &storage)
+                .expect("Expected move to succeed")
+                .expect("Expected a move intent");
 
         assert_eq!(bot, bot_id);
         assert_eq!(position.room, next_room);
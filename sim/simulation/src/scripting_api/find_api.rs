@@ -13,6 +13,7 @@ pub enum FindConstant {
     Resource = 1,
     Spawn = 2,
     EnemyBot = 3,
+    Controller = 4,
 }
 
 impl TryFrom<Value> for FindConstant {
@@ -22,6 +23,7 @@ impl TryFrom<Value> for FindConstant {
             Value::Integer(1) => FindConstant::Resource,
             Value::Integer(2) => FindConstant::Spawn,
             Value::Integer(3) => FindConstant::EnemyBot,
+            Value::Integer(4) => FindConstant::Controller,
             _ => return Err(i),
         };
         Ok(op)
@@ -46,6 +48,7 @@ pub fn parse_find_constant(
         "resource" | "RESOURCE" | "Resource" => FindConstant::Resource,
         "spawn" | "SPAWN" | "Spawn" => FindConstant::Spawn,
         "enemy_bot" | "ENEMY_BOT" | "EnemyBot" => FindConstant::EnemyBot,
+        "controller" | "CONTROLLER" | "Controller" => FindConstant::Controller,
         _ => {
             trace!(
                 "parse_find_constant got an invalid constant value {}",
@@ -127,6 +130,10 @@ impl FindConstant {
                     bots.contains(&id) && owner.get(id).map(|owner_id| owner_id.owner_id) != user_id
                 })
             }
+            FindConstant::Controller => {
+                let controllers = storage.view::<EntityId, components::RoomControllerComponent>();
+                find_closest_entity_impl(storage, position, |id| controllers.contains(id))
+            }
         }?;
         match candidate {
             Some(entity) => {
@@ -0,0 +1,222 @@
+//! Runtime checks of the tables borrowed by systems.
+//!
+//! Views hand out references to tables without the compiler being able to check them, so a
+//! system taking both an `UnsafeView` and a `View` of the same table, or two systems writing the
+//! same table in parallel, would go unnoticed. The tracker catches these in debug builds and is
+//! a no-op in release builds.
+use super::views::{TableKey, ViewAccess};
+
+#[cfg(debug_assertions)]
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Mutex,
+};
+
+#[cfg(debug_assertions)]
+#[derive(Debug, Default)]
+struct Borrows {
+    /// Number of readers per table
+    reads: HashMap<TableKey, u32>,
+    writes: HashSet<TableKey>,
+    /// Name of the system holding an exclusive borrow
+    exclusive: Option<&'static str>,
+    /// Number of systems holding borrows
+    active: u32,
+}
+
+#[derive(Debug, Default)]
+pub struct BorrowTracker {
+    #[cfg(debug_assertions)]
+    borrows: Mutex<Borrows>,
+}
+
+/// Releases the borrowed tables when dropped
+#[must_use]
+pub struct BorrowGuard<'a> {
+    #[cfg(debug_assertions)]
+    tracker: &'a BorrowTracker,
+    #[cfg(debug_assertions)]
+    access: &'a ViewAccess,
+    #[cfg(not(debug_assertions))]
+    _m: std::marker::PhantomData<&'a ()>,
+}
+
+impl BorrowTracker {
+    /// Borrow the tables of `access` until the returned guard is dropped.
+    ///
+    /// # Panics
+    ///
+    /// In debug builds, if `access` borrows a table mutably more than once, or both mutably and
+    /// immutably. Or if any of the tables is borrowed by another system in a conflicting way.
+    #[cfg(debug_assertions)]
+    pub fn borrow<'a>(&'a self, system: &'static str, access: &'a ViewAccess) -> BorrowGuard<'a> {
+        if let Some(table) = aliased_table(access) {
+            panic!(
+                "System {} borrows table {} mutably while also borrowing it elsewhere",
+                system, table.name
+            );
+        }
+
+        // panic after releasing the lock, so the tracker is not poisoned
+        let conflict = {
+            let mut borrows = self.borrows.lock().unwrap();
+            let conflict = if let Some(other) = borrows.exclusive {
+                Some(format!("the World is borrowed exclusively by {}", other))
+            } else if access.exclusive && borrows.active > 0 {
+                Some("the World is borrowed by other systems".to_string())
+            } else {
+                access
+                    .writes
+                    .iter()
+                    .find(|t| borrows.writes.contains(t) || borrows.reads.contains_key(t))
+                    .or_else(|| access.reads.iter().find(|t| borrows.writes.contains(t)))
+                    .map(|t| format!("table {} is borrowed by another system", t.name))
+            };
+            if conflict.is_none() {
+                if access.exclusive {
+                    borrows.exclusive = Some(system);
+                }
+                borrows.writes.extend(access.writes.iter().copied());
+                for t in access.reads.iter() {
+                    *borrows.reads.entry(*t).or_insert(0) += 1;
+                }
+                borrows.active += 1;
+            }
+            conflict
+        };
+        if let Some(conflict) = conflict {
+            panic!("System {} can not borrow its views: {}", system, conflict);
+        }
+
+        BorrowGuard {
+            tracker: self,
+            access,
+        }
+    }
+
+    #[cfg(not(debug_assertions))]
+    #[inline]
+    pub fn borrow<'a>(&'a self, _system: &'static str, _access: &'a ViewAccess) -> BorrowGuard<'a> {
+        BorrowGuard {
+            _m: Default::default(),
+        }
+    }
+
+    #[cfg(debug_assertions)]
+    fn release(&self, access: &ViewAccess) {
+        let mut borrows = match self.borrows.lock() {
+            Ok(b) => b,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+        if access.exclusive {
+            borrows.exclusive = None;
+        }
+        for t in access.writes.iter() {
+            borrows.writes.remove(t);
+        }
+        for t in access.reads.iter() {
+            if let Some(n) = borrows.reads.get_mut(t) {
+                *n -= 1;
+                if *n == 0 {
+                    borrows.reads.remove(t);
+                }
+            }
+        }
+        borrows.active -= 1;
+    }
+}
+
+impl<'a> Drop for BorrowGuard<'a> {
+    fn drop(&mut self) {
+        #[cfg(debug_assertions)]
+        self.tracker.release(self.access);
+    }
+}
+
+/// Returns a table `access` borrows mutably more than once, or both mutably and immutably
+pub(crate) fn aliased_table(access: &ViewAccess) -> Option<&TableKey> {
+    access.writes.iter().enumerate().find_map(|(i, t)| {
+        if access.writes[i + 1..].contains(t) || access.reads.contains(t) {
+            Some(t)
+        } else {
+            None
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prelude::*;
+
+    fn access_of<'a, M: FromWorldMut, C: FromWorld<'a>>() -> ViewAccess {
+        let mut access = ViewAccess::default();
+        M::access(&mut access);
+        C::access(&mut access);
+        access
+    }
+
+    #[test]
+    fn detects_aliased_views() {
+        let access =
+            access_of::<(UnsafeView<EntityId, HpComponent>,), (View<EntityId, HpComponent>,)>();
+        assert!(aliased_table(&access).is_some());
+
+        let access = access_of::<
+            (
+                UnsafeView<EntityId, HpComponent>,
+                UnsafeView<EntityId, HpComponent>,
+            ),
+            (),
+        >();
+        assert!(aliased_table(&access).is_some());
+
+        let access = access_of::<
+            (UnsafeView<EntityId, HpComponent>,),
+            (
+                View<EntityId, EnergyComponent>,
+                View<EntityId, EnergyComponent>,
+            ),
+        >();
+        assert!(aliased_table(&access).is_none());
+    }
+
+    #[test]
+    #[cfg_attr(debug_assertions, should_panic)]
+    fn panics_on_aliased_views() {
+        let tracker = BorrowTracker::default();
+        let access =
+            access_of::<(UnsafeView<EntityId, HpComponent>,), (View<EntityId, HpComponent>,)>();
+        let _guard = tracker.borrow("aliasing", &access);
+    }
+
+    #[test]
+    fn shared_reads_are_allowed() {
+        let tracker = BorrowTracker::default();
+        let a =
+            access_of::<(UnsafeView<EntityId, HpComponent>,), (View<EntityId, EnergyComponent>,)>();
+        let b = access_of::<(), (View<EntityId, EnergyComponent>,)>();
+        let _a = tracker.borrow("a", &a);
+        let _b = tracker.borrow("b", &b);
+    }
+
+    #[test]
+    fn releases_borrows_on_drop() {
+        let tracker = BorrowTracker::default();
+        let a = access_of::<(UnsafeView<EntityId, HpComponent>,), ()>();
+        {
+            let _a = tracker.borrow("a", &a);
+        }
+        let _b = tracker.borrow("b", &a);
+    }
+
+    #[test]
+    #[cfg_attr(debug_assertions, should_panic)]
+    fn panics_on_conflicting_systems() {
+        let tracker = BorrowTracker::default();
+        let a = access_of::<(UnsafeView<EntityId, HpComponent>,), ()>();
+        let b = access_of::<(), (View<EntityId, HpComponent>,)>();
+        let _a = tracker.borrow("a", &a);
+        let _b = tracker.borrow("b", &b);
+    }
+}
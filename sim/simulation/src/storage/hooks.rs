@@ -0,0 +1,336 @@
+//! Hooks called when components of entities are inserted or removed.
+//!
+//! Hooks are registered when the [World] is created and keep related state consistent, like
+//! indices and references to the entity in other components.
+//!
+//! - Remove hooks run when an entity is deleted, before its components are dropped. They
+//!   receive the removed component. Deleting a single component through its table does not run
+//!   them.
+//! - Insert hooks run in the post processing of the tick, for the components inserted during
+//!   the tick. They rely on [change tracking](crate::tables::change_tracking), which is enabled
+//!   on the table when the World is created. Pruning the changes is left to their consumers.
+//!
+//! Hooks may defer deleting further entities, which are deleted in the same post processing
+//! when deferred by remove hooks, or in the next tick when deferred by insert hooks.
+//!
+//! ```
+//! use caolo_sim::prelude::*;
+//!
+//! let mut exc = SimpleExecutor::default();
+//! exc.hooks_mut()
+//!     .on_remove(|_world: &mut World, id, hp: &HpComponent| {
+//!         println!("entity {:?} died with {} hp", id, hp.hp);
+//!     });
+//!
+//! let _world = futures_lite::future::block_on(exc.initialize(Default::default()));
+//! ```
+use std::any::Any;
+use std::sync::Arc;
+
+use super::relations;
+use super::views::TableKey;
+use super::HasTable;
+use crate::components::{
+    EntityComponent, Parent, PositionComponent, SpawnComponent, SpawnQueueComponent,
+};
+use crate::indices::{EntityId, WorldPosition};
+use crate::prelude::World;
+use crate::tables::{ChangeKind, ChangedRows, Component, Table};
+
+type RemoveHook = Arc<dyn Fn(&mut World, EntityId, &dyn Any) + Send + Sync>;
+type InsertHook = Arc<dyn Fn(&mut World, u64) + Send + Sync>;
+
+#[derive(Clone)]
+struct RemoveHooks {
+    table: TableKey,
+    /// Removes the component of the entity, if any
+    take: fn(&mut World, EntityId) -> Option<Box<dyn Any>>,
+    hooks: Vec<RemoveHook>,
+}
+
+#[derive(Clone, Default)]
+pub struct ComponentHooks {
+    on_insert: Vec<InsertHook>,
+    on_remove: Vec<RemoveHooks>,
+    /// Enable change tracking on the tables having insert hooks
+    tracked: Vec<fn(&mut World)>,
+}
+
+impl std::fmt::Debug for ComponentHooks {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ComponentHooks")
+            .field("on_insert", &self.on_insert.len())
+            .field(
+                "on_remove",
+                &self
+                    .on_remove
+                    .iter()
+                    .map(|h| h.table.name)
+                    .collect::<Vec<_>>(),
+            )
+            .finish()
+    }
+}
+
+impl ComponentHooks {
+    /// Call `hook` for the components of type `C` inserted during a tick
+    pub fn on_insert<C, F>(&mut self, hook: F) -> &mut Self
+    where
+        C: Component<EntityId> + Clone,
+        C::Table: ChangedRows<Id = EntityId>,
+        World: HasTable<EntityId, C>,
+        F: Fn(&mut World, EntityId, &C) + Send + Sync + 'static,
+    {
+        self.tracked
+            .push(|world| world.unsafe_view::<EntityId, C>().set_change_tracking(true));
+        self.on_insert
+            .push(Arc::new(move |world: &mut World, since: u64| {
+                let inserted = world
+                    .view::<EntityId, C>()
+                    .iter_changed(since)
+                    .filter(|(_, change, _)| change.kind == ChangeKind::Inserted)
+                    .filter_map(|(id, _, row)| row.map(|row| (id, row.clone())))
+                    .collect::<Vec<_>>();
+                for (id, row) in inserted {
+                    hook(world, id, &row);
+                }
+            }));
+        self
+    }
+
+    /// Call `hook` with the component of type `C` of deleted entities
+    pub fn on_remove<C, F>(&mut self, hook: F) -> &mut Self
+    where
+        C: Component<EntityId>,
+        World: HasTable<EntityId, C>,
+        F: Fn(&mut World, EntityId, &C) + Send + Sync + 'static,
+    {
+        let hook: RemoveHook = Arc::new(move |world: &mut World, id: EntityId, row: &dyn Any| {
+            if let Some(row) = row.downcast_ref::<C>() {
+                hook(world, id, row);
+            }
+        });
+        let table = TableKey::of::<(EntityId, C)>();
+        match self.on_remove.iter_mut().find(|h| h.table == table) {
+            Some(hooks) => hooks.hooks.push(hook),
+            None => self.on_remove.push(RemoveHooks {
+                table,
+                take: |world, id| {
+                    world
+                        .unsafe_view::<EntityId, C>()
+                        .delete(id)
+                        .map(|row| Box::new(row) as Box<dyn Any>)
+                },
+                hooks: vec![hook],
+            }),
+        }
+        self
+    }
+
+    pub(crate) fn enable_change_tracking(&self, world: &mut World) {
+        for enable in self.tracked.iter() {
+            enable(world);
+        }
+    }
+
+    /// Run the remove hooks of the components of entity `id`
+    pub(crate) fn run_on_remove(&self, world: &mut World, id: EntityId) {
+        for RemoveHooks { take, hooks, .. } in self.on_remove.iter() {
+            if let Some(row) = take(world, id) {
+                for hook in hooks.iter() {
+                    hook(world, id, &*row);
+                }
+            }
+        }
+    }
+
+    /// Run the insert hooks of the components inserted at or after tick `since`
+    pub(crate) fn run_on_insert(&self, world: &mut World, since: u64) {
+        for hook in self.on_insert.iter() {
+            hook(world, since);
+        }
+    }
+}
+
+/// Hooks of the built-in components
+pub fn default_hooks() -> ComponentHooks {
+    let mut hooks = ComponentHooks::default();
+    hooks
+        .on_remove(remove_position_index)
+        .on_remove(relations::on_child_removed)
+        .on_remove(remove_from_spawn_queue)
+        .on_remove(relations::on_parent_removed);
+    hooks
+}
+
+/// Remove the entity from the entities-by-position index
+fn remove_position_index(world: &mut World, id: EntityId, pos: &PositionComponent) {
+    let mut index = world.unsafe_view::<WorldPosition, EntityComponent>();
+    if index.get(pos.0).map(|e| e.0 == id).unwrap_or(false) {
+        index.delete(pos.0);
+    }
+}
+
+/// Forget the references of its spawn to a deleted bot waiting for spawn. Queued bots are
+/// children of their spawn.
+fn remove_from_spawn_queue(world: &mut World, id: EntityId, Parent(spawn): &Parent) {
+    if let Some(queue) = world
+        .unsafe_view::<EntityId, SpawnQueueComponent>()
+        .get_mut(*spawn)
+    {
+        queue.queue.retain(|bot| *bot != id);
+    }
+    if let Some(spawn) = world
+        .unsafe_view::<EntityId, SpawnComponent>()
+        .get_mut(*spawn)
+    {
+        if spawn.spawning == Some(id) {
+            spawn.spawning = None;
+            spawn.time_to_spawn = 0;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prelude::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[test]
+    fn deleting_a_spawn_deletes_its_queued_bots() {
+        let mut world = World::new();
+        let spawn = world.insert_entity();
+        let bot = world.insert_entity();
+        let pos = WorldPosition {
+            room: Axial::new(1, 1),
+            pos: Axial::new(5, 5),
+        };
+
+        world
+            .entities
+            .spawn
+            .insert(spawn, SpawnComponent::default());
+        let mut queue = SpawnQueueComponent::default();
+        queue.queue.push_back(bot);
+        world.entities.spawnqueue.insert(spawn, queue);
+        world.entities.pos.insert(spawn, PositionComponent(pos));
+        world
+            .positions
+            .point_entity
+            .insert(pos, EntityComponent(spawn))
+            .unwrap();
+        world
+            .entities
+            .spawnbot
+            .insert(bot, SpawnBotComponent::default());
+        world.set_parent(bot, spawn);
+
+        world.deferred_delete(spawn);
+        world.post_process();
+
+        assert!(!world.is_valid_entity(spawn));
+        assert!(!world.is_valid_entity(bot));
+        assert!(world.entities.spawnbot.get(bot).is_none());
+        assert!(world.positions.point_entity.get(pos).is_none());
+    }
+
+    #[test]
+    fn deleting_a_queued_bot_removes_it_from_the_queue() {
+        let mut world = World::new();
+        let spawn = world.insert_entity();
+        let bot = world.insert_entity();
+        let spawning = world.insert_entity();
+
+        let mut queue = SpawnQueueComponent::default();
+        queue.queue.push_back(bot);
+        world.entities.spawnqueue.insert(spawn, queue);
+        world.entities.spawn.insert(
+            spawn,
+            SpawnComponent {
+                spawning: Some(spawning),
+                time_to_spawn: 3,
+            },
+        );
+        for bot in [bot, spawning].iter().copied() {
+            world
+                .entities
+                .spawnbot
+                .insert(bot, SpawnBotComponent::default());
+            world.set_parent(bot, spawn);
+        }
+
+        world.deferred_delete(bot);
+        world.deferred_delete(spawning);
+        world.post_process();
+
+        assert!(world.is_valid_entity(spawn));
+        assert!(world
+            .entities
+            .spawnqueue
+            .get(spawn)
+            .unwrap()
+            .queue
+            .is_empty());
+        let spawn = world.entities.spawn.get(spawn).unwrap();
+        assert_eq!(spawn.spawning, None);
+        assert_eq!(spawn.time_to_spawn, 0);
+    }
+
+    #[test]
+    fn insert_hooks_run_on_post_process() {
+        static INSERTED: AtomicUsize = AtomicUsize::new(0);
+
+        let mut hooks = ComponentHooks::default();
+        hooks.on_insert(|_: &mut World, _, hp: &HpComponent| {
+            INSERTED.fetch_add(hp.hp as usize, Ordering::Relaxed);
+        });
+        let mut world = World::with_hooks(hooks);
+
+        let a = world.insert_entity();
+        let b = world.insert_entity();
+        world
+            .entities
+            .hp
+            .insert(a, HpComponent { hp: 1, hp_max: 1 });
+        world
+            .entities
+            .hp
+            .insert(b, HpComponent { hp: 2, hp_max: 2 });
+        world.post_process();
+        assert_eq!(INSERTED.load(Ordering::Relaxed), 3);
+
+        // modifications do not run the hooks
+        world.entities.hp.get_mut(a).unwrap().hp = 5;
+        world.post_process();
+        assert_eq!(INSERTED.load(Ordering::Relaxed), 3);
+    }
+
+    #[test]
+    fn insert_hooks_leave_the_changes_to_their_consumers() {
+        static INSERTED: AtomicUsize = AtomicUsize::new(0);
+
+        let mut hooks = ComponentHooks::default();
+        hooks.on_insert(|_: &mut World, _, _: &HpComponent| {
+            INSERTED.fetch_add(1, Ordering::Relaxed);
+        });
+        let mut world = World::with_hooks(hooks);
+
+        let a = world.insert_entity();
+        world
+            .entities
+            .hp
+            .insert(a, HpComponent { hp: 1, hp_max: 1 });
+        world.post_process();
+        world.post_process();
+
+        // the hooks ran once, the change is still visible to other consumers
+        assert_eq!(INSERTED.load(Ordering::Relaxed), 1);
+        let changes: Vec<_> = world
+            .view::<EntityId, HpComponent>()
+            .iter_changed(0)
+            .map(|(id, change, _)| (id, change.kind))
+            .collect();
+        assert_eq!(changes, vec![(a, ChangeKind::Inserted)]);
+    }
+}
@@ -13,7 +13,7 @@
 /// use caolo_sim::query;
 /// use caolo_sim::prelude::*;
 ///
-/// let mut store = futures_lite::future::block_on( SimpleExecutor.initialize(caolo_sim::executor::GameConfig {
+/// let mut store = futures_lite::future::block_on( SimpleExecutor::default().initialize(caolo_sim::executor::GameConfig {
 ///     world_radius: 1,
 ///     room_radius: 10,
 ///     ..Default::default()
@@ -69,7 +69,7 @@ macro_rules! query {
 /// use caolo_sim::join;
 /// use caolo_sim::tables::JoinIterator;
 ///
-/// let mut store = futures_lite::future::block_on( SimpleExecutor.initialize(caolo_sim::executor::GameConfig {
+/// let mut store = futures_lite::future::block_on( SimpleExecutor::default().initialize(caolo_sim::executor::GameConfig {
 ///     world_radius: 1,
 ///     room_radius: 10,
 ///     ..Default::default()
@@ -133,7 +133,7 @@ macro_rules! query {
 /// use caolo_sim::join;
 /// use caolo_sim::tables::JoinIterator;
 ///
-/// let mut store = futures_lite::future::block_on( SimpleExecutor.initialize(caolo_sim::executor::GameConfig {
+/// let mut store = futures_lite::future::block_on( SimpleExecutor::default().initialize(caolo_sim::executor::GameConfig {
 ///     world_radius: 1,
 ///     room_radius: 10,
 ///     ..Default::default()
@@ -339,6 +339,16 @@ macro_rules! archetype {
                         $( $name ),*
                     }
                 }
+
+                /// Set the tick subsequent changes are recorded in
+                pub fn set_change_tick(&mut self, tick: u64) {
+                    $( self.$name.set_change_tick(tick); )*
+                }
+
+                /// Forget the changes recorded before tick `before`
+                pub fn prune_changes(&mut self, before: u64) {
+                    $( self.$name.prune_changes(before); )*
+                }
             }
         }
     };
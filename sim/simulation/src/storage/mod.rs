@@ -1,4 +1,8 @@
+pub mod borrow_tracker;
+pub mod hooks;
 mod macros;
+pub mod query;
+pub mod relations;
 pub mod views;
 
 use crate::tables::{Component, TableId};
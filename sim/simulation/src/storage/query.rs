@@ -0,0 +1,675 @@
+//! Iterate the entities having a set of components
+//!
+//! Queries fetch references to the components of each entity, skipping the entities missing
+//! any of the required components. `Option<&C>` fetches optional components, `EntityId` fetches
+//! the id of the entity. Filters of `With<C>` and `Without<C>` further restrict the entities.
+//!
+//! Queries may be used as system parameters in place of views. The fetched references must
+//! share the lifetime of the Query, e.g. `Query<'a, (&'a mut HpComponent, &'a DecayComponent)>`.
+//!
+//! ```
+//! use caolo_sim::prelude::*;
+//!
+//! let mut exc = SimpleExecutor::default();
+//! let mut world = futures_lite::future::block_on(exc.initialize(Default::default()));
+//!
+//! let query = world.query_filtered::<(
+//!     &mut HpComponent,
+//!     &DecayComponent,
+//!     Option<&OwnedEntity>,
+//! ), Without<Structure>>();
+//! for (id, (hp, decay, owner)) in query.iter() {
+//!     hp.hp = hp.hp.saturating_sub(decay.hp_amount);
+//! }
+//! ```
+use std::marker::PhantomData;
+use std::ptr::NonNull;
+
+use rayon::prelude::*;
+
+use super::borrow_tracker::aliased_table;
+use super::views::{FromWorldMut, FromWorldMutFamily, UnsafeView, ViewAccess};
+use super::HasTable;
+use crate::indices::EntityId;
+use crate::prelude::World;
+use crate::tables::{
+    flag_table::SparseFlagTable, page_table::PageTable, sparse_set_table::SparseSetTable,
+    Component, Table, TableRow,
+};
+
+pub type QueryIds<'a> = Box<dyn Iterator<Item = EntityId> + 'a>;
+
+/// Tables queries may iterate
+pub trait QueryTable: Table<Id = EntityId> {
+    fn num_rows(&self) -> usize;
+    /// Ids in the table, in ascending order
+    fn ids(&self) -> QueryIds<'_>;
+
+    fn contains_id(&self, id: EntityId) -> bool {
+        self.get(id).is_some()
+    }
+}
+
+/// Tables queries may mutate
+///
+/// Rows fetched earlier are still borrowed when the next row is fetched, so implementations must
+/// not form references to the table's rows, nor a mutable reference to the table.
+pub trait QueryTableMut: QueryTable {
+    /// # Safety
+    ///
+    /// `table` must point to a valid table, which is not modified during the call
+    unsafe fn row_ptr(table: NonNull<Self>, id: EntityId) -> Option<NonNull<Self::Row>>;
+
+    /// Record the change of a fetched row, if the table tracks its changes
+    ///
+    /// # Safety
+    ///
+    /// `table` must point to a valid table, which is not accessed otherwise during the call
+    unsafe fn row_changed(_table: NonNull<Self>, _id: EntityId) {}
+}
+
+impl<T: TableRow> QueryTable for PageTable<T> {
+    fn num_rows(&self) -> usize {
+        self.len()
+    }
+
+    fn ids(&self) -> QueryIds<'_> {
+        Box::new(self.iter().map(|(id, _)| id))
+    }
+
+    fn contains_id(&self, id: EntityId) -> bool {
+        self.contains(id)
+    }
+}
+
+impl<T: TableRow> QueryTableMut for PageTable<T> {
+    unsafe fn row_ptr(table: NonNull<Self>, id: EntityId) -> Option<NonNull<T>> {
+        PageTable::row_ptr(table, id)
+    }
+
+    unsafe fn row_changed(table: NonNull<Self>, id: EntityId) {
+        PageTable::row_changed(table, id);
+    }
+}
+
+impl<T: TableRow> QueryTable for SparseSetTable<T> {
+    fn num_rows(&self) -> usize {
+        self.len()
+    }
+
+    /// Sorts the ids, as the rows are not stored in order
+    fn ids(&self) -> QueryIds<'_> {
+        let mut ids = self.ids().to_vec();
+        ids.sort_unstable();
+        Box::new(ids.into_iter())
+    }
+
+    fn contains_id(&self, id: EntityId) -> bool {
+        self.contains(id)
+    }
+}
+
+impl<T: TableRow> QueryTableMut for SparseSetTable<T> {
+    unsafe fn row_ptr(table: NonNull<Self>, id: EntityId) -> Option<NonNull<T>> {
+        SparseSetTable::row_ptr(table, id)
+    }
+}
+
+impl<T: TableRow + Default> QueryTable for SparseFlagTable<EntityId, T> {
+    fn num_rows(&self) -> usize {
+        self.len()
+    }
+
+    fn ids(&self) -> QueryIds<'_> {
+        Box::new(self.iter().map(|(id, _)| id))
+    }
+
+    fn contains_id(&self, id: EntityId) -> bool {
+        self.contains(&id)
+    }
+}
+
+/// Components fetched by a [Query]
+///
+/// # Safety
+///
+/// `access` must register every table `fetch` reads or writes
+pub unsafe trait Fetch<'a>: Sized {
+    type Views: Copy + Send + Sync + 'static;
+
+    fn views(world: &mut World) -> Self::Views;
+    fn access(access: &mut ViewAccess);
+    /// Number of rows and ids of the table the query may iterate. None if the fetch does not
+    /// restrict the entities, like optional components.
+    fn driver(views: Self::Views) -> Option<(usize, QueryIds<'a>)>;
+    /// # Safety
+    ///
+    /// Every entity may be fetched at most once during the lifetime `'a`. Must not be called in
+    /// parallel with other calls to `fetch` or `mark_changed`.
+    unsafe fn fetch(views: Self::Views, id: EntityId) -> Option<Self>;
+
+    /// Record the changes of the rows fetched mutably for entity `id`
+    ///
+    /// # Safety
+    ///
+    /// Must not be called in parallel with other calls to `mark_changed` or `fetch`
+    unsafe fn mark_changed(_views: Self::Views, _id: EntityId) {}
+}
+
+/// Names the [Fetch] of a type for every lifetime, see
+/// [FromWorldMutFamily](super::views::FromWorldMutFamily)
+pub trait FetchFamily: Fetch<'static> + 'static {
+    type Fetch<'w>: Fetch<'w>;
+}
+
+unsafe impl<'a, C> Fetch<'a> for &'a C
+where
+    C: Component<EntityId>,
+    C::Table: QueryTable,
+    World: HasTable<EntityId, C>,
+{
+    type Views = UnsafeView<EntityId, C>;
+
+    fn views(world: &mut World) -> Self::Views {
+        UnsafeView::from_world_mut(world)
+    }
+
+    fn access(access: &mut ViewAccess) {
+        access.read::<EntityId, C>();
+    }
+
+    fn driver(mut views: Self::Views) -> Option<(usize, QueryIds<'a>)> {
+        let table: &'a C::Table = unsafe { &*views.as_ptr() };
+        Some((table.num_rows(), table.ids()))
+    }
+
+    unsafe fn fetch(mut views: Self::Views, id: EntityId) -> Option<Self> {
+        let table: &'a C::Table = &*views.as_ptr();
+        table.get(id)
+    }
+}
+
+impl<C> FetchFamily for &'static C
+where
+    C: Component<EntityId>,
+    C::Table: QueryTable,
+    World: HasTable<EntityId, C>,
+{
+    type Fetch<'w> = &'w C;
+}
+
+unsafe impl<'a, C> Fetch<'a> for &'a mut C
+where
+    C: Component<EntityId>,
+    C::Table: QueryTableMut,
+    World: HasTable<EntityId, C>,
+{
+    type Views = UnsafeView<EntityId, C>;
+
+    fn views(world: &mut World) -> Self::Views {
+        UnsafeView::from_world_mut(world)
+    }
+
+    fn access(access: &mut ViewAccess) {
+        access.write::<EntityId, C>();
+    }
+
+    fn driver(mut views: Self::Views) -> Option<(usize, QueryIds<'a>)> {
+        let table: &'a C::Table = unsafe { &*views.as_ptr() };
+        Some((table.num_rows(), table.ids()))
+    }
+
+    unsafe fn fetch(mut views: Self::Views, id: EntityId) -> Option<Self> {
+        let table = NonNull::new_unchecked(views.as_ptr());
+        C::Table::row_ptr(table, id).map(|row| &mut *row.as_ptr())
+    }
+
+    unsafe fn mark_changed(mut views: Self::Views, id: EntityId) {
+        let table = NonNull::new_unchecked(views.as_ptr());
+        C::Table::row_changed(table, id);
+    }
+}
+
+impl<C> FetchFamily for &'static mut C
+where
+    C: Component<EntityId>,
+    C::Table: QueryTableMut,
+    World: HasTable<EntityId, C>,
+{
+    type Fetch<'w> = &'w mut C;
+}
+
+unsafe impl<'a, T: Fetch<'a>> Fetch<'a> for Option<T> {
+    type Views = T::Views;
+
+    fn views(world: &mut World) -> Self::Views {
+        T::views(world)
+    }
+
+    fn access(access: &mut ViewAccess) {
+        T::access(access);
+    }
+
+    fn driver(_: Self::Views) -> Option<(usize, QueryIds<'a>)> {
+        None
+    }
+
+    unsafe fn fetch(views: Self::Views, id: EntityId) -> Option<Self> {
+        Some(T::fetch(views, id))
+    }
+
+    unsafe fn mark_changed(views: Self::Views, id: EntityId) {
+        T::mark_changed(views, id);
+    }
+}
+
+impl<T: FetchFamily> FetchFamily for Option<T> {
+    type Fetch<'w> = Option<T::Fetch<'w>>;
+}
+
+unsafe impl<'a> Fetch<'a> for EntityId {
+    type Views = ();
+
+    fn views(_: &mut World) -> Self::Views {}
+
+    fn access(_: &mut ViewAccess) {}
+
+    fn driver(_: Self::Views) -> Option<(usize, QueryIds<'a>)> {
+        None
+    }
+
+    unsafe fn fetch(_: Self::Views, id: EntityId) -> Option<Self> {
+        Some(id)
+    }
+}
+
+impl FetchFamily for EntityId {
+    type Fetch<'w> = Self;
+}
+
+macro_rules! impl_fetch_tuple {
+    ($($t: ident),*) => {
+        #[allow(non_snake_case)]
+        unsafe impl<'a, $($t: Fetch<'a>),*> Fetch<'a> for ($($t,)*) {
+            type Views = ($($t::Views,)*);
+
+            fn views(world: &mut World) -> Self::Views {
+                ($($t::views(world),)*)
+            }
+
+            fn access(access: &mut ViewAccess) {
+                $($t::access(access);)*
+            }
+
+            /// Iterate the smallest table
+            fn driver(views: Self::Views) -> Option<(usize, QueryIds<'a>)> {
+                let ($($t,)*) = views;
+                let mut res: Option<(usize, QueryIds<'a>)> = None;
+                $(
+                    if let Some((n, ids)) = $t::driver($t) {
+                        if res.as_ref().map(|(m, _)| n < *m).unwrap_or(true) {
+                            res = Some((n, ids));
+                        }
+                    }
+                )*
+                res
+            }
+
+            unsafe fn fetch(views: Self::Views, id: EntityId) -> Option<Self> {
+                let ($($t,)*) = views;
+                Some(($($t::fetch($t, id)?,)*))
+            }
+
+            unsafe fn mark_changed(views: Self::Views, id: EntityId) {
+                let ($($t,)*) = views;
+                $($t::mark_changed($t, id);)*
+            }
+        }
+
+        impl<$($t: FetchFamily),*> FetchFamily for ($($t,)*) {
+            type Fetch<'w> = ($($t::Fetch<'w>,)*);
+        }
+    };
+}
+
+impl_fetch_tuple!(A);
+impl_fetch_tuple!(A, B);
+impl_fetch_tuple!(A, B, C);
+impl_fetch_tuple!(A, B, C, D);
+impl_fetch_tuple!(A, B, C, D, E);
+impl_fetch_tuple!(A, B, C, D, E, F);
+impl_fetch_tuple!(A, B, C, D, E, F, G);
+impl_fetch_tuple!(A, B, C, D, E, F, G, H);
+
+/// Restricts the entities of a [Query] without fetching components
+pub trait QueryFilter {
+    type Views: Copy + Send + Sync + 'static;
+
+    fn views(world: &mut World) -> Self::Views;
+    fn access(access: &mut ViewAccess);
+    fn matches(views: Self::Views, id: EntityId) -> bool;
+}
+
+/// Only entities having component `C`
+pub struct With<C>(PhantomData<C>);
+
+/// Only entities not having component `C`
+pub struct Without<C>(PhantomData<C>);
+
+impl<C> QueryFilter for With<C>
+where
+    C: Component<EntityId>,
+    C::Table: QueryTable,
+    World: HasTable<EntityId, C>,
+{
+    type Views = UnsafeView<EntityId, C>;
+
+    fn views(world: &mut World) -> Self::Views {
+        UnsafeView::from_world_mut(world)
+    }
+
+    fn access(access: &mut ViewAccess) {
+        access.read::<EntityId, C>();
+    }
+
+    fn matches(views: Self::Views, id: EntityId) -> bool {
+        views.contains_id(id)
+    }
+}
+
+impl<C> QueryFilter for Without<C>
+where
+    C: Component<EntityId>,
+    C::Table: QueryTable,
+    World: HasTable<EntityId, C>,
+{
+    type Views = UnsafeView<EntityId, C>;
+
+    fn views(world: &mut World) -> Self::Views {
+        UnsafeView::from_world_mut(world)
+    }
+
+    fn access(access: &mut ViewAccess) {
+        access.read::<EntityId, C>();
+    }
+
+    fn matches(views: Self::Views, id: EntityId) -> bool {
+        !views.contains_id(id)
+    }
+}
+
+impl QueryFilter for () {
+    type Views = ();
+
+    fn views(_: &mut World) -> Self::Views {}
+
+    fn access(_: &mut ViewAccess) {}
+
+    fn matches(_: Self::Views, _: EntityId) -> bool {
+        true
+    }
+}
+
+macro_rules! impl_filter_tuple {
+    ($($t: ident),*) => {
+        #[allow(non_snake_case)]
+        impl<$($t: QueryFilter),*> QueryFilter for ($($t,)*) {
+            type Views = ($($t::Views,)*);
+
+            fn views(world: &mut World) -> Self::Views {
+                ($($t::views(world),)*)
+            }
+
+            fn access(access: &mut ViewAccess) {
+                $($t::access(access);)*
+            }
+
+            fn matches(views: Self::Views, id: EntityId) -> bool {
+                let ($($t,)*) = views;
+                $($t::matches($t, id))&&*
+            }
+        }
+    };
+}
+
+impl_filter_tuple!(A);
+impl_filter_tuple!(A, B);
+impl_filter_tuple!(A, B, C);
+impl_filter_tuple!(A, B, C, D);
+
+/// Iterates the entities having all components of `Q` and matching `F`.
+///
+/// Do not store Queries for longer than the function scope, like UnsafeViews they hold
+/// pointers into the World.
+pub struct Query<'a, Q: Fetch<'a>, F: QueryFilter = ()> {
+    views: Q::Views,
+    filter: F::Views,
+    _m: PhantomData<(&'a mut World, fn() -> (Q, F))>,
+}
+
+impl<'a, Q: Fetch<'a>, F: QueryFilter> Query<'a, Q, F> {
+    /// Ids of the entities matching the query.
+    /// Collected before any row is fetched, as the drivers and filters borrow their tables.
+    fn ids(&self) -> Vec<EntityId> {
+        let filter = self.filter;
+        let (_, ids) =
+            Q::driver(self.views).expect("Queries require at least one non-optional component");
+        ids.filter(move |id| F::matches(filter, *id)).collect()
+    }
+
+    /// # Panics
+    ///
+    /// If the query has no required components
+    pub fn iter(self) -> impl Iterator<Item = (EntityId, Q)> + 'a
+    where
+        Q: 'a,
+        F: 'a,
+    {
+        let views = self.views;
+        // Safety: the ids are unique, so every entity is fetched once
+        self.ids().into_iter().filter_map(move |id| unsafe {
+            let q = Q::fetch(views, id)?;
+            Q::mark_changed(views, id);
+            Some((id, q))
+        })
+    }
+
+    /// Call `f` on the matching entities in parallel
+    ///
+    /// # Panics
+    ///
+    /// If the query has no required components
+    pub fn par_for_each(self, f: impl Fn(EntityId, Q) + Send + Sync)
+    where
+        Q: Send + 'a,
+        F: 'a,
+    {
+        let views = self.views;
+        // fetch on this thread, the tables are not accessed in the parallel section
+        // Safety: the ids are unique, so every entity is fetched once
+        let rows = self
+            .ids()
+            .into_iter()
+            .filter_map(|id| unsafe { Q::fetch(views, id) }.map(|q| (id, q)))
+            .collect::<Vec<_>>();
+        let fetched = rows.iter().map(|(id, _)| *id).collect::<Vec<_>>();
+        rows.into_par_iter().for_each(|(id, q)| f(id, q));
+        // record the changes once the rows are no longer borrowed
+        for id in fetched {
+            unsafe { Q::mark_changed(views, id) };
+        }
+    }
+}
+
+impl<'a, Q: Fetch<'a>, F: QueryFilter> FromWorldMut for Query<'a, Q, F> {
+    /// # Panics
+    ///
+    /// If `Q` fetches a component mutably more than once, or both mutably and immutably
+    fn from_world_mut(w: &mut World) -> Self {
+        let mut access = ViewAccess::default();
+        Q::access(&mut access);
+        if let Some(table) = aliased_table(&access) {
+            panic!(
+                "Query fetches table {} mutably while also fetching it elsewhere",
+                table.name
+            );
+        }
+        Self {
+            views: Q::views(w),
+            filter: F::views(w),
+            _m: PhantomData,
+        }
+    }
+
+    fn access(access: &mut ViewAccess) {
+        Q::access(access);
+        F::access(access);
+    }
+}
+
+impl<Q: FetchFamily, F: QueryFilter + 'static> FromWorldMutFamily for Query<'static, Q, F> {
+    type View<'w> = Query<'w, Q::Fetch<'w>, F>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prelude::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    fn setup() -> (World, [EntityId; 3]) {
+        let mut world =
+            futures_lite::future::block_on(SimpleExecutor::default().initialize(GameConfig {
+                world_radius: 1,
+                room_radius: 10,
+                ..Default::default()
+            }));
+        let ids = [
+            world.insert_entity(),
+            world.insert_entity(),
+            world.insert_entity(),
+        ];
+        let user = UserId(uuid::Uuid::new_v4());
+        let hp = HpComponent {
+            hp: 100,
+            hp_max: 100,
+        };
+        let decay = DecayComponent {
+            hp_amount: 10,
+            interval: 1,
+            time_remaining: 0,
+        };
+        let mut hps = world.unsafe_view::<EntityId, HpComponent>();
+        let mut decays = world.unsafe_view::<EntityId, DecayComponent>();
+        for id in ids.iter().copied() {
+            hps.insert(id, hp);
+        }
+        decays.insert(ids[0], decay);
+        decays.insert(ids[2], decay);
+        world
+            .unsafe_view::<EntityId, OwnedEntity>()
+            .insert(ids[0], OwnedEntity { owner_id: user });
+        world.unsafe_view::<EntityId, Structure>().insert(ids[2]);
+        (world, ids)
+    }
+
+    #[test]
+    fn fetches_matching_entities() {
+        let (mut world, ids) = setup();
+
+        let res = world
+            .query::<(
+                EntityId,
+                &HpComponent,
+                &DecayComponent,
+                Option<&OwnedEntity>,
+            )>()
+            .iter()
+            .map(|(id, (id2, _, _, owner))| {
+                assert_eq!(id, id2);
+                (id, owner.is_some())
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(res, vec![(ids[0], true), (ids[2], false)]);
+
+        let res = world
+            .query_filtered::<(&HpComponent,), With<Structure>>()
+            .iter()
+            .map(|(id, _)| id)
+            .collect::<Vec<_>>();
+        assert_eq!(res, vec![ids[2]]);
+    }
+
+    #[test]
+    fn mutates_components() {
+        let (mut world, ids) = setup();
+
+        for (_, (hp, decay)) in world
+            .query_filtered::<(&mut HpComponent, &DecayComponent), Without<Structure>>()
+            .iter()
+        {
+            hp.hp -= decay.hp_amount;
+        }
+
+        let hps = world.view::<EntityId, HpComponent>();
+        assert_eq!(hps.get(ids[0]).unwrap().hp, 90);
+        assert_eq!(hps.get(ids[1]).unwrap().hp, 100);
+        assert_eq!(hps.get(ids[2]).unwrap().hp, 100);
+    }
+
+    #[test]
+    fn parallel_iteration() {
+        let (mut world, _) = setup();
+
+        let count = AtomicUsize::new(0);
+        world
+            .query::<(&mut HpComponent,)>()
+            .par_for_each(|_, (hp,)| {
+                hp.hp = 0;
+                count.fetch_add(1, Ordering::Relaxed);
+            });
+
+        assert_eq!(count.load(Ordering::Relaxed), 3);
+        assert!(world
+            .view::<EntityId, HpComponent>()
+            .iter()
+            .all(|(_, hp)| hp.hp == 0));
+    }
+
+    #[test]
+    fn fetched_rows_outlive_the_iteration() {
+        use crate::tables::{ChangeKind, ChangedRows};
+
+        let (mut world, ids) = setup();
+        world.entities.hp.set_change_tracking(true);
+
+        let rows = world
+            .query::<(&mut HpComponent, Option<&mut DecayComponent>)>()
+            .iter()
+            .collect::<Vec<_>>();
+        for (_, (hp, decay)) in rows {
+            hp.hp = if decay.is_some() { 1 } else { 2 };
+        }
+
+        let changes: Vec<_> = world
+            .view::<EntityId, HpComponent>()
+            .iter_changed(0)
+            .map(|(id, change, hp)| (id, change.kind, hp.map(|hp| hp.hp)))
+            .collect();
+        assert_eq!(
+            changes,
+            vec![
+                (ids[0], ChangeKind::Modified, Some(1)),
+                (ids[1], ChangeKind::Modified, Some(2)),
+                (ids[2], ChangeKind::Modified, Some(1)),
+            ]
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn aliasing_components_panic() {
+        let (mut world, _) = setup();
+        let _ = world.query::<(&mut HpComponent, &HpComponent)>();
+    }
+}
@@ -0,0 +1,123 @@
+//! Parent-child links between entities.
+//!
+//! Links are stored in both directions, as the [Parent] of the child and the [Children] of the
+//! parent, and are kept consistent when entities are deleted:
+//!
+//! - Deleting a child removes it from the children of its parent.
+//! - Deleting a parent deletes its children.
+//!
+//! The cleanup is done by the [default hooks](super::hooks::default_hooks) when
+//! `World::post_process` deletes the entities.
+use super::views::UnsafeView;
+use crate::components::{Children, Parent};
+use crate::indices::EntityId;
+use crate::prelude::World;
+use crate::storage::DeferredDeleteById;
+
+pub type RelationViews = (UnsafeView<EntityId, Parent>, UnsafeView<EntityId, Children>);
+
+/// Make `parent` the parent of `child`, unlinking it from its previous parent
+pub fn set_parent((mut parents, mut children): RelationViews, child: EntityId, parent: EntityId) {
+    if let Some(Parent(previous)) = parents.insert(child, Parent(parent)) {
+        unlink_child(children, previous, child);
+    }
+    match children.get_mut(parent) {
+        Some(Children(ids)) => ids.push(child),
+        None => {
+            children.insert(parent, Children(vec![child]));
+        }
+    }
+}
+
+/// Unlink `child` from its parent. Returns the previous parent, if any
+pub fn remove_parent((mut parents, children): RelationViews, child: EntityId) -> Option<EntityId> {
+    let Parent(parent) = parents.remove(child)?;
+    unlink_child(children, parent, child);
+    Some(parent)
+}
+
+fn unlink_child(mut children: UnsafeView<EntityId, Children>, parent: EntityId, child: EntityId) {
+    let empty = match children.get_mut(parent) {
+        Some(Children(ids)) => {
+            ids.retain(|id| *id != child);
+            ids.is_empty()
+        }
+        None => false,
+    };
+    if empty {
+        children.remove(parent);
+    }
+}
+
+/// Remove hook of [Parent]
+pub(crate) fn on_child_removed(world: &mut World, child: EntityId, Parent(parent): &Parent) {
+    unlink_child(world.unsafe_view(), *parent, child);
+}
+
+/// Remove hook of [Children]
+pub(crate) fn on_parent_removed(world: &mut World, _parent: EntityId, children: &Children) {
+    let mut parents = world.unsafe_view::<EntityId, Parent>();
+    for child in children.0.iter().copied() {
+        parents.remove(child);
+        world.deferred_delete(child);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prelude::*;
+
+    #[test]
+    fn deleting_a_parent_deletes_its_children() {
+        let mut world = World::new();
+        let parent = world.insert_entity();
+        let child = world.insert_entity();
+        let grandchild = world.insert_entity();
+        world.set_parent(child, parent);
+        world.set_parent(grandchild, child);
+        assert_eq!(world.children(parent), &[child]);
+
+        world.deferred_delete(parent);
+        world.post_process();
+
+        assert!(!world.is_valid_entity(child));
+        assert!(!world.is_valid_entity(grandchild));
+        assert!(world.entities.parent.is_empty());
+        assert!(world.entities.children.is_empty());
+    }
+
+    #[test]
+    fn deleting_a_child_unlinks_it() {
+        let mut world = World::new();
+        let parent = world.insert_entity();
+        let a = world.insert_entity();
+        let b = world.insert_entity();
+        world.set_parent(a, parent);
+        world.set_parent(b, parent);
+
+        world.deferred_delete(a);
+        world.post_process();
+
+        assert!(world.is_valid_entity(parent));
+        assert_eq!(world.children(parent), &[b]);
+
+        assert_eq!(world.remove_parent(b), Some(parent));
+        assert!(world.children(parent).is_empty());
+        assert!(world.entities.children.get(parent).is_none());
+    }
+
+    #[test]
+    fn reparenting_unlinks_the_previous_parent() {
+        let mut world = World::new();
+        let a = world.insert_entity();
+        let b = world.insert_entity();
+        let child = world.insert_entity();
+        world.set_parent(child, a);
+        world.set_parent(child, b);
+
+        assert!(world.children(a).is_empty());
+        assert_eq!(world.children(b), &[child]);
+        assert_eq!(world.entities.parent.get(child), Some(&Parent(b)));
+    }
+}
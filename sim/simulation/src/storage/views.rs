@@ -19,7 +19,7 @@
 //!     // do stuff
 //! }
 //!
-//! let mut exc = SimpleExecutor;
+//! let mut exc = SimpleExecutor::default();
 //! let mut storage = futures_lite::future::block_on(exc.initialize(Default::default()));
 //! update_minerals(FromWorldMut::from_world_mut(&mut storage), FromWorld::from_world(&storage));
 //! ```
@@ -34,17 +34,100 @@ pub use unwrap::*;
 pub use unwrap_mut::*;
 pub use view::*;
 
-use super::{Component, DeleteById, TableId};
+use super::{Component, TableId};
 use crate::indices::EntityId;
 use crate::prelude::World;
+use std::any::TypeId;
 use std::ptr::NonNull;
 
 pub trait FromWorld<'a> {
     fn from_world(w: &'a World) -> Self;
+    /// Register the tables this view reads
+    fn access(access: &mut ViewAccess);
+}
+
+/// Names the [FromWorld] view of a type for every lifetime of the World.
+///
+/// Implemented for the `'static` instance of a view, e.g. `View<'static, Id, C>`, so systems can
+/// be registered by their signature but receive views borrowing the World for the duration of
+/// their call only.
+pub trait FromWorldFamily: 'static {
+    type View<'w>: FromWorld<'w>;
 }
 
 pub trait FromWorldMut {
     fn from_world_mut(w: &mut World) -> Self;
+    /// Register the tables this view writes
+    fn access(access: &mut ViewAccess);
+}
+
+/// Names the [FromWorldMut] view of a type for every lifetime of the World.
+///
+/// The counterpart of [FromWorldFamily] for views borrowing the World, like
+/// [Query](super::query::Query). Views without a lifetime name themselves.
+pub trait FromWorldMutFamily: 'static {
+    type View<'w>: FromWorldMut;
+}
+
+/// Identifies a table, or other part of the World, accessed by views
+#[derive(Debug, Clone, Copy)]
+pub struct TableKey {
+    pub id: TypeId,
+    pub name: &'static str,
+}
+
+impl TableKey {
+    pub fn of<T: 'static>() -> Self {
+        Self {
+            id: TypeId::of::<T>(),
+            name: std::any::type_name::<T>(),
+        }
+    }
+}
+
+impl PartialEq for TableKey {
+    fn eq(&self, other: &Self) -> bool {
+        self.id == other.id
+    }
+}
+
+impl Eq for TableKey {}
+
+impl std::hash::Hash for TableKey {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.id.hash(state)
+    }
+}
+
+/// Set of tables accessed by a group of views. Used to find the systems that may run in
+/// parallel.
+#[derive(Debug, Clone, Default)]
+pub struct ViewAccess {
+    pub reads: Vec<TableKey>,
+    pub writes: Vec<TableKey>,
+    /// The views may access any part of the World
+    pub exclusive: bool,
+}
+
+impl ViewAccess {
+    pub fn read<Id: TableId, C: Component<Id>>(&mut self) {
+        self.reads.push(TableKey::of::<(Id, C)>());
+    }
+
+    pub fn write<Id: TableId, C: Component<Id>>(&mut self) {
+        self.writes.push(TableKey::of::<(Id, C)>());
+    }
+
+    /// Two sets conflict if either of them writes a table the other accesses
+    pub fn conflicts(&self, other: &ViewAccess) -> bool {
+        if self.exclusive || other.exclusive {
+            return true;
+        }
+        self.writes
+            .iter()
+            .any(|t| other.reads.contains(t) || other.writes.contains(t))
+            || other.writes.iter().any(|t| self.reads.contains(t))
+    }
 }
 
 #[derive(Clone, Copy)]
@@ -76,6 +159,14 @@ impl FromWorldMut for DeferredDeleteEntityView {
             world: unsafe { NonNull::new_unchecked(w) },
         }
     }
+
+    fn access(access: &mut ViewAccess) {
+        access.writes.push(TableKey::of::<Self>());
+    }
+}
+
+impl FromWorldMutFamily for DeferredDeleteEntityView {
+    type View<'w> = Self;
 }
 
 #[derive(Clone, Copy)]
@@ -86,16 +177,14 @@ pub struct DeleteEntityView {
 unsafe impl Send for DeleteEntityView {}
 unsafe impl Sync for DeleteEntityView {}
 
-impl DeleteEntityView
-where
-    crate::world::entity_store::Archetype: super::DeleteById<EntityId>,
-{
+impl DeleteEntityView {
+    /// Runs the remove hooks of the components of the entity
+    ///
     /// # Safety
     /// This function should only be called if the pointed to Storage is in memory and no other
     /// threads have access to it at this time!
     pub unsafe fn delete_entity(&mut self, id: EntityId) {
-        let storage = &mut self.storage.as_mut().entities;
-        storage.delete(id);
+        self.storage.as_mut().delete_entity(id);
     }
 }
 
@@ -105,6 +194,15 @@ impl FromWorldMut for DeleteEntityView {
             storage: unsafe { NonNull::new_unchecked(w) },
         }
     }
+
+    fn access(access: &mut ViewAccess) {
+        // deletes from every entity table
+        access.exclusive = true;
+    }
+}
+
+impl FromWorldMutFamily for DeleteEntityView {
+    type View<'w> = Self;
 }
 
 #[derive(Clone, Copy)]
@@ -121,6 +219,14 @@ impl FromWorldMut for InsertEntityView {
             storage: unsafe { NonNull::new_unchecked(w) },
         }
     }
+
+    fn access(access: &mut ViewAccess) {
+        access.writes.push(TableKey::of::<Self>());
+    }
+}
+
+impl FromWorldMutFamily for InsertEntityView {
+    type View<'w> = Self;
 }
 
 impl InsertEntityView {
@@ -140,6 +246,13 @@ impl<'a> FromWorld<'a> for WorldTime {
     fn from_world(w: &'a World) -> Self {
         Self(w.time())
     }
+
+    /// Time is only updated between ticks
+    fn access(_: &mut ViewAccess) {}
+}
+
+impl FromWorldFamily for WorldTime {
+    type View<'w> = Self;
 }
 
 macro_rules! implement_tuple {
@@ -153,6 +266,16 @@ macro_rules! implement_tuple {
                         $v::from_world(storage) ,
                     )
                 }
+
+                fn access(access: &mut ViewAccess) {
+                    $v::access(access);
+                }
+            }
+
+        impl<$v: FromWorldFamily >
+            FromWorldFamily for ( $v, )
+            {
+                type View<'w> = ( $v::View<'w>, );
             }
 
         impl<$v:FromWorldMut >
@@ -165,6 +288,16 @@ macro_rules! implement_tuple {
                     )
                 }
 
+                fn access(access: &mut ViewAccess) {
+                    $v::access(access);
+                }
+
+            }
+
+        impl<$v: FromWorldMutFamily >
+            FromWorldMutFamily for ( $v, )
+            {
+                type View<'w> = ( $v::View<'w>, );
             }
     };
 
@@ -179,6 +312,17 @@ macro_rules! implement_tuple {
                         $($vv::from_world(storage)),*
                     )
                 }
+
+                #[allow(unused)]
+                fn access(access: &mut ViewAccess) {
+                    $($vv::access(access));*
+                }
+            }
+
+        impl<$($vv:FromWorldFamily),* >
+            FromWorldFamily for ( $($vv),* )
+            {
+                type View<'w> = ( $($vv::View<'w>),* );
             }
 
         impl<'a, $($vv:FromWorldMut),* >
@@ -191,6 +335,17 @@ macro_rules! implement_tuple {
                         $($vv::from_world_mut(storage)),*
                     )
                 }
+
+                #[allow(unused)]
+                fn access(access: &mut ViewAccess) {
+                    $($vv::access(access));*
+                }
+            }
+
+        impl<$($vv:FromWorldMutFamily),* >
+            FromWorldMutFamily for ( $($vv),* )
+            {
+                type View<'w> = ( $($vv::View<'w>),* );
             }
     };
 }
@@ -1,5 +1,5 @@
 use super::super::HasTable;
-use super::{Component, FromWorldMut, TableId};
+use super::{Component, FromWorldMut, FromWorldMutFamily, TableId, ViewAccess};
 use crate::prelude::World;
 use std::ops::{Deref, DerefMut};
 use std::ptr::NonNull;
@@ -32,6 +32,17 @@ where
     fn from_world_mut(w: &mut World) -> Self {
         <World as HasTable<Id, C>>::unsafe_view(w)
     }
+
+    fn access(access: &mut ViewAccess) {
+        access.write::<Id, C>();
+    }
+}
+
+impl<Id: TableId, C: Component<Id>> FromWorldMutFamily for UnsafeView<Id, C>
+where
+    crate::world::World: HasTable<Id, C>,
+{
+    type View<'w> = Self;
 }
 
 impl<Id: TableId, C: Component<Id>> Clone for UnsafeView<Id, C> {
@@ -1,5 +1,5 @@
 use super::super::HasTable;
-use super::{Component, FromWorld, View, World};
+use super::{Component, FromWorld, FromWorldFamily, View, ViewAccess, World};
 use crate::tables::unique_table::UniqueTable;
 use crate::tables::TableId;
 use std::ops::Deref;
@@ -49,4 +49,16 @@ where
         let table: &UniqueTable<Id, C> = View::from_world(w).reborrow();
         UnwrapView(table)
     }
+
+    fn access(access: &mut ViewAccess) {
+        access.read::<Id, C>();
+    }
+}
+
+impl<Id: TableId, C: Default + Component<Id, Table = UniqueTable<Id, C>>> FromWorldFamily
+    for UnwrapView<'static, Id, C>
+where
+    crate::world::World: HasTable<Id, C>,
+{
+    type View<'w> = UnwrapView<'w, Id, C>;
 }
@@ -1,5 +1,5 @@
 use super::super::HasTable;
-use super::{Component, FromWorldMut, UnsafeView, World};
+use super::{Component, FromWorldMut, FromWorldMutFamily, UnsafeView, ViewAccess, World};
 use crate::tables::unique_table::UniqueTable;
 use crate::tables::TableId;
 use std::ops::{Deref, DerefMut};
@@ -54,4 +54,16 @@ where
         let table = UnsafeView::from_world_mut(w).as_ptr();
         UnwrapViewMut(NonNull::new(table).unwrap())
     }
+
+    fn access(access: &mut ViewAccess) {
+        access.write::<Id, C>();
+    }
+}
+
+impl<Id: TableId, C: Default + Component<Id, Table = UniqueTable<Id, C>>> FromWorldMutFamily
+    for UnwrapViewMut<Id, C>
+where
+    crate::world::World: HasTable<Id, C>,
+{
+    type View<'w> = Self;
 }
@@ -1,5 +1,6 @@
 use super::super::HasTable;
-use super::{Component, FromWorld, TableId, World};
+use super::{Component, FromWorld, FromWorldFamily, TableId, ViewAccess, World};
+use crate::tables::{Change, ChangedRows};
 use std::ops::Deref;
 
 /// Fetch read-only tables from a Storage
@@ -25,6 +26,20 @@ impl<'a, Id: TableId, C: Component<Id>> View<'a, Id, C> {
     pub fn from_table(t: &'a C::Table) -> Self {
         Self(t)
     }
+
+    /// Iterate over the rows changed at or after tick `since`.
+    /// Removed rows are returned without their value.
+    ///
+    /// Yields nothing unless change tracking is enabled on the table.
+    pub fn iter_changed(self, since: u64) -> impl Iterator<Item = (Id, Change, Option<&'a C>)>
+    where
+        C::Table: ChangedRows<Id = Id>,
+    {
+        let table = self.0;
+        table
+            .changes_since(since)
+            .map(move |(id, change)| (id, change, table.get(id)))
+    }
 }
 
 impl<'a, Id: TableId, C: Component<Id>> Deref for View<'a, Id, C> {
@@ -42,4 +57,15 @@ where
     fn from_world(w: &'a World) -> Self {
         <World as HasTable<Id, C>>::view(w)
     }
+
+    fn access(access: &mut ViewAccess) {
+        access.read::<Id, C>();
+    }
+}
+
+impl<Id: TableId, C: Component<Id>> FromWorldFamily for View<'static, Id, C>
+where
+    crate::world::World: HasTable<Id, C>,
+{
+    type View<'w> = View<'w, Id, C>;
 }
@@ -1,4 +1,5 @@
 pub mod attack_system;
+pub mod controller_system;
 pub mod death_system;
 pub mod decay_system;
 pub mod dropoff_intent_system;
@@ -16,6 +17,7 @@ pub mod script_history_system;
 pub mod spawn_system;
 
 use attack_system::attack_system_update;
+use controller_system::{controller_downgrade_update, upgrade_controller_intents_update};
 use death_system::death_update;
 use decay_system::decay_update;
 use dropoff_intent_system::dropoff_intents_update;
@@ -31,55 +33,133 @@ use say_intent_system::say_intents_update;
 use script_history_system::script_history_update;
 use spawn_system::{update_spawn_intents, update_spawns};
 
-use crate::storage::views::{FromWorld, FromWorldMut};
+use crate::components::WorldEvents;
+use crate::indices::EmptyKey;
+use crate::scheduler::{SchedulerError, Stage, SystemDescriptor, SystemRegistry};
+use crate::storage::views::{FromWorldMut, UnwrapViewMut};
 use crate::{prelude::World, profile};
 
-pub fn execute_world_update(storage: &mut World) {
+/// Run the systems of the world update, except for the post processing stage
+pub fn execute_world_update(storage: &mut World, systems: &SystemRegistry) {
     profile!("execute_systems_update");
 
-    execute_intents(storage);
-    execute_automated_systems(storage);
-}
+    UnwrapViewMut::<EmptyKey, WorldEvents>::from_world_mut(storage).clear();
 
-fn execute_intents(storage: &mut World) {
-    profile!("execute_intents");
+    systems.run_stage(Stage::PreIntents, storage);
+    systems.run_stage(Stage::Intents, storage);
+    systems.run_stage(Stage::Automated, storage);
+}
 
-    // pre processing
-    execute_update(spawn_system::update_cont_spawns, storage);
+/// Registry of the built-in systems.
+///
+/// Systems are added in the order they should run in if they access the same tables.
+/// Systems without conflicts run in parallel.
+pub fn default_systems() -> SystemRegistry {
+    let mut systems = SystemRegistry::default();
+    add_pre_intent_systems(&mut systems)
+        .and_then(|_| add_intent_systems(&mut systems))
+        .and_then(|_| add_automated_systems(&mut systems))
+        .expect("Failed to register the built-in systems");
+    systems
+}
 
-    // main processing
-    execute_update(attack_system_update, storage);
-    execute_update(move_intents_update, storage);
-    execute_update(mine_intents_update, storage);
-    execute_update(dropoff_intents_update, storage);
-    execute_update(update_spawn_intents, storage);
-    execute_update(log_intents_update, storage);
-    execute_update(path_cache_intents_update, storage);
-    execute_update(script_history_update, storage);
-    execute_update(say_intents_update, storage);
+fn add_pre_intent_systems(systems: &mut SystemRegistry) -> Result<(), SchedulerError> {
+    let stage = Stage::PreIntents;
+    systems.add_system(
+        stage,
+        SystemDescriptor::new("update_cont_spawns", spawn_system::update_cont_spawns),
+    )?;
+    Ok(())
 }
 
-/// Execute systems that run regardless of player actions
-fn execute_automated_systems(storage: &mut World) {
-    profile!("execute_automated_systems");
+fn add_intent_systems(systems: &mut SystemRegistry) -> Result<(), SchedulerError> {
+    let stage = Stage::Intents;
+    systems
+        .add_system(
+            stage,
+            SystemDescriptor::new("attack_system_update", attack_system_update),
+        )?
+        .add_system(
+            stage,
+            SystemDescriptor::new("move_intents_update", move_intents_update),
+        )?
+        .add_system(
+            stage,
+            SystemDescriptor::new("mine_intents_update", mine_intents_update),
+        )?
+        .add_system(
+            stage,
+            SystemDescriptor::new("dropoff_intents_update", dropoff_intents_update),
+        )?
+        .add_system(
+            stage,
+            SystemDescriptor::new(
+                "upgrade_controller_intents_update",
+                upgrade_controller_intents_update,
+            ),
+        )?
+        .add_system(
+            stage,
+            SystemDescriptor::new("update_spawn_intents", update_spawn_intents),
+        )?
+        .add_system(
+            stage,
+            SystemDescriptor::new("log_intents_update", log_intents_update),
+        )?
+        .add_system(
+            stage,
+            SystemDescriptor::new("path_cache_intents_update", path_cache_intents_update),
+        )?
+        .add_system(
+            stage,
+            SystemDescriptor::new("script_history_update", script_history_update),
+        )?
+        .add_system(
+            stage,
+            SystemDescriptor::new("say_intents_update", say_intents_update),
+        )?;
+    Ok(())
+}
 
-    execute_update(decay_update, storage);
-    execute_update(death_update, storage);
-    execute_update(energy_update, storage);
-    execute_update(update_spawns, storage);
-    execute_update(mineral_update, storage);
-    execute_update(positions_update, storage);
-    execute_update(log_update, storage);
+/// Add systems that run regardless of player actions
+fn add_automated_systems(systems: &mut SystemRegistry) -> Result<(), SchedulerError> {
+    let stage = Stage::Automated;
+    systems
+        .add_system(stage, SystemDescriptor::new("decay_update", decay_update))?
+        .add_system(stage, SystemDescriptor::new("death_update", death_update))?
+        .add_system(stage, SystemDescriptor::new("energy_update", energy_update))?
+        .add_system(
+            stage,
+            SystemDescriptor::new("controller_downgrade_update", controller_downgrade_update),
+        )?
+        .add_system(stage, SystemDescriptor::new("update_spawns", update_spawns))?
+        .add_system(
+            stage,
+            SystemDescriptor::new("mineral_update", mineral_update),
+        )?
+        .add_system(
+            stage,
+            SystemDescriptor::new("positions_update", positions_update),
+        )?
+        .add_system(stage, SystemDescriptor::new("log_update", log_update))?;
+    Ok(())
 }
 
-#[inline]
-fn execute_update<'a, M, C, Sys>(sys: Sys, storage: &'a mut World)
-where
-    Sys: Fn(M, C) + 'a,
-    M: FromWorldMut + Clone + 'a,
-    C: FromWorld<'a> + 'a,
-{
-    let m = M::from_world_mut(storage);
-    let c = C::from_world(storage as &_);
-    sys(M::clone(&m), c);
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_systems_are_scheduled() {
+        let systems = default_systems();
+        for stage in Stage::ALL.iter().copied() {
+            let scheduler = systems.stage(stage);
+            let batches = scheduler.batches();
+            assert_eq!(
+                batches.iter().map(|b| b.len()).sum::<usize>(),
+                scheduler.systems().len()
+            );
+        }
+        assert!(!systems.stage(Stage::Intents).systems().is_empty());
+    }
 }
@@ -1,4 +1,6 @@
-use crate::components::{HpComponent, MeleeAttackComponent};
+use crate::components::{
+    HpComponent, MeleeAttackComponent, PositionComponent, WorldEvent, WorldEvents,
+};
 use crate::indices::*;
 use crate::intents::*;
 use crate::profile;
@@ -8,10 +10,17 @@ use tracing::{debug, error};
 type Mut = (
     UnsafeView<EntityId, HpComponent>,
     UnwrapViewMut<EmptyKey, Intents<MeleeIntent>>,
+    UnwrapViewMut<EmptyKey, WorldEvents>,
+);
+type Const<'a> = (
+    View<'a, EntityId, MeleeAttackComponent>,
+    View<'a, EntityId, PositionComponent>,
 );
-type Const<'a> = (View<'a, EntityId, MeleeAttackComponent>,);
 
-pub fn attack_system_update((mut hp_table, mut intents): Mut, (attack_table,): Const) {
+pub fn attack_system_update(
+    (mut hp_table, mut intents, mut events): Mut,
+    (attack_table, positions): Const,
+) {
     profile!("AttackSystem update");
 
     pre_process(&mut intents.0);
@@ -32,7 +41,17 @@ pub fn attack_system_update((mut hp_table, mut intents): Mut, (attack_table,): C
             }
         };
         // hp can not fall below 0
-        hp.hp -= hp.hp.min(attack.strength);
+        let damage = hp.hp.min(attack.strength);
+        hp.hp -= damage;
+
+        if let Some(PositionComponent(pos)) = positions.get(intent.defender).copied() {
+            events.push(WorldEvent::Attack {
+                attacker: intent.attacker,
+                defender: intent.defender,
+                damage,
+                pos,
+            });
+        }
     }
 }
 
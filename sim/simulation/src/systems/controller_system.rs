@@ -0,0 +1,425 @@
+use crate::components::{
+    CarryComponent, OwnedEntity, PositionComponent, RoomControllerComponent, Rooms, UserProperties,
+    CONTROLLER_DOWNGRADE_TICKS,
+};
+use crate::geometry::Axial;
+use crate::indices::*;
+use crate::intents::*;
+use crate::profile;
+use crate::storage::views::{UnsafeView, UnwrapView, View};
+use crate::tables::traits::Table;
+use tracing::{debug, trace, warn};
+
+type Mut = (
+    UnsafeView<EntityId, RoomControllerComponent>,
+    UnsafeView<EntityId, CarryComponent>,
+    UnsafeView<EntityId, OwnedEntity>,
+    UnsafeView<Axial, OwnedEntity>,
+    UnsafeView<UserId, Rooms>,
+);
+type Const<'a> = (
+    UnwrapView<'a, EmptyKey, Intents<UpgradeControllerIntent>>,
+    View<'a, EntityId, PositionComponent>,
+    View<'a, UserId, UserProperties>,
+);
+
+pub fn upgrade_controller_intents_update(
+    (mut controllers, mut carry_table, mut entity_owners, mut room_owners, mut user_rooms): Mut,
+    (intents, positions, user_props): Const,
+) {
+    profile!("UpgradeControllerSystem update");
+
+    for intent in intents.iter() {
+        trace!("Executing upgrade controller intent {:?}", intent);
+        let owner_id = match entity_owners.get(intent.bot) {
+            Some(OwnedEntity { owner_id }) => *owner_id,
+            None => {
+                warn!("Bot has no owner");
+                continue;
+            }
+        };
+        let room = match positions.get(intent.controller) {
+            Some(PositionComponent(pos)) => pos.room,
+            None => {
+                warn!("Controller has no position");
+                continue;
+            }
+        };
+        let claim = match entity_owners.get(intent.controller) {
+            Some(owner) if owner.owner_id != owner_id => {
+                debug!("Controller is owned by another user");
+                continue;
+            }
+            Some(_) => false,
+            None => {
+                // another bot of the user might have claimed a room during this tick
+                if !can_claim_room(owner_id, View::from_table(&*user_rooms), user_props) {
+                    debug!("User can not claim more rooms");
+                    continue;
+                }
+                true
+            }
+        };
+        let carry = match carry_table.get_mut(intent.bot) {
+            Some(x) => x,
+            None => {
+                warn!("Bot has no carry");
+                continue;
+            }
+        };
+        let controller = match controllers.get_mut(intent.controller) {
+            Some(x) => x,
+            None => {
+                warn!("Target is not a controller");
+                continue;
+            }
+        };
+
+        let used = controller.upgrade(carry.carry as u32);
+        carry.carry -= used as u16;
+
+        if claim {
+            debug!("User {:?} claimed room {:?}", owner_id, room);
+            entity_owners.insert(intent.controller, OwnedEntity { owner_id });
+            room_owners.insert(room, OwnedEntity { owner_id });
+            let mut rooms = user_rooms.get(owner_id).cloned().unwrap_or_default();
+            rooms.0.push(Room(room));
+            user_rooms.insert(owner_id, rooms);
+        }
+    }
+}
+
+type DowngradeMut = (
+    UnsafeView<EntityId, RoomControllerComponent>,
+    UnsafeView<EntityId, OwnedEntity>,
+    UnsafeView<Axial, OwnedEntity>,
+    UnsafeView<UserId, Rooms>,
+);
+type DowngradeConst<'a> = (View<'a, EntityId, PositionComponent>,);
+
+/// Controllers lose a level if they're not upgraded for `CONTROLLER_DOWNGRADE_TICKS`.
+/// Owners lose the room when the controller drops to level 0.
+pub fn controller_downgrade_update(
+    (mut controllers, mut entity_owners, mut room_owners, mut user_rooms): DowngradeMut,
+    (positions,): DowngradeConst,
+) {
+    profile!("ControllerDowngradeSystem update");
+
+    let mut lost = Vec::new();
+    for (id, controller) in controllers.iter_mut() {
+        if controller.level == 0 {
+            continue;
+        }
+        controller.downgrade_timer = controller.downgrade_timer.saturating_sub(1);
+        if controller.downgrade_timer > 0 {
+            continue;
+        }
+        controller.level -= 1;
+        controller.progress = 0;
+        if controller.level > 0 {
+            controller.downgrade_timer = CONTROLLER_DOWNGRADE_TICKS;
+        } else {
+            lost.push(id);
+        }
+    }
+
+    for id in lost {
+        let owner = entity_owners.delete(id);
+        let room = positions.get(id).map(|PositionComponent(pos)| pos.room);
+        debug!("Controller {:?} in room {:?} lost its owner", id, room);
+        if let Some(room) = room {
+            room_owners.delete(room);
+            if let Some(rooms) = owner.and_then(|o| user_rooms.get_by_id_mut(o.owner_id)) {
+                rooms.0.retain(|r| r.0 != room);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prelude::*;
+    use crate::query;
+
+    #[test]
+    fn neglected_controllers_are_lost() {
+        let mut world =
+            futures_lite::future::block_on(SimpleExecutor::default().initialize(GameConfig {
+                world_radius: 1,
+                room_radius: 10,
+                ..Default::default()
+            }));
+        let room = Axial::new(1, 1);
+        let user = UserId(uuid::Uuid::new_v4());
+        let id = world.insert_entity();
+        query!(
+            mutate
+            world
+            {
+                EntityId, RoomControllerComponent, .insert(id, RoomControllerComponent {
+                    level: 2,
+                    progress: 100,
+                    downgrade_timer: 1,
+                });
+                EntityId, PositionComponent, .insert(id, PositionComponent(WorldPosition {
+                    room,
+                    pos: Axial::new(2, 2),
+                }));
+                EntityId, OwnedEntity, .insert(id, OwnedEntity { owner_id: user });
+                Axial, OwnedEntity, .insert(room, OwnedEntity { owner_id: user });
+                UserId, Rooms, .insert(user, Rooms(vec![Room(room)]));
+            }
+        );
+
+        let update = |world: &mut World| {
+            controller_downgrade_update(
+                FromWorldMut::from_world_mut(world),
+                FromWorld::from_world(world),
+            )
+        };
+
+        update(&mut world);
+        let controller = *world
+            .view::<EntityId, RoomControllerComponent>()
+            .get(id)
+            .unwrap();
+        assert_eq!(controller.level, 1);
+        assert_eq!(controller.progress, 0);
+        assert_eq!(controller.downgrade_timer, CONTROLLER_DOWNGRADE_TICKS);
+
+        for _ in 0..CONTROLLER_DOWNGRADE_TICKS {
+            update(&mut world);
+        }
+        let controller = *world
+            .view::<EntityId, RoomControllerComponent>()
+            .get(id)
+            .unwrap();
+        assert_eq!(controller.level, 0);
+        assert!(world.view::<EntityId, OwnedEntity>().get(id).is_none());
+        assert!(!world.view::<Axial, OwnedEntity>().contains_key(room));
+        assert!(world
+            .view::<UserId, Rooms>()
+            .get(user)
+            .unwrap()
+            .0
+            .is_empty());
+    }
+
+    /// World with a bot of `user` carrying `carry` energy next to an unowned controller
+    fn setup_upgrade(user: UserId, carry: u16) -> (World, EntityId, EntityId) {
+        let mut world =
+            futures_lite::future::block_on(SimpleExecutor::default().initialize(GameConfig {
+                world_radius: 1,
+                room_radius: 10,
+                ..Default::default()
+            }));
+        let room = Axial::new(1, 1);
+        let bot = world.insert_entity();
+        let controller = world.insert_entity();
+        crate::entity_archetypes::init_room_controller(
+            controller,
+            WorldPosition {
+                room,
+                pos: Axial::new(2, 2),
+            },
+            &mut world,
+        );
+        query!(
+            mutate
+            world
+            {
+                EntityId, Bot, .insert(bot);
+                EntityId, OwnedEntity, .insert(bot, OwnedEntity { owner_id: user });
+                EntityId, CarryComponent, .insert(bot, CarryComponent {
+                    carry,
+                    carry_max: carry,
+                });
+                EntityId, PositionComponent, .insert(bot, PositionComponent(WorldPosition {
+                    room,
+                    pos: Axial::new(2, 3),
+                }));
+                UserId, UserProperties, .insert(user, UserProperties { level: 1 });
+            }
+        );
+        world.resources.upgrade_controller_intents.value =
+            Some(Intents(vec![UpgradeControllerIntent { bot, controller }]));
+        (world, bot, controller)
+    }
+
+    fn run_upgrade(world: &mut World) {
+        upgrade_controller_intents_update(
+            FromWorldMut::from_world_mut(world),
+            FromWorld::from_world(world),
+        );
+    }
+
+    #[test]
+    fn bots_claim_unowned_controllers() {
+        let user = UserId(uuid::Uuid::new_v4());
+        let (mut world, bot, controller) = setup_upgrade(user, 50);
+        let room = Axial::new(1, 1);
+
+        run_upgrade(&mut world);
+
+        assert_eq!(
+            world
+                .view::<EntityId, OwnedEntity>()
+                .get(controller)
+                .map(|o| o.owner_id),
+            Some(user)
+        );
+        assert_eq!(
+            world
+                .view::<Axial, OwnedEntity>()
+                .at(room)
+                .map(|o| o.owner_id),
+            Some(user)
+        );
+        assert_eq!(
+            world.view::<UserId, Rooms>().get(user).unwrap().0,
+            vec![Room(room)]
+        );
+        let c = *world
+            .view::<EntityId, RoomControllerComponent>()
+            .get(controller)
+            .unwrap();
+        assert_eq!(c.level, 1);
+        assert_eq!(c.progress, 50);
+        assert_eq!(c.downgrade_timer, CONTROLLER_DOWNGRADE_TICKS);
+        assert_eq!(
+            world
+                .view::<EntityId, CarryComponent>()
+                .get(bot)
+                .unwrap()
+                .carry,
+            0
+        );
+    }
+
+    #[test]
+    fn users_can_not_claim_more_rooms_than_their_level() {
+        let user = UserId(uuid::Uuid::new_v4());
+        let (mut world, bot, controller) = setup_upgrade(user, 50);
+        world
+            .unsafe_view::<UserId, Rooms>()
+            .insert(user, Rooms(vec![Room(Axial::new(0, 1))]));
+
+        run_upgrade(&mut world);
+
+        assert!(world
+            .view::<EntityId, OwnedEntity>()
+            .get(controller)
+            .is_none());
+        assert!(!world
+            .view::<Axial, OwnedEntity>()
+            .contains_key(Axial::new(1, 1)));
+        assert_eq!(world.view::<UserId, Rooms>().get(user).unwrap().0.len(), 1);
+        assert_eq!(
+            world
+                .view::<EntityId, RoomControllerComponent>()
+                .get(controller)
+                .unwrap()
+                .level,
+            0
+        );
+        assert_eq!(
+            world
+                .view::<EntityId, CarryComponent>()
+                .get(bot)
+                .unwrap()
+                .carry,
+            50
+        );
+    }
+
+    #[test]
+    fn bots_keep_the_energy_the_controller_can_not_use() {
+        let user = UserId(uuid::Uuid::new_v4());
+        let (mut world, bot, controller) = setup_upgrade(user, 50);
+        world
+            .unsafe_view::<EntityId, OwnedEntity>()
+            .insert(controller, OwnedEntity { owner_id: user });
+        // 10 energy short of the max level
+        let mut c = RoomControllerComponent {
+            level: CONTROLLER_MAX_LEVEL - 1,
+            ..Default::default()
+        };
+        c.progress = c.progress_max() - 10;
+        world
+            .unsafe_view::<EntityId, RoomControllerComponent>()
+            .insert(controller, c);
+
+        run_upgrade(&mut world);
+
+        let c = *world
+            .view::<EntityId, RoomControllerComponent>()
+            .get(controller)
+            .unwrap();
+        assert_eq!(c.level, CONTROLLER_MAX_LEVEL);
+        assert_eq!(
+            world
+                .view::<EntityId, CarryComponent>()
+                .get(bot)
+                .unwrap()
+                .carry,
+            40
+        );
+    }
+
+    #[test]
+    fn starting_rooms_are_claimed() {
+        let mut world =
+            futures_lite::future::block_on(SimpleExecutor::default().initialize(GameConfig {
+                world_radius: 1,
+                room_radius: 10,
+                ..Default::default()
+            }));
+        crate::init::init_world_entities(&mut world, 1);
+
+        let (room, user) = world
+            .view::<EntityId, SpawnComponent>()
+            .iter()
+            .map(|(id, _)| {
+                let pos = world
+                    .view::<EntityId, PositionComponent>()
+                    .get(id)
+                    .unwrap()
+                    .0;
+                let owner = world
+                    .view::<EntityId, OwnedEntity>()
+                    .get(id)
+                    .unwrap()
+                    .owner_id;
+                (pos.room, owner)
+            })
+            .next()
+            .expect("Failed to find the spawn of the user");
+
+        let controllers = world.view::<EntityId, RoomControllerComponent>();
+        let positions = world.view::<EntityId, PositionComponent>();
+        let owners = world.view::<EntityId, OwnedEntity>();
+        for (id, c) in controllers.iter() {
+            let owner = owners.get(id).map(|o| o.owner_id);
+            if positions.get(id).unwrap().0.room == room {
+                assert_eq!(owner, Some(user));
+                assert_eq!(c.level, 1);
+                assert_eq!(c.downgrade_timer, CONTROLLER_DOWNGRADE_TICKS);
+            } else {
+                assert_eq!(owner, None);
+                assert_eq!(c.level, 0);
+            }
+        }
+        assert_eq!(
+            world
+                .view::<Axial, OwnedEntity>()
+                .at(room)
+                .map(|o| o.owner_id),
+            Some(user)
+        );
+        assert_eq!(
+            world.view::<UserId, Rooms>().get(user).unwrap().0,
+            vec![Room(room)]
+        );
+    }
+}
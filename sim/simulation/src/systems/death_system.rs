@@ -1,16 +1,28 @@
-use crate::components::HpComponent;
+use crate::components::{HpComponent, PositionComponent, WorldEvent, WorldEvents};
 use crate::indices::*;
 use crate::profile;
-use crate::storage::views::{DeferredDeleteEntityView, View};
+use crate::storage::views::{DeferredDeleteEntityView, UnwrapViewMut, View};
 use tracing::{debug, trace};
 
-pub fn death_update(mut delete: DeferredDeleteEntityView, (hps,): (View<EntityId, HpComponent>,)) {
+type Mut = (
+    DeferredDeleteEntityView,
+    UnwrapViewMut<EmptyKey, WorldEvents>,
+);
+type Const<'a> = (
+    View<'a, EntityId, HpComponent>,
+    View<'a, EntityId, PositionComponent>,
+);
+
+pub fn death_update((mut delete, mut events): Mut, (hps, positions): Const) {
     profile!("DeathSystem update");
     debug!("update death system called");
 
     hps.iter().for_each(|(id, hp)| {
         if hp.hp == 0 {
             trace!("Entity {:?} has died, deleting", id);
+            if let Some(PositionComponent(pos)) = positions.get(id).copied() {
+                events.push(WorldEvent::Death { entity: id, pos });
+            }
             unsafe {
                 delete.delete_entity(id);
             }
@@ -69,4 +81,37 @@ mod tests {
 
         assert_eq!(entities, vec![entity_2]);
     }
+
+    #[test]
+    fn test_death_event_is_recorded() {
+        let mut store = World::new();
+
+        let entity = store.insert_entity();
+        let pos = WorldPosition {
+            room: crate::prelude::Axial::new(1, 2),
+            pos: crate::prelude::Axial::new(3, 4),
+        };
+        query!(
+            mutate
+            store
+            {
+                EntityId, HpComponent, .insert(entity, HpComponent {
+                    hp: 0,
+                    hp_max: 123
+                });
+                EntityId, PositionComponent, .insert(entity, PositionComponent(pos));
+            }
+        );
+
+        death_update(
+            FromWorldMut::from_world_mut(&mut store),
+            FromWorld::from_world(&mut store),
+        );
+
+        let events = store.view::<EmptyKey, WorldEvents>();
+        assert_eq!(
+            events.value.as_ref().unwrap().as_slice(),
+            &[WorldEvent::Death { entity, pos }]
+        );
+    }
 }
@@ -1,24 +1,13 @@
 use crate::components::{DecayComponent, HpComponent};
-use crate::indices::EntityId;
-use crate::join;
 use crate::profile;
-use crate::storage::views::UnsafeView;
-use crate::tables::JoinIterator;
+use crate::storage::query::Query;
 use tracing::{debug, trace};
 
-pub fn decay_update(
-    (mut hps, mut decays): (
-        UnsafeView<EntityId, HpComponent>,
-        UnsafeView<EntityId, DecayComponent>,
-    ),
-    (): (),
-) {
+pub fn decay_update<'a>(decays: Query<'a, (&'a mut DecayComponent, &'a mut HpComponent)>, (): ()) {
     profile!("DecaySystem update");
     debug!("update decay system called");
 
-    let decays = decays.iter_mut();
-    let hps = hps.iter_mut();
-    join!([decays, hps]).for_each(
+    decays.iter().for_each(
         |(
             _id,
             (
@@ -1,20 +1,27 @@
-use crate::components::{CarryComponent, DropoffEventComponent, EnergyComponent};
+use crate::components::{
+    CarryComponent, DropoffEventComponent, EnergyComponent, PositionComponent, WorldEvent,
+    WorldEvents,
+};
 use crate::indices::*;
 use crate::intents::*;
 use crate::profile;
-use crate::storage::views::{UnsafeView, UnwrapView};
+use crate::storage::views::{UnsafeView, UnwrapView, UnwrapViewMut, View};
 use tracing::{trace, warn};
 
 type Mut = (
     UnsafeView<EntityId, EnergyComponent>,
     UnsafeView<EntityId, CarryComponent>,
     UnsafeView<EntityId, DropoffEventComponent>,
+    UnwrapViewMut<EmptyKey, WorldEvents>,
+);
+type Const<'a> = (
+    UnwrapView<'a, EmptyKey, Intents<DropoffIntent>>,
+    View<'a, EntityId, PositionComponent>,
 );
-type Const<'a> = (UnwrapView<'a, EmptyKey, Intents<DropoffIntent>>,);
 
 pub fn dropoff_intents_update(
-    (mut energy_table, mut carry_table, mut events): Mut,
-    (intents,): Const,
+    (mut energy_table, mut carry_table, mut events, mut world_events): Mut,
+    (intents, positions): Const,
 ) {
     profile!("DropoffSystem update");
 
@@ -45,5 +52,13 @@ pub fn dropoff_intents_update(
         carry_component.carry -= dropoff;
 
         events.insert(intent.bot, DropoffEventComponent(intent.structure));
+        if let Some(PositionComponent(pos)) = positions.get(intent.bot).copied() {
+            world_events.push(WorldEvent::Dropoff {
+                bot: intent.bot,
+                structure: intent.structure,
+                amount: dropoff,
+                pos,
+            });
+        }
     }
 }
@@ -1,24 +1,30 @@
-use crate::components::LogEntry;
+use crate::components::{game_config::GameConfig, LogArchives, LogEntry};
 use crate::indices::*;
 use crate::intents::{Intents, LogIntent};
 use crate::profile;
-use crate::storage::views::{UnsafeView, UnwrapViewMut};
+use crate::storage::views::{UnsafeView, UnwrapView, UnwrapViewMut};
 use crate::tables::Table;
 use std::mem::take;
 use tracing::trace;
 
 type Mut = (
     UnsafeView<EntityTime, LogEntry>,
+    UnwrapViewMut<EmptyKey, LogArchives>,
     UnwrapViewMut<EmptyKey, Intents<LogIntent>>,
 );
+type Const<'a> = (UnwrapView<'a, ConfigKey, GameConfig>,);
 
-pub fn log_intents_update((mut log_table, mut intents): Mut, (): ()) {
+pub fn log_intents_update((mut log_table, mut archive, mut intents): Mut, (config,): Const) {
     profile!("LogIntentSystem update");
 
     let intents = take(&mut intents.0);
+    let archive_size = config.log_archive_size;
 
     for intent in intents {
         trace!("inserting log entry {:?}", intent);
+        if archive_size > 0 {
+            archive.push(intent.entity, intent.time, &intent.payload, archive_size);
+        }
         let id = EntityTime(intent.entity, intent.time);
         // use delete to move out of the data structure, then we'll move it back in
         // this should be cheaper than cloning all the time, because of the inner vectors
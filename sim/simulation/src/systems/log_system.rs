@@ -1,19 +1,23 @@
-use crate::components::LogEntry;
-use crate::indices::EntityTime;
+use crate::components::{game_config::GameConfig, LogArchives, LogEntry};
+use crate::indices::{ConfigKey, EmptyKey, EntityTime};
 use crate::profile;
-use crate::storage::views::UnsafeView;
+use crate::storage::views::{UnsafeView, UnwrapView, UnwrapViewMut};
 use crate::tables::Table;
 use crate::Time;
 
-type Mut = UnsafeView<EntityTime, LogEntry>;
-type Const = Time;
+type Mut = (
+    UnsafeView<EntityTime, LogEntry>,
+    UnwrapViewMut<EmptyKey, LogArchives>,
+);
+type Const<'a> = (Time, UnwrapView<'a, ConfigKey, GameConfig>);
 
-pub fn log_update(mut logs: Mut, time: Const) {
+pub fn log_update((mut logs, mut archives): Mut, (time, config): Const) {
     profile!("LogSystem update");
     // clear the old logs
+    let retention = config.log_retention;
     let changeset = logs
         .iter()
-        .filter_map(|(id, _)| (id.1 < time.0.max(5) - 5).then(|| id))
+        .filter_map(|(id, _)| (id.1 + retention < time.0).then(|| id))
         .collect::<Vec<_>>();
 
     // we delete in the same table we iterated on above
@@ -21,4 +25,6 @@ pub fn log_update(mut logs: Mut, time: Const) {
     for id in changeset {
         logs.delete(id);
     }
+
+    archives.prune(time.0.saturating_sub(config.log_archive_age));
 }
@@ -1,10 +1,11 @@
 use crate::components::{
-    CarryComponent, EnergyComponent, MineEventComponent, Resource, ResourceComponent,
+    CarryComponent, EnergyComponent, MineEventComponent, PositionComponent, Resource,
+    ResourceComponent, WorldEvent, WorldEvents,
 };
 use crate::indices::*;
 use crate::intents::{Intents, MineIntent};
 use crate::profile;
-use crate::storage::views::{UnsafeView, UnwrapView, View};
+use crate::storage::views::{UnsafeView, UnwrapView, UnwrapViewMut, View};
 use tracing::{trace, warn};
 
 pub const MINE_AMOUNT: u16 = 10; // TODO: get from bot body
@@ -13,15 +14,17 @@ type Mut = (
     UnsafeView<EntityId, EnergyComponent>,
     UnsafeView<EntityId, CarryComponent>,
     UnsafeView<EntityId, MineEventComponent>,
+    UnwrapViewMut<EmptyKey, WorldEvents>,
 );
 type Const<'a> = (
     View<'a, EntityId, ResourceComponent>,
     UnwrapView<'a, EmptyKey, Intents<MineIntent>>,
+    View<'a, EntityId, PositionComponent>,
 );
 
 pub fn mine_intents_update(
-    (mut energy_table, mut carry_table, mut event): Mut,
-    (resource_table, intents): Const,
+    (mut energy_table, mut carry_table, mut event, mut world_events): Mut,
+    (resource_table, intents, positions): Const,
 ) {
     profile!("MineSystem update");
 
@@ -59,6 +62,14 @@ pub fn mine_intents_update(
                 resource_energy.energy -= mined;
 
                 event.insert(intent.bot, MineEventComponent(intent.resource));
+                if let Some(PositionComponent(pos)) = positions.get(intent.bot).copied() {
+                    world_events.push(WorldEvent::Mine {
+                        bot: intent.bot,
+                        resource: intent.resource,
+                        amount: mined,
+                        pos,
+                    });
+                }
 
                 trace!(
                     "Mine succeeded new bot carry {:?} new resource energy {:?}",
@@ -1,44 +1,44 @@
-use crate::indices::{EntityId, WorldPosition};
+use crate::components as comp;
+use crate::indices::WorldPosition;
 use crate::profile;
-use crate::storage::views::{DeferredDeleteEntityView, UnsafeView, View};
-use crate::tables::JoinIterator;
-use crate::{components as comp, join};
+use crate::storage::query::Query;
+use crate::storage::views::{DeferredDeleteEntityView, View};
 use crate::{geometry::Axial, terrain::TileTerrainType};
 use rand::Rng;
 use tracing::{debug, error, trace};
 
-type Mut = (
-    UnsafeView<EntityId, comp::PositionComponent>,
-    UnsafeView<EntityId, comp::EnergyComponent>,
-    UnsafeView<EntityId, comp::RespawnTimer>,
+type Mut<'a> = (
+    Query<
+        'a,
+        (
+            &'a comp::ResourceComponent,
+            &'a mut comp::PositionComponent,
+            &'a mut comp::EnergyComponent,
+            &'a mut comp::RespawnTimer,
+        ),
+    >,
     DeferredDeleteEntityView,
 );
 type Const<'a> = (
     View<'a, WorldPosition, comp::EntityComponent>,
     View<'a, WorldPosition, comp::TerrainComponent>,
-    View<'a, EntityId, comp::ResourceComponent>,
 );
 
 pub fn mineral_update(
-    (mut entity_positions, mut energy, mut respawn_timer, mut delete_entity_deferred): Mut,
-    (position_entities, terrain_table, resources): Const,
+    (minerals, mut delete_entity_deferred): Mut,
+    (position_entities, terrain_table): Const,
 ) {
     profile!("Mineral System update");
     debug!("update minerals system called");
 
     let mut rng = rand::thread_rng();
 
-    let minerals_it = resources
-        .iter()
-        .filter(|(_, r)| matches!(r.0, comp::Resource::Energy));
-    let entity_positions_it = entity_positions.iter_mut();
-    let energy_iter = energy.iter_mut();
-    let respawn_timer = respawn_timer.iter_mut();
-
     // in case of an error we need to clean up the mineral
     // however best not to clean it inside the iterator, hmmm???
-    join!([minerals_it, entity_positions_it, energy_iter, respawn_timer]).for_each(
-        |(id, (_resource, position, energy, respawn))| {
+    minerals
+        .iter()
+        .filter(|(_, (resource, ..))| matches!(resource.0, comp::Resource::Energy))
+        .for_each(|(id, (_resource, position, energy, respawn))| {
             trace!(
                 "updating {:?} {:?} {:?} {:?} {:?}",
                 id,
@@ -100,8 +100,7 @@ pub fn mineral_update(
                     }
                 }
             }
-        },
-    );
+        });
 
     debug!("update minerals system done");
 }
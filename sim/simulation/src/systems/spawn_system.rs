@@ -10,10 +10,11 @@ mod spawn_intent_system;
 pub use continous_spawn_system::update as update_cont_spawns;
 pub use spawn_intent_system::update as update_spawn_intents;
 
-use crate::indices::{EntityId, UserId};
+use crate::indices::{EmptyKey, EntityId, UserId, WorldPosition};
 use crate::join;
 use crate::profile;
-use crate::storage::views::{UnsafeView, View};
+use crate::storage::relations::{remove_parent, RelationViews};
+use crate::storage::views::{UnsafeView, UnwrapViewMut, View};
 use crate::tables::{JoinIterator, Table};
 use crate::{components::*, entity_archetypes::init_bot};
 use tracing::{trace, warn};
@@ -32,10 +33,12 @@ type SpawnSystemMut = (
         UnsafeView<EntityId, OwnedEntity>,
         UnsafeView<EntityId, EntityScript>,
     ),
+    UnwrapViewMut<EmptyKey, WorldEvents>,
+    RelationViews,
 );
 
 pub fn update_spawns(
-    (mut spawns, mut spawn_queue, mut energy, spawn_views): SpawnSystemMut,
+    (mut spawns, mut spawn_queue, mut energy, spawn_views, mut events, relations): SpawnSystemMut,
     user_default_scripts: View<UserId, EntityScript>,
 ) {
     profile!("SpawnSystem update");
@@ -64,7 +67,15 @@ pub fn update_spawns(
             }
         })
         .for_each(|(spawn_id, entity_id)| {
-            spawn_bot(spawn_id, entity_id, spawn_views, user_default_scripts)
+            if let Some(pos) = spawn_bot(spawn_id, entity_id, spawn_views, user_default_scripts) {
+                // spawned bots outlive their spawn
+                remove_parent(relations, entity_id);
+                events.push(WorldEvent::Spawn {
+                    spawn: spawn_id,
+                    bot: entity_id,
+                    pos,
+                });
+            }
         });
 }
 
@@ -81,12 +92,14 @@ type SpawnBotMut = (
 
 /// Spawns a bot from a spawn.
 /// Removes the spawning bot from the spawn and initializes a bot in the world
+///
+/// Returns the position of the new bot
 fn spawn_bot(
     spawn_id: EntityId,
     entity_id: EntityId,
     (mut spawn_bots, bots, hps, decay, carry, positions, owned, script_table): SpawnBotMut,
     user_default_scripts: View<UserId, EntityScript>,
-) {
+) -> Option<WorldPosition> {
     trace!(
         "spawn_bot spawn_id: {:?} entity_id: {:?}",
         spawn_id,
@@ -97,7 +110,7 @@ fn spawn_bot(
         Some(_) => (),
         None => {
             warn!("Spawning bot {:?} was not found", entity_id);
-            return;
+            return None;
         }
     };
 
@@ -124,4 +137,5 @@ fn spawn_bot(
         spawn_id,
         entity_id
     );
+    Some(pos)
 }
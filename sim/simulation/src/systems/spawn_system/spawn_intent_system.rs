@@ -2,6 +2,7 @@ use crate::components::{Bot, OwnedEntity, SpawnBotComponent, SpawnQueueComponent
 use crate::indices::*;
 use crate::intents::{Intents, SpawnIntent};
 use crate::profile;
+use crate::storage::relations::{set_parent, RelationViews};
 use crate::storage::views::{InsertEntityView, UnsafeView, UnwrapView};
 use tracing::{debug, trace};
 
@@ -10,12 +11,13 @@ type Mut = (
     UnsafeView<EntityId, SpawnQueueComponent>,
     UnsafeView<EntityId, OwnedEntity>,
     InsertEntityView,
+    RelationViews,
 );
 
 type Const<'a> = (UnwrapView<'a, EmptyKey, Intents<SpawnIntent>>,);
 
 pub fn update(
-    (mut spawn_bot_table, mut spawn_queue, mut owner_table, mut insert_entity): Mut,
+    (mut spawn_bot_table, mut spawn_queue, mut owner_table, mut insert_entity, relations): Mut,
     (intents,): Const,
 ) {
     profile!("SpawnSystem update");
@@ -48,5 +50,7 @@ pub fn update(
             owner_table.insert(bot_id, OwnedEntity { owner_id });
         }
         spawn.queue.push_back(bot_id);
+        // the bot is deleted if the spawn is deleted before spawning it
+        set_parent(relations, bot_id, intent.spawn_id);
     }
 }
@@ -1,3 +1,4 @@
+use super::change_tracking::{ChangeTicks, ChangedRows};
 use super::*;
 use crate::components::LogEntry;
 use crate::indices::EntityTime;
@@ -11,6 +12,8 @@ where
     Row: TableRow,
 {
     data: BTreeMap<Id, Row>,
+    #[serde(skip)]
+    changes: ChangeTicks<Id>,
 }
 
 impl<Id, Row> BTreeTable<Id, Row>
@@ -21,6 +24,7 @@ where
     pub fn new() -> Self {
         Self {
             data: BTreeMap::new(),
+            changes: Default::default(),
         }
     }
 
@@ -28,16 +32,29 @@ where
         self.data.iter().map(|(id, row)| (*id, row))
     }
 
+    /// Every row visited is recorded as modified, if change tracking is enabled
     pub fn iter_mut(&mut self) -> impl TableIterator<Id, &mut Row> {
-        self.data.iter_mut().map(|(id, row)| (*id, row))
+        let changes = &mut self.changes;
+        self.data.iter_mut().map(move |(id, row)| {
+            changes.modified(*id);
+            (*id, row)
+        })
     }
 
     pub fn get(&self, id: Id) -> Option<&Row> {
         self.data.get(&id)
     }
 
+    /// Rows accessed mutably are recorded as modified, if change tracking is enabled
     pub fn get_by_id_mut(&mut self, id: Id) -> Option<&mut Row> {
-        self.data.get_mut(&id)
+        let row = self.data.get_mut(&id)?;
+        self.changes.modified(id);
+        Some(row)
+    }
+
+    /// Iterate over the rows with ids in the given range, in ascending order
+    pub fn range(&self, range: impl std::ops::RangeBounds<Id>) -> impl TableIterator<Id, &Row> {
+        self.data.range(range).map(|(id, row)| (*id, row))
     }
 
     pub fn get_by_ids(&self, ids: &[Id]) -> Vec<(Id, &Row)> {
@@ -53,7 +70,13 @@ where
     }
 
     pub fn insert(&mut self, id: Id, row: Row) -> Option<Row> {
-        self.data.insert(id, row)
+        let existing = self.data.insert(id, row);
+        if existing.is_some() {
+            self.changes.modified(id);
+        } else {
+            self.changes.inserted(id);
+        }
+        existing
     }
 
     pub fn len(&self) -> usize {
@@ -65,6 +88,11 @@ where
     }
 
     pub fn clear(&mut self) {
+        if self.changes.is_enabled() {
+            for id in self.data.keys() {
+                self.changes.removed(*id);
+            }
+        }
         self.data.clear();
     }
 }
@@ -78,12 +106,36 @@ where
     type Row = Row;
 
     fn delete(&mut self, id: Id) -> Option<Row> {
-        self.data.remove(&id)
+        let row = self.data.remove(&id)?;
+        self.changes.removed(id);
+        Some(row)
     }
 
     fn get(&self, id: Id) -> Option<&Row> {
         BTreeTable::get(self, id)
     }
+
+    fn set_change_tick(&mut self, tick: u64) {
+        self.changes.set_tick(tick);
+    }
+
+    fn prune_changes(&mut self, before: u64) {
+        self.changes.prune(before);
+    }
+}
+
+impl<Id, Row> ChangedRows for BTreeTable<Id, Row>
+where
+    Id: TableId,
+    Row: TableRow,
+{
+    fn change_ticks(&self) -> &ChangeTicks<Id> {
+        &self.changes
+    }
+
+    fn set_change_tracking(&mut self, enabled: bool) {
+        self.changes.set_enabled(enabled);
+    }
 }
 
 impl LogTable for BTreeTable<EntityTime, LogEntry> {
@@ -0,0 +1,215 @@
+//! Optional per-row change ticks of tables.
+//!
+//! Tables supporting change tracking record the tick of the last insertion, modification or
+//! removal of their rows, so consumers may process only the rows that changed since a given
+//! tick. Tracking is disabled by default.
+//!
+use super::{Table, TableId};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ChangeKind {
+    Inserted,
+    Modified,
+    Removed,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Change {
+    /// Tick of the last change
+    pub tick: u64,
+    pub kind: ChangeKind,
+}
+
+#[derive(Debug, Clone)]
+pub struct ChangeTicks<Id: TableId> {
+    enabled: bool,
+    tick: u64,
+    changes: BTreeMap<Id, Change>,
+}
+
+impl<Id: TableId> Default for ChangeTicks<Id> {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            tick: 0,
+            changes: BTreeMap::new(),
+        }
+    }
+}
+
+impl<Id: TableId> ChangeTicks<Id> {
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Disabling tracking drops the recorded changes
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+        if !enabled {
+            self.changes.clear();
+        }
+    }
+
+    pub fn tick(&self) -> u64 {
+        self.tick
+    }
+
+    pub fn set_tick(&mut self, tick: u64) {
+        self.tick = tick;
+    }
+
+    #[inline]
+    pub fn inserted(&mut self, id: Id) {
+        if !self.enabled {
+            return;
+        }
+        let tick = self.tick;
+        let kind = match self.changes.get(&id) {
+            // removed and re-inserted
+            Some(Change {
+                kind: ChangeKind::Removed,
+                ..
+            }) => ChangeKind::Modified,
+            Some(Change {
+                kind: ChangeKind::Inserted,
+                tick: t,
+            }) if *t == tick => ChangeKind::Inserted,
+            Some(_) => ChangeKind::Modified,
+            None => ChangeKind::Inserted,
+        };
+        self.changes.insert(id, Change { tick, kind });
+    }
+
+    #[inline]
+    pub fn modified(&mut self, id: Id) {
+        if !self.enabled {
+            return;
+        }
+        let tick = self.tick;
+        let change = self.changes.entry(id).or_insert(Change {
+            tick,
+            kind: ChangeKind::Modified,
+        });
+        // rows inserted in this tick remain `Inserted`
+        if change.kind != ChangeKind::Inserted || change.tick != tick {
+            change.kind = ChangeKind::Modified;
+        }
+        change.tick = tick;
+    }
+
+    #[inline]
+    pub fn removed(&mut self, id: Id) {
+        if !self.enabled {
+            return;
+        }
+        let tick = self.tick;
+        match self.changes.get(&id) {
+            // the row never existed as far as the consumers are concerned
+            Some(Change {
+                kind: ChangeKind::Inserted,
+                tick: t,
+            }) if *t == tick => {
+                self.changes.remove(&id);
+            }
+            _ => {
+                self.changes.insert(
+                    id,
+                    Change {
+                        tick,
+                        kind: ChangeKind::Removed,
+                    },
+                );
+            }
+        }
+    }
+
+    /// Iterate over the rows changed at or after tick `since`, in ascending order of their ids
+    pub fn since(&self, since: u64) -> impl Iterator<Item = (Id, Change)> + '_ {
+        self.changes
+            .iter()
+            .filter(move |(_, c)| c.tick >= since)
+            .map(|(id, c)| (*id, *c))
+    }
+
+    pub fn get(&self, id: Id) -> Option<Change> {
+        self.changes.get(&id).copied()
+    }
+
+    /// Forget the changes made before tick `before`
+    pub fn prune(&mut self, before: u64) {
+        self.changes.retain(|_, c| c.tick >= before);
+    }
+
+    pub fn len(&self) -> usize {
+        self.changes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+}
+
+/// Tables that can record the ticks their rows changed in.
+pub trait ChangedRows: Table {
+    fn change_ticks(&self) -> &ChangeTicks<Self::Id>;
+
+    fn set_change_tracking(&mut self, enabled: bool);
+
+    /// Iterate over the ids of the rows changed at or after tick `since`
+    fn changes_since(&self, since: u64) -> Box<dyn Iterator<Item = (Self::Id, Change)> + '_> {
+        Box::new(self.change_ticks().since(since))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_change_kinds() {
+        let mut changes = ChangeTicks::<u32>::default();
+        changes.inserted(1);
+        assert!(changes.is_empty(), "tracking is disabled by default");
+
+        changes.set_enabled(true);
+        changes.set_tick(3);
+        changes.inserted(1);
+        changes.modified(1);
+        changes.inserted(2);
+        changes.removed(2);
+        changes.inserted(3);
+        assert_eq!(
+            changes.get(1).map(|c| c.kind),
+            Some(ChangeKind::Inserted),
+            "modifications in the tick of insertion keep the row inserted"
+        );
+        assert_eq!(changes.get(2), None);
+
+        changes.set_tick(4);
+        changes.modified(1);
+        changes.removed(3);
+        assert_eq!(
+            changes.get(1),
+            Some(Change {
+                tick: 4,
+                kind: ChangeKind::Modified
+            })
+        );
+        assert_eq!(changes.get(3).map(|c| c.kind), Some(ChangeKind::Removed));
+
+        changes.set_tick(5);
+        changes.inserted(3);
+        assert_eq!(changes.get(3).map(|c| c.kind), Some(ChangeKind::Modified));
+
+        changes.modified(4);
+        let ids: Vec<_> = changes.since(5).map(|(id, _)| id).collect();
+        assert_eq!(ids, vec![3, 4]);
+
+        changes.prune(5);
+        assert_eq!(changes.len(), 2);
+    }
+}
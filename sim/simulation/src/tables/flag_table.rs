@@ -22,6 +22,14 @@ where
         self.ids.binary_search(id).is_ok()
     }
 
+    pub fn len(&self) -> usize {
+        self.ids.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ids.is_empty()
+    }
+
     pub fn iter(&self) -> impl TableIterator<Id, ()> + '_ {
         self.ids.iter().map(move |id| (*id, ()))
     }
@@ -2,6 +2,7 @@
 //! Tables are generic collections that store game data split by [shape] components.
 //!
 pub mod btree_table;
+pub mod change_tracking;
 pub mod flag_table;
 pub mod handle_table;
 pub mod hex_grid;
@@ -9,9 +10,11 @@ pub mod iterators;
 pub mod morton_hierarchy;
 pub mod morton_table;
 pub mod page_table;
+pub mod sparse_set_table;
 pub mod traits;
 pub mod unique_table;
 
+pub use self::change_tracking::{Change, ChangeKind, ChangedRows};
 pub use self::iterators::*;
 pub use self::morton_hierarchy::*;
 pub use self::traits::*;
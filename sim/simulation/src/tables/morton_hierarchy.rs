@@ -9,8 +9,6 @@ use thiserror::Error;
 
 #[derive(Debug, Clone, Error)]
 pub enum ExtendFailure {
-    #[error("Failed to extend the room level {0:?}")]
-    RoomExtendFailure(super::morton_table::ExtendFailure),
     #[error("Failed to insert poision {0:?}")]
     InvalidPosition(WorldPosition),
     #[error("Room {0:?} does not exist")]
@@ -114,9 +112,7 @@ where
     pub fn insert(&mut self, id: WorldPosition, val: Row) -> Result<(), ExtendFailure> {
         let mut room = self.table.at_mut(id.room);
         if room.is_none() {
-            self.table
-                .insert(id.room, InnerTable::default())
-                .map_err(ExtendFailure::RoomExtendFailure)?;
+            self.table.insert(id.room, InnerTable::default());
             room = self.table.at_mut(id.room);
         }
         room.unwrap()
@@ -141,14 +137,13 @@ where
         self.table.at(id.room).and_then(|room| room.at(id.pos))
     }
 
-    pub fn extend_rooms<It>(&mut self, iter: It) -> Result<&mut Self, ExtendFailure>
+    pub fn extend_rooms<It>(&mut self, iter: It) -> &mut Self
     where
         It: Iterator<Item = Room>,
     {
         self.table
-            .extend(iter.map(|Room(p)| (p, Default::default())))
-            .map_err(ExtendFailure::RoomExtendFailure)?;
-        Ok(self)
+            .extend(iter.map(|Room(p)| (p, Default::default())));
+        self
     }
 
     /// Extend the map by the items provided.
@@ -164,12 +159,7 @@ where
             // produce a key list from the rooms of the values
             let mut keys = values
                 .iter()
-                .map(|(wp, _)| {
-                    MortonKey::new(
-                        u16::try_from(wp.room.q).expect("expected room q to fit into u16"),
-                        u16::try_from(wp.room.r).expect("expected room r to fit into u16"),
-                    )
-                })
+                .map(|(wp, _)| MortonKey::new(wp.room.q, wp.room.r))
                 .collect::<Vec<_>>();
 
             // use the morton sorting to sort these values by their rooms
@@ -194,7 +184,7 @@ where
                 .map(|rid| Room(*rid))
                 .collect::<Vec<_>>();
 
-            self.extend_rooms(new_rooms.into_iter())?;
+            self.extend_rooms(new_rooms.into_iter());
         }
 
         self.table.iter_mut().try_for_each(move |(room_id, room)| {
@@ -263,13 +253,8 @@ impl<'a, Row> GroupByRooms<'a, Row> {
                 let mut current = it.next().unwrap();
                 for item in it {
                     assert!(
-                        MortonKey::new(
-                            u16::try_from(current.0.room.q).unwrap(),
-                            u16::try_from(current.0.room.r).unwrap(),
-                        ) <= MortonKey::new(
-                            u16::try_from(item.0.room.q).unwrap(),
-                            u16::try_from(item.0.room.r).unwrap(),
-                        )
+                        MortonKey::new(current.0.room.q, current.0.room.r)
+                            <= MortonKey::new(item.0.room.q, item.0.room.r)
                     );
                     current = item;
                 }
@@ -319,6 +304,71 @@ where
             })
         })
     }
+
+    /// Find the `k` rows closest to `center` for which `filter` returns true, crossing room
+    /// boundaries.
+    ///
+    /// `room_offset` maps rooms to their offset in world space, so that the world space position
+    /// of `pos` in `room` is `room_offset(room) + pos`. Offsets differing by a constant give the
+    /// same results, so the `offset` of `RoomComponent` may be used. Rooms without an offset are
+    /// skipped. World space positions must fit into `i32`.
+    ///
+    /// Rooms are searched in the order of their distance to `center`, until no room may hold a
+    /// row closer than the `k`th found. Returns the world space distance, position and row of the
+    /// results, ordered by their distance to `center`, then by their position.
+    pub fn k_nearest<'a, O, F>(
+        &'a self,
+        center: WorldPosition,
+        k: usize,
+        room_offset: O,
+        filter: F,
+    ) -> std::vec::IntoIter<(u32, WorldPosition, &'a Row)>
+    where
+        O: Fn(Room) -> Option<Axial>,
+        F: Fn(WorldPosition, &Row) -> bool,
+    {
+        let mut res = Vec::new();
+        let center_world = match room_offset(Room(center.room)) {
+            Some(offset) if k > 0 => offset + center.pos,
+            _ => return res.into_iter(),
+        };
+
+        let mut rooms = self
+            .iter_rooms()
+            .filter_map(|(room, table)| {
+                // center in the coordinates of the room
+                let local = center_world - room_offset(room)?;
+                let [min, max] = table.aabb()?;
+                Some((min_dist_to_aabb(local, min, max), room, local, table))
+            })
+            .collect::<Vec<_>>();
+        rooms.sort_unstable_by_key(|(bound, room, ..)| (*bound, *room));
+
+        for (bound, Room(room), local, table) in rooms {
+            if res.len() >= k && res[k - 1].0 < bound {
+                break;
+            }
+            res.extend(
+                table
+                    .k_nearest(local, k, |pos, row| {
+                        filter(WorldPosition { room, pos }, row)
+                    })
+                    .map(|(dist, pos, row)| (dist, WorldPosition { room, pos }, row)),
+            );
+            res.sort_unstable_by_key(|(dist, pos, _)| (*dist, *pos));
+            res.truncate(k);
+        }
+        res.into_iter()
+    }
+}
+
+/// Lower bound of the distance of `point` to the points of the AABB `[min, max]`
+fn min_dist_to_aabb(point: Axial, min: Axial, max: Axial) -> u32 {
+    let [q, r] = [point.q as i64, point.r as i64];
+    let dq = (min.q as i64 - q).max(q - max.q as i64).max(0);
+    let dr = (min.r as i64 - r).max(r - max.r as i64).max(0);
+    // the hex distance is at least the distance along either axis
+    u32::try_from(dq.max(dr)).unwrap_or(u32::MAX)
 }
 
 #[cfg(test)]
@@ -373,17 +423,74 @@ mod tests {
         ];
 
         let mut table = MortonMortonTable::new();
-        table
-            .extend_rooms(
-                [Axial::new(69, 69), Axial::new(42, 69)]
-                    .iter()
-                    .cloned()
-                    .map(|p| Room(p)),
-            )
-            .unwrap();
+        table.extend_rooms(
+            [Axial::new(69, 69), Axial::new(42, 69)]
+                .iter()
+                .cloned()
+                .map(|p| Room(p)),
+        );
         table.extend_from_slice(&mut pts).unwrap();
 
         assert_eq!(table.table.get(Axial::new(69, 69)).unwrap().len(), 2);
         assert_eq!(table.table.get(Axial::new(42, 69)).unwrap().len(), 4);
     }
+
+    #[test]
+    fn k_nearest_crosses_rooms() {
+        let mut table = MortonMortonTable::new();
+        let mut insert = |room, pos, val| {
+            table
+                .insert(
+                    WorldPosition {
+                        room: Axial::new(room, 0),
+                        pos,
+                    },
+                    val,
+                )
+                .unwrap();
+        };
+        // rooms are 10 tiles apart along q
+        insert(0, Axial::new(2, 5), 1);
+        insert(0, Axial::new(9, 5), 2);
+        insert(1, Axial::new(0, 5), 3);
+        insert(1, Axial::new(5, 5), 4);
+        insert(2, Axial::new(0, 5), 5);
+        let offset = |Room(room): Room| Some(Axial::new(room.q * 10, 0));
+
+        let center = WorldPosition {
+            room: Axial::new(0, 0),
+            pos: Axial::new(8, 5),
+        };
+        let res = table
+            .k_nearest(center, 3, offset, |_, _| true)
+            .map(|(dist, _, val)| (dist, *val))
+            .collect::<Vec<_>>();
+        assert_eq!(res, vec![(1, 2), (2, 3), (6, 1)]);
+
+        let res = table
+            .k_nearest(center, 2, offset, |_, val| *val >= 4)
+            .map(|(dist, pos, val)| (dist, pos, *val))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            res,
+            vec![
+                (
+                    7,
+                    WorldPosition {
+                        room: Axial::new(1, 0),
+                        pos: Axial::new(5, 5)
+                    },
+                    4
+                ),
+                (
+                    12,
+                    WorldPosition {
+                        room: Axial::new(2, 0),
+                        pos: Axial::new(0, 5)
+                    },
+                    5
+                ),
+            ]
+        );
+    }
 }
@@ -1,10 +1,10 @@
 //! Linear Quadtree.
-//! # Contracts:
-//! - Key axis must be in the interval [0, 2^16)
-//! This is a severe restriction on the keys that can be used, however dense queries and
-//! constructing from iterators is much faster than quadtrees.
 //!
-//! When compiling for x86 we assume that the machine is capable of executing SSE2 instructions.
+//! Positions are keyed by 64 bit Morton keys, so any `i32` coordinates may be stored, including
+//! negative ones. Dense queries and constructing from iterators is much faster than quadtrees.
+//!
+//! Query radii must fit into 31 bits. Distances are computed in 64 bits and saturate at
+//! `u32::MAX`, see [Axial::hex_distance].
 //!
 
 mod find_key_partition;
@@ -18,16 +18,12 @@ mod tests;
 
 pub use self::{litmax_bigmin::msb_de_bruijn, morton_key::*, skiplist::*};
 
-use self::litmax_bigmin::round_down_to_one_less_than_pow_two;
+use self::litmax_bigmin::round_down_to_one_less_than_pow_two_u64;
+use super::change_tracking::{ChangeTicks, ChangedRows};
 use super::*;
 use crate::geometry::Axial;
 use litmax_bigmin::litmax_bigmin;
 use std::convert::{TryFrom, TryInto};
-use thiserror::Error;
-
-// at most 15 bits long non-negative integers
-// having the 16th bit set might create problems in find_key
-pub const MORTON_POS_MAX: i32 = 0b0111_1111_1111_1111;
 
 // The original paper counts the garbage items and splits above a threshold.
 // Instead let's speculate if we need a split or if it more beneficial to just scan the
@@ -35,18 +31,13 @@ pub const MORTON_POS_MAX: i32 = 0b0111_1111_1111_1111;
 // The number I picked is more or less arbitrary, I ran the basic benchmarks to probe a few numbers.
 const MAX_BRUTE_ITERS: usize = 16;
 
-#[derive(Debug, Clone, Error)]
-pub enum ExtendFailure {
-    #[error("Position {0:?} is out of bounds!")]
-    OutOfBounds(Axial),
-}
-
 pub struct MortonTable<Row> {
     keys: Vec<MortonKey>,
     values: Vec<(Axial, Row)>,
     // SkipList contains the last item of every bucket
     skiplist: SkipList,
     bucket_size: u32,
+    changes: ChangeTicks<Axial>,
 }
 
 impl<Row> std::fmt::Debug for MortonTable<Row>
@@ -70,6 +61,7 @@ where
             skiplist: Default::default(),
             keys: Default::default(),
             values: Default::default(),
+            changes: Default::default(),
         }
     }
 }
@@ -84,21 +76,18 @@ where
             bucket_size: 0,
             keys: vec![],
             values: vec![],
+            changes: Default::default(),
         }
     }
 
-    pub fn from_vec(values: Vec<(Axial, Row)>) -> Result<Self, ExtendFailure>
+    pub fn from_vec(values: Vec<(Axial, Row)>) -> Self
     where
         Row: Default,
     {
         let mut keys = Vec::with_capacity(values.len());
         for (pos, _) in values.iter() {
-            if !Self::is_valid_pos(*pos) {
-                return Err(ExtendFailure::OutOfBounds(*pos));
-            }
             let [x, y] = pos.as_array();
-            // the above check ensured that x and y are safely convertible
-            keys.push(MortonKey::new(x as u16, y as u16))
+            keys.push(MortonKey::new(x, y))
         }
         let mut res = Self {
             keys,
@@ -107,7 +96,7 @@ where
         };
         sorting::sort(&mut res.keys, &mut res.values);
         res.rebuild_skip_list();
-        Ok(res)
+        res
     }
 
     pub fn with_capacity(cap: usize) -> Self {
@@ -116,6 +105,7 @@ where
             bucket_size: 0,
             values: Vec::with_capacity(cap),
             keys: Vec::with_capacity(cap),
+            changes: Default::default(),
         }
     }
 
@@ -127,50 +117,55 @@ where
         self.keys.len()
     }
 
+    /// Every row visited is recorded as modified, if change tracking is enabled
     pub fn iter_mut(&mut self) -> impl Iterator<Item = (Axial, &mut Row)> {
-        self.values.iter_mut().map(|(p, v)| (*p, v))
+        let changes = &mut self.changes;
+        self.values.iter_mut().map(move |(p, v)| {
+            changes.modified(*p);
+            (*p, v)
+        })
     }
 
     pub fn iter(&self) -> impl Iterator<Item = (Axial, &Row)> {
         self.values.iter().map(|(p, v)| (*p, v))
     }
 
-    pub fn from_iterator<It>(it: It) -> Result<Self, ExtendFailure>
+    pub fn from_iterator<It>(it: It) -> Self
     where
         It: Iterator<Item = (Axial, Row)>,
         Row: Default,
     {
         let mut res = Self::new();
-        res.extend(it)?;
-        Ok(res)
+        res.extend(it);
+        res
     }
 
     pub fn clear(&mut self) {
+        if self.changes.is_enabled() {
+            for (pos, _) in self.values.iter() {
+                self.changes.removed(*pos);
+            }
+        }
         self.keys.clear();
         self.values.clear();
         self.rebuild_skip_list();
     }
 
     /// Extend the map by the items provided.
-    pub fn extend<It>(&mut self, it: It) -> Result<(), ExtendFailure>
+    pub fn extend<It>(&mut self, it: It)
     where
         It: Iterator<Item = (Axial, Row)>,
         Row: Default,
     {
         for (id, value) in it {
-            if !self.intersects(id) {
-                return Err(ExtendFailure::OutOfBounds(id));
-            }
             let [x, y] = id.as_array();
-            // if the id is in bounds this transformation is safe
-            let [x, y] = [x as u16, y as u16];
             let key = MortonKey::new(x, y);
             self.keys.push(key);
             self.values.push((id, value));
+            self.changes.inserted(id);
         }
         sorting::sort(&mut self.keys, &mut self.values);
         self.rebuild_skip_list();
-        Ok(())
     }
 
     fn rebuild_skip_list(&mut self) {
@@ -182,7 +177,7 @@ where
         self.bucket_size = step as u32;
         self.skiplist = SkipList::default();
         for (i, k) in (0..len).step_by(step).skip(1).take(SKIP_LEN).enumerate() {
-            self.skiplist.set(i, self.keys[k].0 as i32);
+            self.skiplist.set(i, self.keys[k].0);
         }
     }
 
@@ -207,12 +202,10 @@ where
 
     /// Return false if id is not in the map, otherwise override the first instance found
     pub fn update(&mut self, id: Axial, row: Row) -> Option<&Row> {
-        self.find_key(id)
-            .map(move |ind| {
-                self.values[ind].1 = row;
-                &self.values[ind].1
-            })
-            .ok()
+        let ind = self.find_key(id).ok()?;
+        self.changes.modified(id);
+        self.values[ind].1 = row;
+        Some(&self.values[ind].1)
     }
 
     /// Return a reference to the new Row if it's in the map or None otherwise
@@ -220,61 +213,46 @@ where
     where
         F: FnOnce(&mut Row),
     {
-        self.find_key(id)
-            .map(move |ind| {
-                f(&mut self.values[ind].1);
-                &self.values[ind].1
-            })
-            .ok()
+        let ind = self.find_key(id).ok()?;
+        self.changes.modified(id);
+        f(&mut self.values[ind].1);
+        Some(&self.values[ind].1)
     }
 
-    /// Return a reference to the new Row if it's in the map or None otherwise
-    pub fn insert(&mut self, id: Axial, row: Row) -> Result<(), ExtendFailure> {
-        if !self.intersects(id) {
-            return Err(ExtendFailure::OutOfBounds(id));
-        }
+    /// Insert the row, overriding the previous row at `id`, if any
+    pub fn insert(&mut self, id: Axial, row: Row) {
         match self.find_key(id) {
             Ok(ind) => {
+                self.changes.modified(id);
                 self.values[ind].1 = row;
             }
             Err(ind) => {
+                self.changes.inserted(id);
                 let [x, y] = id.as_array();
-                let [x, y] = [x as u16, y as u16];
                 self.keys.insert(ind, MortonKey::new(x, y));
                 self.values.insert(ind, (id, row));
                 self.rebuild_skip_list();
             }
         }
-        Ok(())
     }
 
     /// Returns the first item with given id, if any
     #[inline]
     pub fn at(&self, id: Axial) -> Option<&Row> {
-        if !self.intersects(id) {
-            return None;
-        }
-
         self.find_key(id).map(|ind| &self.values[ind].1).ok()
     }
 
-    /// Returns the first item with given id, if any
+    /// Returns the first item with given id, if any.
+    /// The row is recorded as modified, if change tracking is enabled
     #[inline]
     pub fn at_mut(&mut self, id: Axial) -> Option<&mut Row> {
-        if !self.intersects(id) {
-            return None;
-        }
-
-        self.find_key(id)
-            .map(move |ind| &mut self.values[ind].1)
-            .ok()
+        let ind = self.find_key(id).ok()?;
+        self.changes.modified(id);
+        Some(&mut self.values[ind].1)
     }
 
     #[inline]
     pub fn contains_key(&self, id: Axial) -> bool {
-        if !self.intersects(id) {
-            return false;
-        }
         self.find_key(id).is_ok()
     }
 
@@ -283,7 +261,7 @@ where
     #[inline]
     fn find_key(&self, id: Axial) -> Result<usize, usize> {
         let [x, y] = id.as_array();
-        let key = MortonKey::new(x as u16, y as u16);
+        let key = MortonKey::new(x, y);
 
         self.find_key_morton(key)
     }
@@ -342,11 +320,8 @@ where
         let r = i32::try_from(radius).expect("radius to fit into 31 bits");
 
         let [x, y] = center.as_array();
-        let min = MortonKey::new((x - r).max(0) as u16, (y - r).max(0) as u16);
-        let max = MortonKey::new(
-            ((x + r).min(MORTON_POS_MAX)) as u16,
-            ((y + r).min(MORTON_POS_MAX)) as u16,
-        );
+        let min = MortonKey::new(x.saturating_sub(r), y.saturating_sub(r));
+        let max = MortonKey::new(x.saturating_add(r), y.saturating_add(r));
         self.query_range_impl(center, radius, min, max, op);
     }
 
@@ -368,10 +343,7 @@ where
                 }
                 (i, self.values[i].0.as_array())
             })
-            .unwrap_or_else(|i| {
-                let [x, y] = min.as_point();
-                (i, [x as i32, y as i32])
-            });
+            .unwrap_or_else(|i| (i, min.as_point()));
 
         let (max_ind, max_point) = self
             .find_key_morton(max)
@@ -386,10 +358,7 @@ where
                 }
                 (j, self.values[i].0.as_array())
             })
-            .unwrap_or_else(|i| {
-                let [x, y] = max.as_point();
-                (i, [x as i32, y as i32])
-            });
+            .unwrap_or_else(|i| (i, max.as_point()));
 
         debug_assert!(
             min_ind <= max_ind,
@@ -400,9 +369,9 @@ where
 
         if max_ind - min_ind > MAX_BRUTE_ITERS {
             let [x, y] = min_point;
-            let pmin = [x as u32, y as u32];
+            let pmin = [to_axis(x), to_axis(y)];
             let [x, y] = max_point;
-            let pmax = [x as u32, y as u32];
+            let pmax = [to_axis(x), to_axis(y)];
             let [litmax, bigmin] = litmax_bigmin(min.0, pmin, max.0, pmax);
             // split and recurse
             self.query_range_impl(center, radius, min, litmax, op);
@@ -429,11 +398,56 @@ where
             .min_by_key(|t| t.0)
     }
 
+    /// Find the `k` rows closest to `center` for which `filter` returns true.
+    ///
+    /// Searches rings of doubling radius around `center`, until `k` rows are found or every row
+    /// was visited. Returns the distance, position and row of the results, ordered by their
+    /// distance to `center`, then by their position.
+    ///
+    /// Rows further than `i32::MAX` from `center` are not returned.
+    pub fn k_nearest<'a, F>(
+        &'a self,
+        center: Axial,
+        k: usize,
+        filter: F,
+    ) -> std::vec::IntoIter<(u32, Axial, &'a Row)>
+    where
+        F: Fn(Axial, &Row) -> bool,
+    {
+        let mut res = Vec::with_capacity(k.min(self.len()));
+        if k == 0 {
+            return res.into_iter();
+        }
+        const MAX_RADIUS: u32 = i32::MAX as u32;
+        let mut inner = None;
+        let mut radius = 1;
+        loop {
+            let mut visited = 0;
+            self.query_range(center, radius, &mut |pos, row| {
+                visited += 1;
+                let dist = center.dist(pos);
+                // rows of the inner rings were already collected
+                if inner.map(|r| r < dist).unwrap_or(true) && filter(pos, row) {
+                    res.push((dist, pos, row));
+                }
+            });
+            if res.len() >= k || visited >= self.len() || radius >= MAX_RADIUS {
+                break;
+            }
+            inner = Some(radius);
+            radius = radius.saturating_mul(2).min(MAX_RADIUS);
+        }
+        res.sort_unstable_by_key(|(dist, pos, _)| (*dist, *pos));
+        res.truncate(k);
+        res.into_iter()
+    }
+
     /// Count in AABB
     pub fn count_in_range(&self, center: Axial, radius: u32) -> u32 {
         let r = i32::try_from(radius).expect("radius to fit into 31 bits");
-        let min = center + Axial::new(-r, -r);
-        let max = center + Axial::new(r, r);
+        let [x, y] = center.as_array();
+        let min = Axial::new(x.saturating_sub(r), y.saturating_sub(r));
+        let max = Axial::new(x.saturating_add(r), y.saturating_add(r));
 
         let [min, max] = self.morton_min_max(min, max);
 
@@ -451,8 +465,9 @@ where
         Query: Fn(Axial, &Row) -> bool,
     {
         let r = i32::try_from(radius).expect("radius to fit into 31 bits");
-        let min = center + Axial::new(-r, -r);
-        let max = center + Axial::new(r, r);
+        let [x, y] = center.as_array();
+        let min = Axial::new(x.saturating_sub(r), y.saturating_sub(r));
+        let max = Axial::new(x.saturating_add(r), y.saturating_add(r));
 
         let [min, max] = self.morton_min_max(min, max);
 
@@ -465,60 +480,29 @@ where
     }
 
     /// Turn AABB min-max to from-to indices
-    /// Clamps `min` and `max` to intersect `self`
     fn morton_min_max(&self, min: Axial, max: Axial) -> [usize; 2] {
-        let min: usize = {
-            if !self.intersects(min) {
-                0
-            } else {
-                self.find_key(min).unwrap_or_else(|i| i)
-            }
-        };
-        let max: usize = {
-            if !self.intersects(max) {
-                (self.keys.len() as i64 - 1).max(0) as usize
-            } else {
-                self.find_key(max).unwrap_or_else(|i| i)
-            }
-        };
+        let min = self.find_key(min).unwrap_or_else(|i| i);
+        let max = self.find_key(max).unwrap_or_else(|i| i);
         [min, max]
     }
 
-    pub fn is_valid_pos(point: Axial) -> bool {
-        let [x, y] = point.as_array();
-
-        0 <= x && (x & MORTON_POS_MAX) == x && 0 <= y && (y & MORTON_POS_MAX) == y
-    }
-
-    /// Return wether point is within the bounds of this node
-    #[inline]
-    pub fn intersects(&self, point: Axial) -> bool {
-        Self::is_valid_pos(point)
-    }
-
-    /// Return [min, max) of the bounds of this table
+    /// Return the [min, max] of the positions this table can hold
     pub fn bounds(&self) -> (Axial, Axial) {
         (
-            Axial::new(0, 0),
-            Axial::new(MORTON_POS_MAX + 1, MORTON_POS_MAX + 1),
+            Axial::new(i32::MIN, i32::MIN),
+            Axial::new(i32::MAX, i32::MAX),
         )
     }
 
     /// Compute the minimum and maximum positions for this table's AABB.
     /// Note that this might be (a lot) larger than the minimum bounding box that might hold this table!
     pub fn aabb(&self) -> Option<[Axial; 2]> {
-        let min = self.keys.get(0)?;
-        let [minx, miny] = self.values[0].0.as_array();
-        let min_loc = round_down_to_one_less_than_pow_two(min.0) + 1;
-        let [ax, ay] = MortonKey(min_loc).as_point();
-        let [minx, miny] = [minx.min(ax as i32), miny.min(ay as i32)];
-
-        let max = *self.keys.last().unwrap_or(min);
-        let max = round_down_to_one_less_than_pow_two(max.0) + 1;
-        let max = MortonKey(max);
-        let [maxx, maxy] = self.values[self.values.len() - 1].0.as_array();
-        let [bx, by] = max.as_point();
-        let [maxx, maxy] = [maxx.max(bx as i32), maxy.max(by as i32)];
+        let min = self.keys.first()?.0;
+        let max = self.keys.last()?.0;
+        // every key in [min, max] shares the prefix of `min` and `max`
+        let suffix = round_down_to_one_less_than_pow_two_u64(min ^ max);
+        let [minx, miny] = MortonKey(min & !suffix).as_point();
+        let [maxx, maxy] = MortonKey(max | suffix).as_point();
 
         let res = [Axial::new(minx, miny), Axial::new(maxx, maxy)];
         Some(res)
@@ -548,10 +532,6 @@ where
 
     /// delete all values at id and return the first one, if any
     fn delete(&mut self, id: Axial) -> Option<Row> {
-        if !self.intersects(id) {
-            return None;
-        }
-
         let val = self
             .find_key(id)
             .map(|ind| {
@@ -567,6 +547,7 @@ where
         }
 
         self.rebuild_skip_list();
+        self.changes.removed(id);
 
         Some(val)
     }
@@ -574,13 +555,34 @@ where
     fn get(&self, id: Axial) -> Option<&Row> {
         MortonTable::at(self, id)
     }
+
+    fn set_change_tick(&mut self, tick: u64) {
+        self.changes.set_tick(tick);
+    }
+
+    fn prune_changes(&mut self, before: u64) {
+        self.changes.prune(before);
+    }
+}
+
+impl<Row> ChangedRows for MortonTable<Row>
+where
+    Row: TableRow,
+{
+    fn change_ticks(&self) -> &ChangeTicks<Axial> {
+        &self.changes
+    }
+
+    fn set_change_tracking(&mut self, enabled: bool) {
+        self.changes.set_enabled(enabled);
+    }
 }
 
 impl<Row> SpacialStorage<Row> for MortonTable<Row>
 where
     Row: TableRow + Default,
 {
-    type ExtendFailure = ExtendFailure;
+    type ExtendFailure = std::convert::Infallible;
     fn clear(&mut self) {
         MortonTable::clear(self);
     }
@@ -590,14 +592,16 @@ where
     }
 
     fn insert(&mut self, id: Axial, row: Row) -> Result<(), Self::ExtendFailure> {
-        MortonTable::insert(self, id, row)
+        MortonTable::insert(self, id, row);
+        Ok(())
     }
 
     fn extend<It>(&mut self, it: It) -> Result<(), Self::ExtendFailure>
     where
         It: Iterator<Item = (Axial, Row)>,
     {
-        MortonTable::extend(self, it)
+        MortonTable::extend(self, it);
+        Ok(())
     }
 
     fn at(&self, pos: Axial) -> Option<&Row> {
@@ -23,18 +23,18 @@ use super::morton_key::MortonKey;
 /// - `mortonmin <= litmax`
 /// - `bigmin <= mortonmax`
 pub fn litmax_bigmin(
-    mortonmin: u32,
+    mortonmin: u64,
     [x1, y1]: [u32; 2],
-    mortonmax: u32,
+    mortonmax: u64,
     [x2, y2]: [u32; 2],
 ) -> [MortonKey; 2] {
     debug_assert!(mortonmin < mortonmax);
-    debug_assert!(MortonKey(mortonmin).as_point() == [x1 as u16, y1 as u16]);
-    debug_assert!(MortonKey(mortonmax).as_point() == [x2 as u16, y2 as u16]);
+    debug_assert!(MortonKey::from_axes(x1, y1).0 == mortonmin);
+    debug_assert!(MortonKey::from_axes(x2, y2).0 == mortonmax);
 
     // find the most significant bit that's different
     let diff = mortonmin ^ mortonmax;
-    let diff_msb = msb_de_bruijn(diff);
+    let diff_msb = 63 - diff.leading_zeros();
 
     // split among the side with the higher most significant bit
     // even msb will mean the x axis.
@@ -42,19 +42,13 @@ pub fn litmax_bigmin(
         let [x1, x2] = impl_litmax_bigmin(x1, x2, diff_msb / 2);
         debug_assert!(x1 < x2);
 
-        [
-            MortonKey::new(x1 as u16, y2 as u16),
-            MortonKey::new(x2 as u16, y1 as u16),
-        ]
+        [MortonKey::from_axes(x1, y2), MortonKey::from_axes(x2, y1)]
     } else {
         let [m1, y2] = impl_litmax_bigmin(y1, y2, diff_msb / 2);
         let y1 = m1 | y1;
         debug_assert!(y1 < y2);
 
-        [
-            MortonKey::new(x2 as u16, y1 as u16),
-            MortonKey::new(x1 as u16, y2 as u16),
-        ]
+        [MortonKey::from_axes(x2, y1), MortonKey::from_axes(x1, y2)]
     };
 
     debug_assert!(litmax.0 < bigmin.0);
@@ -111,3 +105,13 @@ pub fn round_down_to_one_less_than_pow_two(mut v: u32) -> u32 {
     v |= v >> 8;
     v | v >> 16
 }
+
+#[inline]
+pub fn round_down_to_one_less_than_pow_two_u64(mut v: u64) -> u64 {
+    v |= v >> 1;
+    v |= v >> 2;
+    v |= v >> 4;
+    v |= v >> 8;
+    v |= v >> 16;
+    v | v >> 32
+}
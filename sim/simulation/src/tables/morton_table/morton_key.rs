@@ -25,56 +25,73 @@ static MORTON_TABLE256: [u16; 256] = [
     0x5550, 0x5551, 0x5554, 0x5555,
 ];
 
+/// Key of a position on the Z-order curve.
+///
+/// Signed coordinates are mapped to unsigned axes by flipping their sign bit, which preserves
+/// their order, so the keys of any `i32` positions can be compared.
 #[derive(
     Debug, Clone, Copy, Hash, Eq, PartialEq, PartialOrd, Ord, Serialize, Deserialize, Default,
 )]
-pub struct MortonKey(pub u32);
+pub struct MortonKey(pub u64);
 
 impl MortonKey {
     #[inline]
-    pub fn new(x: u16, y: u16) -> Self {
+    pub fn new(x: i32, y: i32) -> Self {
+        Self::from_axes(to_axis(x), to_axis(y))
+    }
+
+    /// Create a key from axes already mapped by [to_axis]
+    #[inline]
+    pub fn from_axes(x: u32, y: u32) -> Self {
         Self(Self::morton2(x, y))
     }
 
     #[inline(always)]
-    fn morton2(x: u16, y: u16) -> u32 {
-        unsafe {
-            (*MORTON_TABLE256.get_unchecked((y >> 8) as usize) as u32) << 17
-                | (*MORTON_TABLE256.get_unchecked((x >> 8) as usize) as u32) << 16
-                | (*MORTON_TABLE256.get_unchecked((y & 0xFF) as usize) as u32) << 1
-                | (*MORTON_TABLE256.get_unchecked((x & 0xFF) as usize) as u32)
+    fn morton2(x: u32, y: u32) -> u64 {
+        let mut res = 0;
+        for byte in 0..4 {
+            let shift = byte * 8;
+            let bx = MORTON_TABLE256[((x >> shift) & 0xFF) as usize] as u64;
+            let by = MORTON_TABLE256[((y >> shift) & 0xFF) as usize] as u64;
+            res |= by << (2 * shift + 1) | bx << (2 * shift);
         }
+        res
     }
 
     /// Calculate the original point of this hash key.
     /// In practice it is more beneficial to just store the original key if you need to access it
     /// later.
-    #[allow(unused)]
-    pub fn as_point(self) -> [u16; 2] {
-        let x = Self::reconstruct(self.0) as u16;
-        let y = Self::reconstruct(self.0 >> 1) as u16;
-        [x, y]
+    pub fn as_point(self) -> [i32; 2] {
+        let x = Self::reconstruct(self.0);
+        let y = Self::reconstruct(self.0 >> 1);
+        [from_axis(x), from_axis(y)]
     }
 
     #[inline(always)]
-    fn reconstruct(mut n: u32) -> u32 {
-        // -f-e-d-c-b-a-9-8-7-6-5-4-3-2-1-0 : After (1)
-        // -ffeeddccbbaa9988776655443322110 : After (2)
-        // --fe--dc--ba--98--76--54--32--10 : After (3)
-        // --fefedcdcbaba989876765454323210 : After (4)
-        // ----fedc----ba98----7654----3210 : After (5)
-        // ----fedcfedcba98ba98765476543210 : After (6)
-        // --------fedcba98--------76543210 : After (7)
-        // --------fedcba98fedcba9876543210 : After (8)
-        // ----------------fedcba9876543210 : After (9)
-        n &= 0x5555_5555;
+    fn reconstruct(mut n: u64) -> u32 {
+        // compact the even bits of `n`, doubling the width of the compacted groups in each step
+        n &= 0x5555_5555_5555_5555;
         n |= n >> 1;
-        n &= 0x3333_3333;
+        n &= 0x3333_3333_3333_3333;
         n |= n >> 2;
-        n &= 0x0f0f_0f0f;
+        n &= 0x0f0f_0f0f_0f0f_0f0f;
         n |= n >> 4;
-        n &= 0x00ff_00ff;
+        n &= 0x00ff_00ff_00ff_00ff;
         n |= n >> 8;
-        n & 0x0000_ffff
+        n &= 0x0000_ffff_0000_ffff;
+        n |= n >> 16;
+        (n & 0x0000_0000_ffff_ffff) as u32
     }
 }
+
+/// Map a coordinate to an axis of the key, preserving their order
+#[inline(always)]
+pub fn to_axis(x: i32) -> u32 {
+    (x as u32) ^ 0x8000_0000
+}
+
+/// Inverse of [to_axis]
+#[inline(always)]
+pub fn from_axis(x: u32) -> i32 {
+    (x ^ 0x8000_0000) as i32
+}
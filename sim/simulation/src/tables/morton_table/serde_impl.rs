@@ -52,13 +52,7 @@ where
             }
         }
         let values = values.ok_or_else(|| de::Error::missing_field("values"))?;
-        let len = values.len();
-        MortonTable::from_vec(values).map_err(|e| {
-            de::Error::invalid_length(
-                len,
-                &format!("Failed to build MortonTable {:?}", e).as_str(),
-            )
-        })
+        Ok(MortonTable::from_vec(values))
     }
 }
 
@@ -99,7 +93,7 @@ mod tests {
             })
             .collect::<Vec<_>>();
 
-        let table = MortonTable::from_iterator(points.iter().cloned()).unwrap();
+        let table = MortonTable::from_iterator(points.iter().cloned());
 
         let s = serde_json::to_string(&table).unwrap();
         dbg!(&s);
@@ -5,7 +5,7 @@ use std::mem::{size_of, swap};
 
 const RADIX_MASK_LEN: usize = 8; // how many bits are considered at a time
 const NUM_BUCKETS: usize = 1usize << (RADIX_MASK_LEN as usize);
-const RADIX_MASK: u32 = (NUM_BUCKETS - 1) as u32;
+const RADIX_MASK: u64 = (NUM_BUCKETS - 1) as u64;
 const MORTON_BITS: usize = size_of::<MortonKey>() * 8;
 
 pub fn sort<T: Default>(keys: &mut Vec<MortonKey>, values: &mut [T]) {
@@ -103,15 +103,16 @@ mod tests {
 
     #[test]
     fn test_sorting_sorted() {
-        let control: Vec<MortonKey> = (0..(1 << 31))
+        let control: Vec<MortonKey> = (0..(1u64 << 31))
             .step_by(2000)
+            .map(|i| i << 31 | i)
             .chain(
                 [
-                    std::u32::MAX - 4,
-                    std::u32::MAX - 3,
-                    std::u32::MAX - 2,
-                    std::u32::MAX - 1,
-                    std::u32::MAX,
+                    std::u64::MAX - 4,
+                    std::u64::MAX - 3,
+                    std::u64::MAX - 2,
+                    std::u64::MAX - 1,
+                    std::u64::MAX,
                 ]
                 .iter()
                 .copied(),
@@ -12,7 +12,7 @@ fn aabb_simple() {
         Axial::new(23, 12),
     ];
 
-    let table = MortonTable::from_iterator(points.iter().cloned().map(|p| (p, 1))).unwrap();
+    let table = MortonTable::from_iterator(points.iter().cloned().map(|p| (p, 1)));
     let [min, max] = table.aabb().unwrap();
 
     let min = min.as_array();
@@ -49,14 +49,14 @@ fn simple_from_iterator() {
         Axial::new(rng.gen_range(0..1 << 15), rng.gen_range(0..1 << 15)),
     ];
     points.shuffle(&mut rng);
-    MortonTable::from_iterator(points.iter().enumerate().map(|(i, p)| (*p, i))).unwrap();
+    MortonTable::from_iterator(points.iter().enumerate().map(|(i, p)| (*p, i)));
 }
 
 #[test]
 fn insertions() {
     let mut table = MortonTable::new();
 
-    table.insert(Axial::new(16, 32), 123i32).unwrap();
+    table.insert(Axial::new(16, 32), 123i32);
 }
 
 fn test_range_query_all_by_rng(rng: &mut impl rand::Rng) {
@@ -72,7 +72,7 @@ fn test_range_query_all_by_rng(rng: &mut impl rand::Rng) {
         })
         .collect::<HashSet<_>>();
 
-    let table = MortonTable::from_iterator(points.iter().cloned()).unwrap();
+    let table = MortonTable::from_iterator(points.iter().cloned());
 
     let mut res = Vec::new();
     table.find_by_range(
@@ -198,13 +198,13 @@ fn regression_at_bug1() {
         .map(|(i, p)| (p, i))
         .collect();
 
-    let table = MortonTable::<usize>::from_iterator(points.iter().cloned()).unwrap();
+    let table = MortonTable::<usize>::from_iterator(points.iter().cloned());
 
     dbg!(&table);
 
     for p in points {
         let found = table.at(p.0);
-        let key = MortonKey::new(p.0.q as u16, p.0.r as u16);
+        let key = MortonKey::new(p.0.q, p.0.r);
         assert_eq!(found, Some(&p.1), "{:?} {:?}", p.0, key);
     }
 }
@@ -226,14 +226,13 @@ fn at_few_items() {
             let i = 1000 * p.q + p.r;
             points.insert((p, i as usize));
         }
-        let table =
-            MortonTable::<usize>::from_iterator(points.iter().cloned()).expect("table build");
+        let table = MortonTable::<usize>::from_iterator(points.iter().cloned());
 
         println!("{:?}\n{:?}", table.skiplist, table.keys);
 
         for p in points.iter() {
             let found = table.at(p.0);
-            let key = MortonKey::new(p.0.q as u16, p.0.r as u16);
+            let key = MortonKey::new(p.0.q, p.0.r);
             assert_eq!(found, Some(&p.1), "{:?} {:?}", p.0, key);
         }
     }
@@ -254,13 +253,13 @@ fn at() {
         points.insert((p, i as usize));
     }
 
-    let table = MortonTable::<usize>::from_iterator(points.iter().cloned()).expect("table build");
+    let table = MortonTable::<usize>::from_iterator(points.iter().cloned());
 
     println!("{:?}\n{:?}", table.skiplist, table.keys);
 
     for p in points {
         let found = table.at(p.0);
-        let key = MortonKey::new(p.0.q as u16, p.0.r as u16);
+        let key = MortonKey::new(p.0.q, p.0.r);
         assert_eq!(found, Some(&p.1), "{:?} {:?}", p.0, key);
     }
 }
@@ -270,8 +269,8 @@ fn morton_key_reconstruction_rand() {
     let mut rng = rand::thread_rng();
 
     for _ in 0..(1 << 12) {
-        let q = rng.gen_range(0..2000);
-        let r = rng.gen_range(0..2000);
+        let q = rng.gen_range(-2000..2000);
+        let r = rng.gen_range(-2000..2000);
 
         let morton = MortonKey::new(q, r);
 
@@ -281,6 +280,70 @@ fn morton_key_reconstruction_rand() {
     }
 }
 
+#[test]
+fn morton_key_extreme_coordinates() {
+    for p in [
+        [i32::MIN, i32::MIN],
+        [i32::MIN, i32::MAX],
+        [i32::MAX, 0],
+        [-1, 1 << 20],
+    ]
+    .iter()
+    {
+        assert_eq!(MortonKey::new(p[0], p[1]).as_point(), *p);
+    }
+
+    // keys preserve the order of the coordinates along each axis
+    assert!(MortonKey::new(-1, 0) < MortonKey::new(0, 0));
+    assert!(MortonKey::new(0, -1) < MortonKey::new(0, 0));
+    assert!(MortonKey::new(i32::MIN, i32::MIN) < MortonKey::new(i32::MAX, i32::MAX));
+}
+
+#[test]
+fn negative_and_large_coordinates() {
+    let mut rng = rand::thread_rng();
+    let center = Axial::new(-(1 << 20), 1 << 18);
+
+    let points = (0..512)
+        .map(|i| {
+            let p = Axial {
+                q: rng.gen_range(-64..=64),
+                r: rng.gen_range(-64..=64),
+            } + center;
+            (p, i)
+        })
+        .collect::<HashMap<_, _>>();
+
+    let mut table = MortonTable::from_iterator(points.iter().map(|(p, i)| (*p, *i)));
+    for (p, i) in points.iter() {
+        assert_eq!(table.at(*p), Some(i));
+    }
+
+    let radius = 32;
+    let mut res = Vec::new();
+    table.find_by_range(center, radius, &mut res);
+    let res = res
+        .into_iter()
+        .map(|(p, i)| (p, *i))
+        .collect::<HashSet<_>>();
+    let exp = points
+        .iter()
+        .filter(|(p, _)| p.hex_distance(center) <= radius)
+        .map(|(p, i)| (*p, *i))
+        .collect::<HashSet<_>>();
+    assert_eq!(res, exp);
+
+    let [min, max] = table.aabb().unwrap();
+    for p in points.keys() {
+        assert!(min.q <= p.q && p.q <= max.q);
+        assert!(min.r <= p.r && p.r <= max.r);
+    }
+
+    let p = Axial::new(i32::MIN, i32::MAX);
+    table.insert(p, 42);
+    assert_eq!(table.delete(p), Some(42));
+}
+
 #[test]
 fn from_iterator_inserts_correctly() {
     let mut rng = rand::thread_rng();
@@ -298,8 +361,7 @@ fn from_iterator_inserts_correctly() {
         let val = rng.next_u32();
         points.insert(pos.clone(), val);
         Some((pos, val))
-    }))
-    .unwrap();
+    }));
 
     for (pos, val) in points {
         let v = *table.at(pos).expect("to find the value");
@@ -317,8 +379,7 @@ fn dedupe_simple() {
             r: rng.gen_range(0..1 << 15),
         };
         vec![(pos, 0), (pos, 1), (pos, 3)]
-    }))
-    .unwrap();
+    }));
     table.dedupe();
 
     let mut cnt = 0;
@@ -333,3 +394,92 @@ fn dedupe_simple() {
     assert_eq!(positions.len(), 128);
     assert_eq!(cnt, 128);
 }
+
+#[test]
+fn tracks_changes() {
+    let mut table = MortonTable::new();
+    table.set_change_tracking(true);
+    table.set_change_tick(1);
+    table.insert(Axial::new(1, 2), 1);
+    table.insert(Axial::new(3, 4), 2);
+
+    table.set_change_tick(2);
+    *table.at_mut(Axial::new(1, 2)).unwrap() = 3;
+    table.delete(Axial::new(3, 4)).unwrap();
+    assert!(table.at_mut(Axial::new(5, 5)).is_none());
+
+    let mut changes = table.changes_since(2).collect::<Vec<_>>();
+    changes.sort_by_key(|(p, _)| p.as_array());
+    assert_eq!(
+        changes
+            .into_iter()
+            .map(|(p, c)| (p, c.kind))
+            .collect::<Vec<_>>(),
+        vec![
+            (Axial::new(1, 2), ChangeKind::Modified),
+            (Axial::new(3, 4), ChangeKind::Removed)
+        ]
+    );
+}
+
+#[test]
+fn overwriting_a_row_is_a_modification() {
+    let mut table = MortonTable::new();
+    table.set_change_tracking(true);
+    table.set_change_tick(1);
+    table.insert(Axial::new(1, 2), 1);
+    table.prune_changes(2);
+
+    table.set_change_tick(2);
+    table.insert(Axial::new(1, 2), 2);
+    table.insert(Axial::new(3, 4), 3);
+    table.insert(Axial::new(3, 4), 4);
+
+    let mut changes = table.changes_since(2).collect::<Vec<_>>();
+    changes.sort_by_key(|(p, _)| p.as_array());
+    assert_eq!(
+        changes
+            .into_iter()
+            .map(|(p, c)| (p, c.kind))
+            .collect::<Vec<_>>(),
+        vec![
+            (Axial::new(1, 2), ChangeKind::Modified),
+            (Axial::new(3, 4), ChangeKind::Inserted)
+        ]
+    );
+    assert_eq!(table.at(Axial::new(1, 2)), Some(&2));
+}
+
+#[test]
+fn k_nearest_matches_brute_force() {
+    let mut rng = rand::thread_rng();
+    let center = Axial::new(-20, 300);
+
+    let points = (0..512)
+        .map(|i| {
+            let p = Axial {
+                q: rng.gen_range(-100..=100),
+                r: rng.gen_range(-100..=100),
+            } + center;
+            (p, i)
+        })
+        .collect::<HashMap<_, _>>();
+    let table = MortonTable::from_iterator(points.iter().map(|(p, i)| (*p, *i)));
+
+    for k in [0, 1, 7, 64, 1000].iter().copied() {
+        let res = table
+            .k_nearest(center, k, |_, i| i % 3 == 0)
+            .map(|(dist, p, i)| (dist, p, *i))
+            .collect::<Vec<_>>();
+
+        let mut exp = points
+            .iter()
+            .filter(|(_, i)| *i % 3 == 0)
+            .map(|(p, i)| (p.hex_distance(center), *p, *i))
+            .collect::<Vec<_>>();
+        exp.sort_by_key(|(dist, p, _)| (*dist, *p));
+        exp.truncate(k);
+
+        assert_eq!(res, exp, "k: {}", k);
+    }
+}
@@ -3,7 +3,9 @@ mod pt_iter;
 use crate::prelude::EntityId;
 
 use self::pt_iter::PTIter;
-use std::{mem::MaybeUninit, ptr::drop_in_place};
+use super::change_tracking::{ChangeTicks, ChangedRows};
+use std::mem::MaybeUninit;
+use std::ptr::{self, drop_in_place, NonNull};
 
 const PAGE_SIZE: usize = 512;
 const PAGE_FLAG_SIZE: usize = PAGE_SIZE / 64;
@@ -14,6 +16,7 @@ type PageEntry<T> = Option<Box<Page<T>>>;
 pub struct PageTable<T> {
     num_entities: usize,
     pages: Vec<PageEntry<T>>,
+    changes: ChangeTicks<EntityId>,
 }
 
 impl<T> Default for PageTable<T> {
@@ -27,6 +30,7 @@ impl<T> PageTable<T> {
         Self {
             num_entities: 0,
             pages: Vec::with_capacity(capacity / PAGE_SIZE),
+            changes: Default::default(),
         }
     }
 
@@ -39,7 +43,21 @@ impl<T> PageTable<T> {
             .and_then(|page| page.get(index as usize & PAGE_MASK))
     }
 
-    pub fn get_mut(&mut self, index: EntityId) -> Option<&mut T> {
+    /// Rows accessed mutably are recorded as modified, if change tracking is enabled
+    pub fn get_mut(&mut self, id: EntityId) -> Option<&mut T> {
+        self.mark_modified(id);
+        self.get_mut_untracked(id)
+    }
+
+    /// Record the row as modified, if it exists and change tracking is enabled
+    pub(crate) fn mark_modified(&mut self, id: EntityId) {
+        if self.changes.is_enabled() && self.contains(id) {
+            self.changes.modified(id);
+        }
+    }
+
+    /// Access a row mutably without recording the change
+    pub(crate) fn get_mut_untracked(&mut self, index: EntityId) -> Option<&mut T> {
         let index = index.index();
         let page_index = index as usize / PAGE_SIZE;
         self.pages
@@ -48,8 +66,42 @@ impl<T> PageTable<T> {
             .and_then(|page| page.get_mut(index as usize & PAGE_MASK))
     }
 
-    pub fn remove(&mut self, index: EntityId) -> Option<T> {
-        let index = index.index();
+    /// Pointer to the row of `id`. No references to the pages are formed, so other rows of the
+    /// table may be borrowed mutably.
+    ///
+    /// # Safety
+    ///
+    /// `table` must point to a valid table, which is not modified during the call
+    pub(crate) unsafe fn row_ptr(table: NonNull<Self>, id: EntityId) -> Option<NonNull<T>> {
+        let index = id.index() as usize;
+        let pages = &*ptr::addr_of!((*table.as_ptr()).pages);
+        let page = pages.get(index / PAGE_SIZE)?.as_ref()?;
+        let page = ptr::addr_of!(**page) as *mut Page<T>;
+        let i = index & PAGE_MASK;
+        let flags = *ptr::addr_of!((*page).filled).cast::<u64>().add(i / 64);
+        if (flags >> (i & 63)) & 1 == 0 {
+            return None;
+        }
+        let row = ptr::addr_of_mut!((*page).data)
+            .cast::<MaybeUninit<T>>()
+            .add(i);
+        NonNull::new(row.cast())
+    }
+
+    /// Record the row of `id` as modified, if it exists and change tracking is enabled.
+    /// Like [row_ptr](Self::row_ptr) no references to the pages are formed.
+    ///
+    /// # Safety
+    ///
+    /// `table` must point to a valid table, which is not accessed otherwise during the call
+    pub(crate) unsafe fn row_changed(table: NonNull<Self>, id: EntityId) {
+        if Self::row_ptr(table, id).is_some() {
+            (*ptr::addr_of_mut!((*table.as_ptr()).changes)).modified(id);
+        }
+    }
+
+    pub fn remove(&mut self, id: EntityId) -> Option<T> {
+        let index = id.index();
         let page_index = index as usize / PAGE_SIZE;
         self.pages
             .get_mut(page_index)
@@ -59,15 +111,19 @@ impl<T> PageTable<T> {
                 // if removal succeeded
                 // TODO if page is empty now, delete the page
                 self.num_entities -= 1;
+                self.changes.removed(id);
                 page
             })
     }
 
     /// Returns the previous value, if any
     pub fn insert(&mut self, index: EntityId, value: T) -> Option<T> {
-        if let Some(existing) = self.get_mut(index) {
+        if self.contains(index) {
+            self.changes.modified(index);
+            let existing = self.get_mut_untracked(index).unwrap();
             Some(std::mem::replace(existing, value))
         } else {
+            self.changes.inserted(index);
             self.num_entities += 1;
             let page_ind = index.index() as usize / PAGE_SIZE;
             if page_ind >= self.pages.len() {
@@ -109,7 +165,9 @@ impl<T> PageTable<T> {
         PTIter::new(it, self.num_entities)
     }
 
+    /// Every row visited is recorded as modified, if change tracking is enabled
     pub fn iter_mut(&mut self) -> impl ExactSizeIterator<Item = (EntityId, &mut T)> {
+        let changes = &mut self.changes;
         let it = self
             .pages
             .iter_mut()
@@ -121,11 +179,21 @@ impl<T> PageTable<T> {
                     id.index += offset as u32;
                     (id, item)
                 })
+            })
+            .map(move |(id, item)| {
+                changes.modified(id);
+                (id, item)
             });
         PTIter::new(it, self.num_entities)
     }
 
     pub fn clear(&mut self) {
+        if self.changes.is_enabled() {
+            let ids = self.iter().map(|(id, _)| id).collect::<Vec<_>>();
+            for id in ids {
+                self.changes.removed(id);
+            }
+        }
         self.num_entities = 0;
         self.pages.clear();
     }
@@ -290,6 +358,24 @@ impl<T: super::TableRow> crate::tables::Table for PageTable<T> {
     fn get(&self, id: Self::Id) -> Option<&Self::Row> {
         self.get(id)
     }
+
+    fn set_change_tick(&mut self, tick: u64) {
+        self.changes.set_tick(tick);
+    }
+
+    fn prune_changes(&mut self, before: u64) {
+        self.changes.prune(before);
+    }
+}
+
+impl<T: super::TableRow> ChangedRows for PageTable<T> {
+    fn change_ticks(&self) -> &ChangeTicks<EntityId> {
+        &self.changes
+    }
+
+    fn set_change_tracking(&mut self, enabled: bool) {
+        self.changes.set_enabled(enabled);
+    }
 }
 
 #[cfg(test)]
@@ -418,4 +504,59 @@ mod tests {
             assert_eq!(exp, actual);
         }
     }
+
+    #[test]
+    fn test_change_tracking() {
+        use crate::tables::{ChangeKind, ChangedRows, Table};
+
+        let mut table = PageTable::<i32>::new(64);
+        table.set_change_tracking(true);
+        table.set_change_tick(1);
+        table.insert(EntityId { index: 1, gen: 0 }, 1);
+        table.insert(EntityId { index: 2, gen: 0 }, 2);
+        table.insert(EntityId { index: 3, gen: 0 }, 3);
+
+        table.set_change_tick(2);
+        *table.get_mut(EntityId { index: 2, gen: 0 }).unwrap() = 42;
+        table.remove(EntityId { index: 3, gen: 0 });
+        assert!(table.get_mut(EntityId { index: 4, gen: 0 }).is_none());
+
+        let changes: Vec<_> = table
+            .changes_since(2)
+            .map(|(id, change)| (id.index(), change.kind))
+            .collect();
+        assert_eq!(
+            changes,
+            vec![(2, ChangeKind::Modified), (3, ChangeKind::Removed)]
+        );
+        assert_eq!(table.changes_since(1).count(), 3);
+
+        table.prune_changes(2);
+        assert_eq!(table.changes_since(0).count(), 2);
+    }
+
+    #[test]
+    fn overwriting_a_row_is_a_modification() {
+        use crate::tables::{ChangeKind, ChangedRows, Table};
+
+        let mut table = PageTable::<i32>::new(64);
+        table.set_change_tracking(true);
+        table.set_change_tick(1);
+        table.insert(EntityId { index: 1, gen: 0 }, 1);
+        table.prune_changes(2);
+
+        table.set_change_tick(2);
+        assert_eq!(table.insert(EntityId { index: 1, gen: 0 }, 2), Some(1));
+        table.insert(EntityId { index: 2, gen: 0 }, 3);
+        table.insert(EntityId { index: 2, gen: 0 }, 4);
+
+        let changes: Vec<_> = table
+            .changes_since(2)
+            .map(|(id, change)| (id.index(), change.kind))
+            .collect();
+        assert_eq!(
+            changes,
+            vec![(1, ChangeKind::Modified), (2, ChangeKind::Inserted)]
+        );
+    }
 }
@@ -0,0 +1,217 @@
+//! Sparse set of entity components.
+//!
+//! Rows are packed tightly, so iteration only visits existing rows, and insertion and removal
+//! are O(1). Intended for components held by few entities, or inserted and removed often, like
+//! the events of a tick.
+//!
+//! Rows are iterated in the order of insertion, until rows are removed. Use a [PageTable] if
+//! the rows need to be iterated in ascending order of their ids.
+//!
+//! [PageTable]: super::page_table::PageTable
+use super::{Table, TableRow};
+use crate::indices::EntityId;
+use std::ptr::{self, NonNull};
+
+const EMPTY: u32 = !0;
+
+pub struct SparseSetTable<T> {
+    /// Index of the row of the entity in `dense`, by entity index
+    sparse: Vec<u32>,
+    ids: Vec<EntityId>,
+    dense: Vec<T>,
+}
+
+impl<T> Default for SparseSetTable<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: std::fmt::Debug> std::fmt::Debug for SparseSetTable<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+impl<T> SparseSetTable<T> {
+    pub fn new() -> Self {
+        Self {
+            sparse: vec![],
+            ids: vec![],
+            dense: vec![],
+        }
+    }
+
+    pub fn with_capacity(cap: usize) -> Self {
+        Self {
+            sparse: vec![],
+            ids: Vec::with_capacity(cap),
+            dense: Vec::with_capacity(cap),
+        }
+    }
+
+    /// Index of the row of `id` in the dense arrays
+    #[inline]
+    fn row(&self, id: EntityId) -> Option<usize> {
+        let row = *self.sparse.get(id.index() as usize)?;
+        (row != EMPTY && self.ids[row as usize] == id).then(|| row as usize)
+    }
+
+    pub fn get(&self, id: EntityId) -> Option<&T> {
+        self.row(id).map(|row| &self.dense[row])
+    }
+
+    pub fn get_mut(&mut self, id: EntityId) -> Option<&mut T> {
+        let row = self.row(id)?;
+        Some(&mut self.dense[row])
+    }
+
+    pub fn contains(&self, id: EntityId) -> bool {
+        self.row(id).is_some()
+    }
+
+    /// Pointer to the row of `id`. No references to the rows are formed, so other rows of the
+    /// table may be borrowed mutably.
+    ///
+    /// # Safety
+    ///
+    /// `table` must point to a valid table, which is not modified during the call
+    pub(crate) unsafe fn row_ptr(table: NonNull<Self>, id: EntityId) -> Option<NonNull<T>> {
+        let table = table.as_ptr();
+        let row = (*table).row(id)?;
+        let dense = ptr::addr_of_mut!((*table).dense);
+        NonNull::new((*dense).as_mut_ptr().add(row))
+    }
+
+    /// Returns the previous value, if any
+    pub fn insert(&mut self, id: EntityId, value: T) -> Option<T> {
+        let index = id.index() as usize;
+        if index >= self.sparse.len() {
+            self.sparse.resize(index + 1, EMPTY);
+        }
+        match self.sparse[index] {
+            EMPTY => {
+                self.sparse[index] = self.dense.len() as u32;
+                self.ids.push(id);
+                self.dense.push(value);
+                None
+            }
+            row => {
+                let row = row as usize;
+                let previous = std::mem::replace(&mut self.dense[row], value);
+                // the row of a previous generation of the entity is dropped
+                let same_entity = std::mem::replace(&mut self.ids[row], id) == id;
+                same_entity.then(|| previous)
+            }
+        }
+    }
+
+    pub fn remove(&mut self, id: EntityId) -> Option<T> {
+        let row = self.row(id)?;
+        self.sparse[id.index() as usize] = EMPTY;
+        self.ids.swap_remove(row);
+        let value = self.dense.swap_remove(row);
+        if let Some(moved) = self.ids.get(row) {
+            self.sparse[moved.index() as usize] = row as u32;
+        }
+        Some(value)
+    }
+
+    pub fn len(&self) -> usize {
+        self.dense.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.dense.is_empty()
+    }
+
+    pub fn iter(&self) -> impl ExactSizeIterator<Item = (EntityId, &T)> {
+        self.ids.iter().copied().zip(self.dense.iter())
+    }
+
+    pub fn iter_mut(&mut self) -> impl ExactSizeIterator<Item = (EntityId, &mut T)> {
+        self.ids.iter().copied().zip(self.dense.iter_mut())
+    }
+
+    /// Ids of the rows, in the order of iteration
+    pub fn ids(&self) -> &[EntityId] {
+        &self.ids
+    }
+
+    /// O(n) in the number of rows, instead of the highest entity index
+    pub fn clear(&mut self) {
+        for id in self.ids.iter() {
+            self.sparse[id.index() as usize] = EMPTY;
+        }
+        self.ids.clear();
+        self.dense.clear();
+    }
+}
+
+impl<T: TableRow> Table for SparseSetTable<T> {
+    type Id = EntityId;
+    type Row = T;
+
+    fn delete(&mut self, id: Self::Id) -> Option<Self::Row> {
+        self.remove(id)
+    }
+
+    fn get(&self, id: Self::Id) -> Option<&Self::Row> {
+        SparseSetTable::get(self, id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_insert_remove() {
+        let mut table = SparseSetTable::<i32>::new();
+        for i in 0..8 {
+            assert!(table.insert(EntityId::new(i * 3, 0), i as i32).is_none());
+        }
+        assert_eq!(table.len(), 8);
+        assert_eq!(table.insert(EntityId::new(6, 0), 42), Some(2));
+
+        assert_eq!(table.remove(EntityId::new(0, 0)), Some(0));
+        assert_eq!(table.remove(EntityId::new(0, 0)), None);
+        assert_eq!(table.remove(EntityId::new(1, 0)), None);
+        assert_eq!(table.len(), 7);
+
+        // the last row was moved in place of the removed one
+        assert_eq!(table.get(EntityId::new(21, 0)), Some(&7));
+        assert_eq!(table.get(EntityId::new(6, 0)), Some(&42));
+        for i in 1..8 {
+            assert!(table.contains(EntityId::new(i * 3, 0)));
+        }
+        assert_eq!(table.iter().count(), 7);
+    }
+
+    #[test]
+    fn test_generations() {
+        let mut table = SparseSetTable::<i32>::new();
+        table.insert(EntityId::new(3, 0), 1);
+        assert!(table.get(EntityId::new(3, 1)).is_none());
+        assert!(table.remove(EntityId::new(3, 1)).is_none());
+
+        assert_eq!(table.insert(EntityId::new(3, 1), 2), None);
+        assert!(table.get(EntityId::new(3, 0)).is_none());
+        assert_eq!(table.get(EntityId::new(3, 1)), Some(&2));
+        assert_eq!(table.len(), 1);
+    }
+
+    #[test]
+    fn test_clear() {
+        let mut table = SparseSetTable::<i32>::new();
+        table.insert(EntityId::new(3, 0), 1);
+        table.insert(EntityId::new(1000, 0), 2);
+        table.clear();
+        assert!(table.is_empty());
+        assert!(table.get(EntityId::new(1000, 0)).is_none());
+
+        table.insert(EntityId::new(1000, 0), 3);
+        let rows = table.iter().map(|(id, x)| (id, *x)).collect::<Vec<_>>();
+        assert_eq!(rows, vec![(EntityId::new(1000, 0), 3)]);
+    }
+}
@@ -40,6 +40,12 @@ pub trait Table {
     fn delete(&mut self, id: Self::Id) -> Option<Self::Row>;
     fn get(&self, id: Self::Id) -> Option<&Self::Row>;
 
+    /// Set the tick subsequent changes are recorded in. No-op for tables not tracking changes.
+    fn set_change_tick(&mut self, _tick: u64) {}
+
+    /// Forget the changes recorded before tick `before`. No-op for tables not tracking changes.
+    fn prune_changes(&mut self, _before: u64) {}
+
     fn name() -> &'static str {
         use std::any::type_name;
 
@@ -5,7 +5,9 @@ use crate::indices::*;
 use crate::intents::*;
 use crate::storage::{
     self,
-    views::{UnsafeView, View},
+    hooks::{default_hooks, ComponentHooks},
+    query::{Fetch, Query, QueryFilter},
+    views::{FromWorldMut, UnsafeView, View},
 };
 use crate::tables::btree_table::BTreeTable;
 use crate::tables::flag_table::SparseFlagTable;
@@ -14,12 +16,16 @@ use crate::tables::morton_hierarchy::MortonGridTable;
 use crate::tables::morton_hierarchy::MortonMortonTable;
 use crate::tables::morton_table::MortonTable;
 use crate::tables::page_table::PageTable;
+use crate::tables::sparse_set_table::SparseSetTable;
 use crate::tables::unique_table::UniqueTable;
 use crate::tables::Component;
+use crate::tables::Table;
 use crate::tables::TableId;
 use crate::Time;
 use crate::{archetype, tables::hex_grid::HexGrid};
 use crate::{components::game_config::GameConfig, prelude::Axial};
+use std::collections::HashSet;
+use std::sync::Arc;
 
 archetype!(
     module room_store key Axial,
@@ -46,12 +52,15 @@ archetype!(
     table EntityScript : PageTable<EntityScript> = script,
     table SpawnComponent : PageTable<SpawnComponent> = spawn,
     table SpawnQueueComponent : PageTable<SpawnQueueComponent> = spawnqueue,
+    table RoomControllerComponent : PageTable<RoomControllerComponent> = controller,
     table OwnedEntity : PageTable<OwnedEntity> = owner,
     table MeleeAttackComponent : PageTable<MeleeAttackComponent> = melee,
-    table SayComponent : PageTable<SayComponent> = say,
-    table MineEventComponent : PageTable<MineEventComponent> = mine_intents,
-    table DropoffEventComponent : PageTable<DropoffEventComponent> = dropoff_intents,
+    table SayComponent : SparseSetTable<SayComponent> = say,
+    table MineEventComponent : SparseSetTable<MineEventComponent> = mine_intents,
+    table DropoffEventComponent : SparseSetTable<DropoffEventComponent> = dropoff_intents,
     table RespawnTimer : PageTable<RespawnTimer> = respawn_timer,
+    table Parent : PageTable<Parent> = parent,
+    table Children : PageTable<Children> = children,
 
     table PathCacheComponent : PageTable<PathCacheComponent> = pathcache,
     table ScriptHistory : PageTable<ScriptHistory> = script_history
@@ -76,16 +85,19 @@ archetype!(
     module resource_store key EmptyKey,
 
     table Time : UniqueTable<EmptyKey, Time> = time,
+    table WorldEvents : UniqueTable<EmptyKey, WorldEvents> = events,
     table Intents<MoveIntent> : UniqueTable<EmptyKey, Intents<MoveIntent>> = move_intents,
     table Intents<SpawnIntent> : UniqueTable<EmptyKey, Intents<SpawnIntent>> = spawn_intents,
     table Intents<MineIntent> : UniqueTable<EmptyKey, Intents<MineIntent>> = mine_intents,
     table Intents<DropoffIntent> : UniqueTable<EmptyKey, Intents<DropoffIntent>> = dropoff_intents,
+    table Intents<UpgradeControllerIntent> : UniqueTable<EmptyKey, Intents<UpgradeControllerIntent>> = upgrade_controller_intents,
     table Intents<LogIntent> : UniqueTable<EmptyKey, Intents<LogIntent>> = log_intents,
     table Intents<CachePathIntent> : UniqueTable<EmptyKey, Intents<CachePathIntent>> = update_path_cache_intents,
     table Intents<MutPathCacheIntent> : UniqueTable<EmptyKey, Intents<MutPathCacheIntent>> = mut_path_cache_intents,
     table Intents<MeleeIntent> : UniqueTable<EmptyKey, Intents<MeleeIntent>> = melee_intents,
     table Intents<ScriptHistoryEntry> : UniqueTable<EmptyKey, Intents<ScriptHistoryEntry>> = script_history_intents,
-    table Intents<SayIntent> : UniqueTable<EmptyKey, Intents<SayIntent>> = say_intents
+    table Intents<SayIntent> : UniqueTable<EmptyKey, Intents<SayIntent>> = say_intents,
+    table LogArchives : UniqueTable<EmptyKey, LogArchives> = log_archives
 );
 
 archetype!(
@@ -132,6 +144,10 @@ pub struct World {
     deferred_deletes: entity_store::DeferredDeletes,
 
     entity_handles: HandleTable,
+
+    hooks: Arc<ComponentHooks>,
+    /// The insert hooks processed the changes recorded before this tick
+    insert_hooks_tick: u64,
 }
 
 macro_rules! impl_hastable {
@@ -173,21 +189,32 @@ impl World {
     /// Moving World around in memory would invalidate views, so let's make sure it doesn't
     /// happen.
     pub(crate) fn new() -> Self {
+        Self::with_hooks(default_hooks())
+    }
+
+    pub(crate) fn with_hooks(hooks: ComponentHooks) -> Self {
         let mut config: config_store::Archetype = Default::default();
         config.game_config.value = Some(Default::default());
+        let mut resources: resource_store::Archetype = Default::default();
+        resources.events.value = Some(Default::default());
+        resources.log_archives.value = Some(Default::default());
 
         let mut res = Self {
             config,
             entities: Default::default(),
             room: Default::default(),
-            resources: Default::default(),
+            resources,
             entity_logs: Default::default(),
             scripts: Default::default(),
             positions: Default::default(),
             deferred_deletes: Default::default(),
             entity_handles: HandleTable::new(5_000_000),
             user: Default::default(),
+            hooks: Arc::new(hooks),
+            insert_hooks_tick: 0,
         };
+        let hooks = Arc::clone(&res.hooks);
+        hooks.enable_change_tracking(&mut res);
 
         // initialize the intent tables
         let botints = crate::intents::BotIntents::default();
@@ -209,6 +236,16 @@ impl World {
         <Self as storage::HasTable<Id, C>>::unsafe_view(self)
     }
 
+    /// Iterate the entities having the components of `Q`. See [Query](storage::query::Query)
+    pub fn query<'a, Q: Fetch<'a>>(&'a mut self) -> Query<'a, Q> {
+        Query::from_world_mut(self)
+    }
+
+    /// Iterate the entities having the components of `Q` and matching the filter `F`
+    pub fn query_filtered<'a, Q: Fetch<'a>, F: QueryFilter>(&'a mut self) -> Query<'a, Q, F> {
+        Query::from_world_mut(self)
+    }
+
     pub fn time(&self) -> u64 {
         let view = &self.resources.time.value;
         view.map(|Time(t)| t).unwrap_or(0)
@@ -216,18 +253,85 @@ impl World {
 
     /// Perform post-tick cleanup on the storage
     pub(crate) fn post_process(&mut self) {
-        for e in self.deferred_deletes.entityid.iter().copied() {
-            self.entity_handles.free(e);
+        // remove hooks may defer the deletion of further entities
+        let mut deleted = HashSet::new();
+        loop {
+            let deletes = std::mem::take(&mut self.deferred_deletes.entityid);
+            if deletes.is_empty() {
+                break;
+            }
+            for id in deletes {
+                if deleted.insert(id) {
+                    self.delete_entity(id);
+                    self.entity_handles.free(id);
+                }
+            }
         }
-        self.deferred_deletes.execute_all(&mut self.entities);
         self.deferred_deletes.clear();
 
+        let tick = self.time();
+        let hooks = Arc::clone(&self.hooks);
+        hooks.run_on_insert(self, self.insert_hooks_tick);
+        self.insert_hooks_tick = tick + 1;
+
         self.resources.time.value = self
             .resources
             .time
             .value
             .map(|Time(x)| Time(x + 1))
             .or(Some(Time(1)));
+
+        self.set_change_tick(self.time());
+    }
+
+    fn set_change_tick(&mut self, tick: u64) {
+        self.entities.set_change_tick(tick);
+        self.room.set_change_tick(tick);
+        self.user.set_change_tick(tick);
+        self.config.set_change_tick(tick);
+        self.resources.set_change_tick(tick);
+        self.scripts.set_change_tick(tick);
+        self.entity_logs.set_change_tick(tick);
+        self.positions.set_change_tick(tick);
+    }
+
+    /// Forget the changes recorded before tick `before` in tables tracking their changes.
+    /// Consumers should call this once they processed the changes of a tick.
+    pub fn prune_changes(&mut self, before: u64) {
+        self.entities.prune_changes(before);
+        self.room.prune_changes(before);
+        self.user.prune_changes(before);
+        self.config.prune_changes(before);
+        self.resources.prune_changes(before);
+        self.scripts.prune_changes(before);
+        self.entity_logs.prune_changes(before);
+        self.positions.prune_changes(before);
+    }
+
+    /// Delete the components of the entity, running their remove hooks.
+    /// Does not free the entity handle.
+    pub(crate) fn delete_entity(&mut self, id: EntityId) {
+        let hooks = Arc::clone(&self.hooks);
+        hooks.run_on_remove(self, id);
+        storage::DeleteById::delete(&mut self.entities, id);
+    }
+
+    /// Make `parent` the parent of `child`. See [relations](storage::relations)
+    pub fn set_parent(&mut self, child: EntityId, parent: EntityId) {
+        storage::relations::set_parent(FromWorldMut::from_world_mut(self), child, parent);
+    }
+
+    /// Unlink `child` from its parent. Returns the previous parent, if any
+    pub fn remove_parent(&mut self, child: EntityId) -> Option<EntityId> {
+        storage::relations::remove_parent(FromWorldMut::from_world_mut(self), child)
+    }
+
+    pub fn children(&self, parent: EntityId) -> &[EntityId] {
+        self.entities
+            .children
+            .get(parent)
+            .map(|Children(ids)| ids.as_slice())
+            .unwrap_or(&[])
     }
 
     pub fn insert_entity(&mut self) -> EntityId {
@@ -319,4 +423,58 @@ mod tests {
         let structures: Vec<_> = world.entities.iterby_structure().collect();
         serde_json::to_string_pretty(&structures).unwrap();
     }
+
+    #[test]
+    fn iter_changed_since_tick() {
+        use crate::tables::{ChangeKind, ChangedRows};
+
+        let mut world = World::new();
+        world.entities.hp.set_change_tracking(true);
+
+        let a = world.insert_entity();
+        let b = world.insert_entity();
+        world
+            .entities
+            .hp
+            .insert(a, HpComponent { hp: 1, hp_max: 1 });
+        world
+            .entities
+            .hp
+            .insert(b, HpComponent { hp: 1, hp_max: 1 });
+        world.post_process();
+        let tick = world.time();
+
+        world.entities.hp.get_mut(a).unwrap().hp = 0;
+        world.entities.hp.delete(b);
+
+        let changes: Vec<_> = world
+            .view::<EntityId, HpComponent>()
+            .iter_changed(tick)
+            .map(|(id, change, hp)| (id, change.kind, hp.map(|hp| hp.hp)))
+            .collect();
+        assert_eq!(
+            changes,
+            vec![
+                (a, ChangeKind::Modified, Some(0)),
+                (b, ChangeKind::Removed, None)
+            ]
+        );
+
+        world.prune_changes(tick);
+        assert_eq!(
+            world
+                .view::<EntityId, HpComponent>()
+                .iter_changed(0)
+                .count(),
+            2
+        );
+        world.prune_changes(tick + 1);
+        assert_eq!(
+            world
+                .view::<EntityId, HpComponent>()
+                .iter_changed(0)
+                .count(),
+            0
+        );
+    }
 }
@@ -0,0 +1,157 @@
+use crate::game_loop::LoopControl;
+use crate::input::admin;
+use crate::protos::{cao_admin, cao_common};
+use caolo_sim::prelude::*;
+use tonic::{Request, Response, Status};
+use tracing::info;
+
+#[derive(Clone)]
+pub struct AdminService {
+    world: crate::WorldContainer,
+    control: LoopControl,
+}
+
+impl std::fmt::Debug for AdminService {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AdminService").finish()
+    }
+}
+
+impl AdminService {
+    pub fn new(world: crate::WorldContainer, control: LoopControl) -> Self {
+        Self { world, control }
+    }
+
+    async fn loop_state(&self) -> Response<cao_admin::LoopState> {
+        let tick_latency_ms = self
+            .world
+            .read()
+            .await
+            .view::<ConfigKey, GameConfig>()
+            .unwrap_value()
+            .target_tick_ms;
+        Response::new(cao_admin::LoopState {
+            paused: self.control.is_paused(),
+            pending_steps: self.control.pending_steps(),
+            tick_latency_ms,
+        })
+    }
+}
+
+#[tonic::async_trait]
+impl cao_admin::admin_server::Admin for AdminService {
+    async fn spawn_entity(
+        &self,
+        request: Request<cao_admin::SpawnEntityMsg>,
+    ) -> Result<Response<cao_admin::EntityIdMsg>, Status> {
+        let mut w = self.world.write().await;
+        admin::spawn_entity(&mut w, request.get_ref())
+            .map(|id| {
+                Response::new(cao_admin::EntityIdMsg {
+                    entity_id: id.into(),
+                })
+            })
+            .map_err(|err| Status::invalid_argument(err.to_string()))
+    }
+
+    async fn delete_entity(
+        &self,
+        request: Request<cao_admin::EntityIdMsg>,
+    ) -> Result<Response<cao_admin::AdminResult>, Status> {
+        let mut w = self.world.write().await;
+        admin::delete_entity(&mut w, request.get_ref().entity_id.into())
+            .map(|_| Response::new(cao_admin::AdminResult {}))
+            .map_err(|err| Status::not_found(err.to_string()))
+    }
+
+    async fn teleport_bot(
+        &self,
+        request: Request<cao_admin::TeleportBotMsg>,
+    ) -> Result<Response<cao_admin::AdminResult>, Status> {
+        let mut w = self.world.write().await;
+        admin::teleport_bot(&mut w, request.get_ref())
+            .map(|_| Response::new(cao_admin::AdminResult {}))
+            .map_err(|err| Status::invalid_argument(err.to_string()))
+    }
+
+    async fn set_component(
+        &self,
+        request: Request<cao_admin::SetComponentMsg>,
+    ) -> Result<Response<cao_admin::AdminResult>, Status> {
+        let mut w = self.world.write().await;
+        admin::set_component(&mut w, request.get_ref())
+            .map(|_| Response::new(cao_admin::AdminResult {}))
+            .map_err(|err| Status::invalid_argument(err.to_string()))
+    }
+
+    async fn pause(
+        &self,
+        _request: Request<cao_common::Empty>,
+    ) -> Result<Response<cao_admin::LoopState>, Status> {
+        info!("Admin pausing the game loop");
+        self.control.pause();
+        Ok(self.loop_state().await)
+    }
+
+    async fn resume(
+        &self,
+        _request: Request<cao_common::Empty>,
+    ) -> Result<Response<cao_admin::LoopState>, Status> {
+        info!("Admin resuming the game loop");
+        self.control.resume();
+        Ok(self.loop_state().await)
+    }
+
+    async fn step(
+        &self,
+        request: Request<cao_admin::StepMsg>,
+    ) -> Result<Response<cao_admin::LoopState>, Status> {
+        let ticks = request.get_ref().ticks.max(1);
+        info!("Admin stepping the game loop {} ticks", ticks);
+        self.control.step(ticks);
+        Ok(self.loop_state().await)
+    }
+
+    async fn set_tick_latency(
+        &self,
+        request: Request<cao_admin::TickLatencyMsg>,
+    ) -> Result<Response<cao_admin::LoopState>, Status> {
+        let tick_latency_ms = request.get_ref().tick_latency_ms;
+        info!("Admin setting the tick latency to {}ms", tick_latency_ms);
+        {
+            let mut w = self.world.write().await;
+            w.unsafe_view::<ConfigKey, GameConfig>()
+                .unwrap_mut()
+                .target_tick_ms = tick_latency_ms;
+        }
+        Ok(self.loop_state().await)
+    }
+
+    async fn get_loop_state(
+        &self,
+        _request: Request<cao_common::Empty>,
+    ) -> Result<Response<cao_admin::LoopState>, Status> {
+        Ok(self.loop_state().await)
+    }
+
+    async fn update_game_config(
+        &self,
+        request: Request<cao_admin::GameConfigUpdate>,
+    ) -> Result<Response<cao_admin::GameConfigMsg>, Status> {
+        let msg = request.get_ref();
+        info!("Admin updating game config {:?}", msg);
+        let mut w = self.world.write().await;
+        let mut config = w.unsafe_view::<ConfigKey, GameConfig>();
+        let config = config.unwrap_mut();
+        if msg.target_tick_ms > 0 {
+            config.target_tick_ms = msg.target_tick_ms;
+        }
+        if msg.execution_limit > 0 {
+            config.execution_limit = msg.execution_limit;
+        }
+        Ok(Response::new(cao_admin::GameConfigMsg {
+            target_tick_ms: config.target_tick_ms,
+            execution_limit: config.execution_limit,
+        }))
+    }
+}
@@ -10,6 +10,18 @@ pub struct Config {
     pub target_tick_ms: u64,
     /// Number of previous world states to hold on to, for slow clients
     pub world_buff_size: u64,
+    /// Path to a YAML file of map generation presets
+    pub map_preset_file: Option<String>,
+    /// Name of the map generation preset to use from `map_preset_file`
+    pub map_preset: String,
+    /// Seed of the world generation, random if not set
+    pub world_seed: Option<u64>,
+    /// Number of rings of rooms added to the overworld when every room is taken. 0 disables growth
+    pub map_growth_rings: u32,
+    /// Number of ticks between the keyframes of the entity delta stream
+    pub keyframe_interval: u64,
+    /// Start the game loop paused, waiting for an admin to resume or step it
+    pub start_paused: bool,
 }
 
 impl Default for Config {
@@ -20,6 +32,12 @@ impl Default for Config {
             world_radius: 8,
             target_tick_ms: 200,
             world_buff_size: 1,
+            map_preset_file: None,
+            map_preset: "default".to_string(),
+            world_seed: None,
+            map_growth_rings: 1,
+            keyframe_interval: 30,
+            start_paused: false,
         }
     }
 }
@@ -50,6 +68,20 @@ impl Config {
             world_buff_size: std::env::var("CAO_WORLD_BUFFER")
                 .map(|i| i.parse::<u64>().unwrap())
                 .unwrap_or(1),
+            map_preset_file: std::env::var("CAO_MAP_PRESET_FILE").ok(),
+            map_preset: std::env::var("CAO_MAP_PRESET").unwrap_or_else(|_| "default".to_string()),
+            world_seed: std::env::var("CAO_WORLD_SEED")
+                .ok()
+                .map(|s| s.parse().expect("expected world seed to be an integer")),
+            map_growth_rings: std::env::var("CAO_MAP_GROWTH_RINGS")
+                .map(|i| i.parse::<u32>().unwrap())
+                .unwrap_or(1),
+            keyframe_interval: std::env::var("CAO_KEYFRAME_INTERVAL")
+                .map(|i| i.parse::<u64>().unwrap())
+                .unwrap_or(30),
+            start_paused: std::env::var("CAO_START_PAUSED")
+                .map(|x| x.parse().expect("expected start paused to be a boolean"))
+                .unwrap_or(false),
         }
     }
 }
@@ -1,22 +1,99 @@
 use std::{
-    sync::Arc,
+    sync::{Arc, Mutex},
     time::{Duration, Instant},
 };
 
-use caolo_sim::executor::SimpleExecutor;
-use tokio::sync::broadcast::Sender;
-use tracing::{debug, info, warn};
+use caolo_sim::{
+    executor::{count_free_rooms, SimpleExecutor},
+    prelude::{ConfigKey, GameConfig},
+};
+use tokio::sync::{broadcast::Sender, Notify};
+use tracing::{debug, error, info, warn};
+
+use crate::{config::Config, world_service, WorldContainer};
+
+#[derive(Debug, Default)]
+struct LoopState {
+    paused: bool,
+    /// Number of ticks to run while paused
+    steps: u64,
+}
+
+/// Pause, resume and step the game loop from the outside
+#[derive(Debug, Clone, Default)]
+pub struct LoopControl {
+    state: Arc<Mutex<LoopState>>,
+    notify: Arc<Notify>,
+}
+
+impl LoopControl {
+    pub fn pause(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.paused = true;
+        state.steps = 0;
+    }
+
+    pub fn resume(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.paused = false;
+        state.steps = 0;
+        self.notify.notify_one();
+    }
+
+    /// Pause the loop after running `ticks` more ticks
+    pub fn step(&self, ticks: u64) {
+        let mut state = self.state.lock().unwrap();
+        state.paused = true;
+        state.steps += ticks;
+        self.notify.notify_one();
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.state.lock().unwrap().paused
+    }
 
-use crate::{world_service, WorldContainer};
+    pub fn pending_steps(&self) -> u64 {
+        self.state.lock().unwrap().steps
+    }
+
+    /// Wait until the next tick may run
+    async fn wait_for_tick(&self) {
+        loop {
+            {
+                let mut state = self.state.lock().unwrap();
+                if !state.paused {
+                    return;
+                }
+                if state.steps > 0 {
+                    state.steps -= 1;
+                    return;
+                }
+            }
+            // `notify_one` stores a permit if we're not waiting yet, so no wakeup is lost
+            self.notify.notified().await;
+        }
+    }
+}
 
 pub async fn game_loop(
     world: WorldContainer,
     mut executor: SimpleExecutor,
     outpayload: Arc<Sender<Arc<world_service::Payload>>>,
-    tick_latency: Duration,
+    map: world_service::MapHandle,
+    config: Config,
+    control: LoopControl,
 ) {
+    let map_growth_rings = config.map_growth_rings;
+    let keyframe_interval = config.keyframe_interval;
     let mut lag = Duration::new(0, 0);
+    let mut last_payload: Option<Arc<world_service::Payload>> = None;
+    let mut ticks_since_keyframe = 0;
     loop {
+        if control.is_paused() {
+            // don't accumulate lag while paused
+            lag = Duration::new(0, 0);
+        }
+        control.wait_for_tick().await;
         let start = Instant::now();
 
         #[cfg(save_world)]
@@ -45,6 +122,13 @@ pub async fn game_loop(
         let world_guard = world.read().await;
         let sp = tracing::error_span!("game-loop", tick = world_guard.time());
         let _e = sp.enter();
+        // may be changed at runtime by the admins
+        let tick_latency = Duration::from_millis(
+            world_guard
+                .view::<ConfigKey, GameConfig>()
+                .unwrap_value()
+                .target_tick_ms,
+        );
 
         let intents = executor.forward_bots(&world_guard).await.unwrap();
         drop(world_guard); // free the read guard
@@ -57,16 +141,53 @@ pub async fn game_loop(
             .apply_intents(&mut world_guard, intents)
             .await
             .unwrap();
+        if let Some(pl) = last_payload.as_ref() {
+            // the last payload consumed the changes before its tick
+            world_guard.prune_changes(pl.time);
+        }
+        let mut map_update = None;
+        if map_growth_rings > 0 && count_free_rooms(&world_guard) == 0 {
+            info!("No free rooms left, growing the overworld");
+            match executor
+                .grow_overworld(&mut world_guard, map_growth_rings)
+                .await
+            {
+                Ok(extension) => {
+                    let mut rooms = extension.new_rooms;
+                    rooms.extend(extension.modified_rooms);
+                    map_update = Some(world_service::MapCache::from_rooms(
+                        &world_guard,
+                        rooms.as_slice(),
+                    ));
+                }
+                Err(err) => error!("Failed to grow the overworld: {}", err),
+            }
+        }
         drop(world_guard); // free the write guard
 
+        if let Some(update) = map_update {
+            map.update_rooms(update).await;
+        }
+
+        let keyframe = ticks_since_keyframe >= keyframe_interval;
         let world_guard = world.read().await;
         let mut pl = world_service::Payload::default();
-        pl.update(&world_guard);
+        // keyframes are serialized in full
+        pl.update(&world_guard, last_payload.as_deref().filter(|_| !keyframe));
         drop(world_guard); // free the read guard
 
+        pl.update_deltas(last_payload.as_deref(), keyframe);
+        ticks_since_keyframe = if pl.keyframe {
+            0
+        } else {
+            ticks_since_keyframe + 1
+        };
+        let pl = Arc::new(pl);
+        last_payload = Some(Arc::clone(&pl));
+
         if outpayload.receiver_count() > 0 {
             debug!("Sending world entities to subscribers");
-            if outpayload.send(Arc::new(pl)).is_err() {
+            if outpayload.send(pl).is_err() {
                 // happens if the subscribers disconnect while we sent the payload
                 warn!("Lost all world subscribers");
             }
@@ -95,3 +216,27 @@ pub async fn game_loop(
         tokio::time::sleep(sleep_duration).await;
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stepping_runs_the_requested_ticks_then_pauses() {
+        let control = LoopControl::default();
+        futures_lite::future::block_on(control.wait_for_tick());
+
+        control.step(2);
+        assert!(control.is_paused());
+        assert_eq!(control.pending_steps(), 2);
+
+        futures_lite::future::block_on(control.wait_for_tick());
+        futures_lite::future::block_on(control.wait_for_tick());
+        assert!(control.is_paused());
+        assert_eq!(control.pending_steps(), 0);
+
+        control.resume();
+        futures_lite::future::block_on(control.wait_for_tick());
+        assert!(!control.is_paused());
+    }
+}
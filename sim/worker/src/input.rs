@@ -1,4 +1,5 @@
 //! Handle inputs received via the message bus
+pub mod admin;
 pub mod rooms;
 pub mod script_update;
 pub mod structures;
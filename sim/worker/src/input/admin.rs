@@ -0,0 +1,310 @@
+//! Direct world manipulation by the administrators
+use crate::protos::{
+    cao_admin::{EntityKind, SetComponentMsg, SpawnEntityMsg, TeleportBotMsg},
+    cao_common,
+};
+use caolo_sim::prelude::*;
+use thiserror::Error;
+use tracing::{error, info};
+use uuid::Uuid;
+
+#[derive(Debug, Error)]
+pub enum AdminError {
+    #[error("Missing expected field {0}")]
+    MissingField(&'static str),
+
+    #[error("Failed to parse owner id")]
+    OwnerIdError,
+
+    #[error("Unrecognized entity kind {0}")]
+    BadKind(i32),
+
+    #[error("Entity {0:?} does not exist")]
+    EntityNotFound(EntityId),
+
+    #[error("Entity {0:?} is not a bot")]
+    NotABot(EntityId),
+
+    #[error("position {0:?} is not valid!")]
+    InvalidPosition(WorldPosition),
+
+    #[error("position {0:?} is taken!")]
+    TakenPosition(WorldPosition),
+
+    #[error("Unrecognized component {0}")]
+    UnknownComponent(String),
+
+    #[error("Failed to deserialize component value: {0}")]
+    BadComponentValue(serde_json::Error),
+}
+
+pub fn spawn_entity(world: &mut World, msg: &SpawnEntityMsg) -> Result<EntityId, AdminError> {
+    info!("Admin spawning entity {:?}", msg);
+
+    let kind = EntityKind::from_i32(msg.kind).ok_or(AdminError::BadKind(msg.kind))?;
+    let pos = parse_position(msg.position.as_ref())?;
+    check_free_position(world, pos)?;
+    let owner = msg.owner_id.as_ref().map(parse_uuid).transpose()?;
+    // validate before allocating the entity, so failed spawns do not leak handles
+    if matches!(kind, EntityKind::Spawn) && owner.is_none() {
+        return Err(AdminError::MissingField("owner_id"));
+    }
+
+    let id = world.insert_entity();
+    match kind {
+        EntityKind::Bot => {
+            caolo_sim::entity_archetypes::init_bot(
+                id,
+                owner,
+                pos,
+                FromWorldMut::from_world_mut(world),
+                world.view(),
+            );
+            world
+                .unsafe_view::<WorldPosition, EntityComponent>()
+                .insert(pos, EntityComponent(id))
+                .expect("entities_by_pos insert failed");
+        }
+        EntityKind::Resource => {
+            caolo_sim::entity_archetypes::init_resource_energy(
+                id,
+                Room(pos.room),
+                pos,
+                FromWorldMut::from_world_mut(world),
+                FromWorld::from_world(world),
+            );
+        }
+        EntityKind::Spawn => {
+            let owner = owner.expect("owner_id is validated above");
+            caolo_sim::entity_archetypes::init_structure_spawn(id, owner, pos, world);
+        }
+    }
+    info!("Admin spawned {:?} {:?} at {:?}", kind, id, pos);
+    Ok(id)
+}
+
+/// The entity is removed at the end of the current tick
+pub fn delete_entity(world: &mut World, id: EntityId) -> Result<(), AdminError> {
+    info!("Admin deleting entity {:?}", id);
+    if !world.is_valid_entity(id) {
+        return Err(AdminError::EntityNotFound(id));
+    }
+    world.deferred_delete(id);
+    Ok(())
+}
+
+pub fn teleport_bot(world: &mut World, msg: &TeleportBotMsg) -> Result<(), AdminError> {
+    info!("Admin teleporting bot {:?}", msg);
+
+    let id = parse_entity_id(world, msg.entity_id)?;
+    if !world.view::<EntityId, Bot>().contains(&id) {
+        return Err(AdminError::NotABot(id));
+    }
+    let pos = parse_position(msg.position.as_ref())?;
+    check_free_position(world, pos)?;
+
+    world
+        .unsafe_view::<EntityId, PositionComponent>()
+        .insert(id, PositionComponent(pos));
+    // the old position is cleared when the positions table is rebuilt on the next tick
+    world
+        .unsafe_view::<WorldPosition, EntityComponent>()
+        .insert(pos, EntityComponent(id))
+        .expect("entities_by_pos insert failed");
+    Ok(())
+}
+
+/// Overwrite a component of the entity with the deserialized `value`
+pub fn set_component(world: &mut World, msg: &SetComponentMsg) -> Result<(), AdminError> {
+    info!(
+        "Admin setting component {} of entity {}",
+        msg.component, msg.entity_id
+    );
+
+    let id = parse_entity_id(world, msg.entity_id)?;
+    let value = msg
+        .value
+        .as_ref()
+        .ok_or(AdminError::MissingField("value"))?
+        .value
+        .as_slice();
+
+    macro_rules! set_component {
+        ($($name: literal => $component: ty),* $(,)?) => {
+            match msg.component.as_str() {
+                $(
+                    $name => {
+                        let value: $component =
+                            serde_json::from_slice(value).map_err(AdminError::BadComponentValue)?;
+                        world.unsafe_view::<EntityId, $component>().insert(id, value);
+                    }
+                )*
+                _ => return Err(AdminError::UnknownComponent(msg.component.clone())),
+            }
+        };
+    }
+
+    set_component!(
+        "hp" => HpComponent,
+        "energy" => EnergyComponent,
+        "energyRegen" => EnergyRegenComponent,
+        "carry" => CarryComponent,
+        "decay" => DecayComponent,
+        "melee" => MeleeAttackComponent,
+        "owner" => OwnedEntity,
+    );
+    Ok(())
+}
+
+fn parse_entity_id(world: &World, id: u64) -> Result<EntityId, AdminError> {
+    let id = EntityId::from(id);
+    if !world.is_valid_entity(id) {
+        return Err(AdminError::EntityNotFound(id));
+    }
+    Ok(id)
+}
+
+fn parse_uuid(id: &cao_common::Uuid) -> Result<Uuid, AdminError> {
+    Uuid::from_slice(id.data.as_slice()).map_err(|err| {
+        error!("Failed to parse owner id {:?}", err);
+        AdminError::OwnerIdError
+    })
+}
+
+fn parse_position(
+    position: Option<&cao_common::WorldPosition>,
+) -> Result<WorldPosition, AdminError> {
+    let position = position.ok_or(AdminError::MissingField("position"))?;
+    let pos = position
+        .pos
+        .as_ref()
+        .ok_or(AdminError::MissingField("position.pos"))?;
+    let room = position
+        .room
+        .as_ref()
+        .ok_or(AdminError::MissingField("position.room"))?;
+    Ok(WorldPosition {
+        room: Axial::new(room.q, room.r),
+        pos: Axial::new(pos.q, pos.r),
+    })
+}
+
+fn check_free_position(world: &World, position: WorldPosition) -> Result<(), AdminError> {
+    let is_valid_terrain = world
+        .view::<WorldPosition, TerrainComponent>()
+        .get(position)
+        .map(|TerrainComponent(t)| t.is_walkable())
+        .unwrap_or(false);
+    if !is_valid_terrain {
+        return Err(AdminError::InvalidPosition(position));
+    }
+
+    let is_free = world
+        .view::<WorldPosition, EntityComponent>()
+        .get(position)
+        .is_none();
+    if !is_free {
+        return Err(AdminError::TakenPosition(position));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn can_set_components() {
+        let mut exc = SimpleExecutor::default();
+        let mut w =
+            futures_lite::future::block_on(exc.initialize(caolo_sim::executor::GameConfig {
+                world_radius: 1,
+                room_radius: 10,
+                ..Default::default()
+            }));
+
+        let id = w.insert_entity();
+        caolo_sim::query!(
+            mutate
+            w
+            {
+                EntityId, Bot, .insert(id);
+                EntityId, HpComponent, .insert(id, HpComponent { hp: 42, hp_max: 100 });
+            }
+        );
+
+        set_component(
+            &mut w,
+            &SetComponentMsg {
+                entity_id: id.into(),
+                component: "hp".to_string(),
+                value: Some(cao_common::Json {
+                    value: br#"{"hp": 69, "hpMax": 420}"#.to_vec(),
+                }),
+            },
+        )
+        .expect("Failed to set hp");
+
+        let hp = w.view::<EntityId, HpComponent>().get(id).unwrap().hp;
+        assert_eq!(hp, 69);
+
+        let err = set_component(
+            &mut w,
+            &SetComponentMsg {
+                entity_id: id.into(),
+                component: "winnie".to_string(),
+                value: Some(cao_common::Json {
+                    value: b"{}".to_vec(),
+                }),
+            },
+        )
+        .unwrap_err();
+        assert!(matches!(err, AdminError::UnknownComponent(_)));
+    }
+
+    #[test]
+    fn spawn_without_owner_does_not_allocate_an_entity() {
+        let mut exc = SimpleExecutor::default();
+        let mut w =
+            futures_lite::future::block_on(exc.initialize(caolo_sim::executor::GameConfig {
+                world_radius: 1,
+                room_radius: 10,
+                ..Default::default()
+            }));
+
+        let pos = w
+            .view::<WorldPosition, TerrainComponent>()
+            .iter()
+            .find(|(pos, TerrainComponent(t))| {
+                t.is_walkable() && check_free_position(&w, *pos).is_ok()
+            })
+            .map(|(pos, _)| pos)
+            .expect("Failed to find a free position");
+
+        let before = w.insert_entity();
+        let err = spawn_entity(
+            &mut w,
+            &SpawnEntityMsg {
+                kind: EntityKind::Spawn as i32,
+                position: Some(cao_common::WorldPosition {
+                    room: Some(cao_common::Axial {
+                        q: pos.room.q,
+                        r: pos.room.r,
+                    }),
+                    pos: Some(cao_common::Axial {
+                        q: pos.pos.q,
+                        r: pos.pos.r,
+                    }),
+                    offset: None,
+                }),
+                owner_id: None,
+            },
+        )
+        .unwrap_err();
+        assert!(matches!(err, AdminError::MissingField("owner_id")));
+
+        // the next handle is the one following `before`
+        let after = w.insert_entity();
+        assert_eq!(u64::from(after), u64::from(before) + 1);
+    }
+}
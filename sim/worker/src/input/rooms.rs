@@ -1,5 +1,4 @@
 use crate::protos::cao_commands::TakeRoomCommand;
-use anyhow::Context;
 use caolo_sim::prelude::*;
 use thiserror::Error;
 use tracing::{info, trace};
@@ -11,8 +10,6 @@ pub enum TakeRoomError {
     Owned,
     #[error("Maximum number of rooms ({0}) owned already")]
     MaxRoomsExceeded(usize),
-    #[error("Internal error: {0}")]
-    InternalError(anyhow::Error),
     #[error("User by id {0} was not registered")]
     NotRegistered(Uuid),
     #[error("Missing expected field {0}")]
@@ -53,18 +50,12 @@ pub fn take_room(world: &mut World, msg: &TakeRoomCommand) -> Result<(), TakeRoo
         return Err(TakeRoomError::Owned);
     }
 
-    let rooms = world
-        .view::<UserId, Rooms>()
-        .reborrow()
-        .get(UserId(user_id));
-    let num_rooms = rooms.map(|x| x.0.len()).unwrap_or(0);
-
     let props = world
         .view::<UserId, UserProperties>()
         .reborrow()
         .get(UserId(user_id));
 
-    let available_rooms = match props.map(|p| p.level) {
+    let level = match props.map(|p| p.level) {
         Some(l) => l,
         None => {
             info!("Room is not registered");
@@ -72,27 +63,58 @@ pub fn take_room(world: &mut World, msg: &TakeRoomCommand) -> Result<(), TakeRoo
         }
     };
 
-    if num_rooms > available_rooms as usize {
+    if !caolo_sim::intents::can_claim_room(
+        UserId(user_id),
+        FromWorld::from_world(world),
+        FromWorld::from_world(world),
+    ) {
         info!("User would exceed max rooms");
-        return Err(TakeRoomError::MaxRoomsExceeded(available_rooms as usize));
+        return Err(TakeRoomError::MaxRoomsExceeded(level as usize));
     }
-    let mut rooms = rooms.cloned().unwrap_or_default();
-    rooms.0.push(Room(room_id));
-
-    world
-        .unsafe_view::<Axial, OwnedEntity>()
-        .insert(
-            room_id,
-            OwnedEntity {
-                owner_id: UserId(user_id),
-            },
-        )
-        .with_context(|| "Failed to insert the new owner")
-        .map_err(TakeRoomError::InternalError)?;
-
-    world
-        .unsafe_view::<UserId, Rooms>()
-        .insert(UserId(user_id), rooms);
+    caolo_sim::init::claim_room(UserId(user_id), Room(room_id), world);
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protos::cao_common;
+
+    fn take_room_msg(user_id: Uuid, room_id: Axial) -> TakeRoomCommand {
+        TakeRoomCommand {
+            user_id: Some(cao_common::Uuid {
+                data: user_id.as_bytes().to_vec(),
+            }),
+            room_id: Some(cao_common::Axial {
+                q: room_id.q,
+                r: room_id.r,
+            }),
+        }
+    }
+
+    #[test]
+    fn users_may_take_as_many_rooms_as_their_level() {
+        let mut exc = SimpleExecutor::default();
+        let mut w =
+            futures_lite::future::block_on(exc.initialize(caolo_sim::executor::GameConfig {
+                world_radius: 1,
+                room_radius: 10,
+                ..Default::default()
+            }));
+
+        let user_id = Uuid::new_v4();
+        w.unsafe_view::<UserId, UserProperties>()
+            .insert(UserId(user_id), UserProperties { level: 1 });
+
+        let rooms: Vec<Axial> = w
+            .view::<Axial, RoomComponent>()
+            .iter()
+            .map(|(room_id, _)| room_id)
+            .collect();
+
+        take_room(&mut w, &take_room_msg(user_id, rooms[0])).expect("Failed to take the room");
+        let err = take_room(&mut w, &take_room_msg(user_id, rooms[1])).unwrap_err();
+        assert!(matches!(err, TakeRoomError::MaxRoomsExceeded(1)));
+    }
+}
@@ -3,12 +3,14 @@ mod game_loop;
 mod input;
 mod protos;
 
+mod admin_service;
 mod command_service;
 mod health_service;
 mod scripting_service;
 mod users_service;
 mod world_service;
 
+use crate::protos::cao_admin::admin_server::AdminServer;
 use crate::protos::cao_commands::command_server::CommandServer;
 use crate::protos::cao_common::health_server::HealthServer;
 use crate::protos::cao_script::scripting_server::ScriptingServer;
@@ -78,21 +80,37 @@ async fn main() {
     let world_span = tracing::error_span!("world-service", queen_tag = tag.as_str());
     let game_loop_span = tracing::error_span!("game-loop", queen_tag = tag.as_str());
 
+    let map_generation = match config.map_preset_file.as_ref() {
+        Some(path) => {
+            info!("Loading map preset {} from {}", config.map_preset, path);
+            caolo_sim::map_generation::presets::load_preset(path, config.map_preset.as_str())
+                .expect("Failed to load map generation preset")
+        }
+        None => Default::default(),
+    };
+
+    let mut game_config = GameConfig {
+        world_radius: config.world_radius,
+        room_radius: config.room_radius,
+        target_tick_ms: config.target_tick_ms,
+        queen_tag: tag.clone(),
+        map_generation,
+        ..Default::default()
+    };
+    if let Some(world_seed) = config.world_seed {
+        game_config.world_seed = world_seed;
+    }
+    info!("Using world seed {}", game_config.world_seed);
+
     info!("Creating cao executor with tag {}", tag);
-    let mut executor = SimpleExecutor;
+    let mut executor = SimpleExecutor::default();
     info!("Init storage");
-    let mut world = executor
-        .initialize(GameConfig {
-            world_radius: config.world_radius,
-            room_radius: config.room_radius,
-            queen_tag: tag.clone(),
-            ..Default::default()
-        })
-        .await;
+    let mut world = executor.initialize(game_config).await;
 
     info!("Starting with {} actors", config.n_actors);
 
     caolo_sim::init::init_world_entities(&mut world, config.n_actors as usize);
+    world_service::enable_change_tracking(&mut world);
 
     let addr = env::var("CAO_SERVICE_ADDR")
         .ok()
@@ -111,23 +129,15 @@ async fn main() {
             .radius as i32,
     );
 
-    let terrain = world
-        .view::<caolo_sim::prelude::WorldPosition, caolo_sim::prelude::TerrainComponent>()
-        .iter_rooms()
-        .map(|(room_id, room_terrain)| {
-            (
-                room_id.0,
-                room_terrain.iter().map(|(_, t)| t).copied().collect(),
-            )
-        })
-        .collect();
-    let rooms = world
-        .view::<caolo_sim::prelude::Axial, caolo_sim::prelude::RoomComponent>()
-        .iter()
-        .map(|(room_id, comp)| (room_id, *comp))
-        .collect();
+    let map =
+        world_service::MapHandle::new(&world, room_bounds.radius, config.world_buff_size as usize);
 
     let world = Arc::new(tokio::sync::RwLock::new(world));
+    let loop_control = game_loop::LoopControl::default();
+    if config.start_paused {
+        info!("Starting the game loop paused");
+        loop_control.pause();
+    }
 
     let server = tonic::transport::Server::builder()
         .trace_fn(move |_| tracing::error_span!("service", queen_tag = tag.as_str()))
@@ -138,20 +148,24 @@ async fn main() {
             crate::scripting_service::ScriptingService::new(Arc::clone(&world)),
         ))
         .add_service(WorldServer::new(crate::world_service::WorldService::new(
+            Arc::clone(&world),
             Arc::clone(&outpayload),
             room_bounds,
-            Arc::new(terrain),
-            Arc::new(rooms),
+            map.clone(),
             world_span,
         )))
+        .add_service(AdminServer::new(admin_service::AdminService::new(
+            Arc::clone(&world),
+            loop_control.clone(),
+        )))
         .add_service(HealthServer::new(health_service::HealthService {}))
         .add_service(UsersServer::new(crate::users_service::UsersService::new(
             Arc::clone(&world),
         )))
         .serve(addr);
 
-    let game_loop =
-        game_loop::game_loop(world, executor, outpayload, tick_latency).instrument(game_loop_span);
+    let game_loop = game_loop::game_loop(world, executor, outpayload, map, config, loop_control)
+        .instrument(game_loop_span);
 
     info!(
         "Initialization done in {:?}",
@@ -18,6 +18,10 @@ pub mod cao_intents {
     tonic::include_proto!("cao_intents");
 }
 
+pub mod cao_admin {
+    tonic::include_proto!("cao_admin");
+}
+
 pub mod cao_users {
     tonic::include_proto!("cao_users");
 }
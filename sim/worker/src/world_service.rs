@@ -1,20 +1,27 @@
+mod delta;
+mod entity;
 mod ser_bots;
 mod ser_resources;
 mod ser_structures;
+mod subscription;
 mod util;
 mod world_events;
 
 use caolo_sim::{
-    components::RoomComponent,
-    prelude::{Axial, Hexagon, TerrainComponent, World},
+    components::*,
+    prelude::{
+        Axial, Component, EmptyKey, EntityId, EntityTime, HasTable, Hexagon, World, WorldPosition,
+    },
+    tables::{ChangedRows, TableId},
 };
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::convert::TryInto;
 use std::sync::Arc;
 use tokio::sync::{
     broadcast::{error::RecvError, Sender},
-    mpsc,
+    mpsc, watch, RwLock,
 };
-use tokio_stream::wrappers::ReceiverStream;
+use tokio_stream::{wrappers::ReceiverStream, StreamExt};
 use tonic::Status;
 use tracing::{info, log::warn, Instrument};
 
@@ -23,10 +30,10 @@ use crate::protos::cao_world;
 
 #[derive(Clone)]
 pub struct WorldService {
+    world: crate::WorldContainer,
     entities: WorldPayloadSender,
     room_bounds: Hexagon,
-    terrain: Arc<HashMap<Axial, Vec<TerrainComponent>>>,
-    rooms: Arc<HashMap<Axial, RoomComponent>>,
+    map: MapHandle,
     tracing_span: tracing::Span,
 }
 
@@ -38,55 +45,378 @@ impl std::fmt::Debug for WorldService {
 
 type WorldPayloadSender = Arc<Sender<Arc<Payload>>>;
 
+/// Snapshot of the static parts of the map, so queries do not have to lock the world
+#[derive(Default, Debug)]
+pub struct MapCache {
+    pub terrain: HashMap<Axial, Vec<TerrainComponent>>,
+    pub rooms: HashMap<Axial, RoomComponent>,
+}
+
+impl MapCache {
+    pub fn from_world(world: &World) -> Self {
+        let rooms: Vec<Axial> = world
+            .view::<Axial, RoomComponent>()
+            .iter()
+            .map(|(room_id, _)| room_id)
+            .collect();
+        Self::from_rooms(world, rooms.as_slice())
+    }
+
+    /// Snapshot of the given rooms of the world
+    pub fn from_rooms(world: &World, rooms: &[Axial]) -> Self {
+        let mut cache = Self::default();
+        let terrain = world.view::<WorldPosition, TerrainComponent>();
+        let room_components = world.view::<Axial, RoomComponent>();
+        for room_id in rooms.iter().copied() {
+            if let Some(room) = room_components.at(room_id) {
+                cache.rooms.insert(room_id, *room);
+            }
+            if let Some(room_terrain) = terrain.table.at(room_id) {
+                cache.terrain.insert(
+                    room_id,
+                    room_terrain.iter().map(|(_, t)| t).copied().collect(),
+                );
+            }
+        }
+        cache
+    }
+
+    /// Replace the rooms of `self` by the rooms in `update`
+    pub fn merge(&mut self, update: Self) {
+        self.rooms.extend(update.rooms);
+        self.terrain.extend(update.terrain);
+    }
+
+    fn room_msg(&self, room_id: Axial, radius: i32) -> Option<cao_world::Room> {
+        let room = self.rooms.get(&room_id)?;
+        let offset = room.offset;
+        Some(cao_world::Room {
+            room_id: Some(cao_common::Axial {
+                q: room_id.q,
+                r: room_id.r,
+            }),
+            radius,
+            offset: Some(cao_common::Axial {
+                q: offset.q,
+                r: offset.r,
+            }),
+            seed: room.seed,
+        })
+    }
+}
+
+/// Shared [MapCache] and the channel notifying clients about the changes of the map
+#[derive(Clone)]
+pub struct MapHandle {
+    cache: Arc<RwLock<MapCache>>,
+    updates: Arc<Sender<cao_world::RoomList>>,
+    room_radius: i32,
+}
+
+impl MapHandle {
+    pub fn new(world: &World, room_radius: i32, buffer_size: usize) -> Self {
+        let (updates, _) = tokio::sync::broadcast::channel(buffer_size.max(1));
+        Self {
+            cache: Arc::new(RwLock::new(MapCache::from_world(world))),
+            updates: Arc::new(updates),
+            room_radius,
+        }
+    }
+
+    /// Update the cache by a snapshot of the changed rooms, see [MapCache::from_rooms], and
+    /// notify the subscribers.
+    ///
+    /// Takes a snapshot instead of the World, so the World is not locked while the update is
+    /// sent.
+    pub async fn update_rooms(&self, update: MapCache) {
+        let rooms = update.rooms.keys().copied().collect::<Vec<_>>();
+        let mut cache = self.cache.write().await;
+        cache.merge(update);
+        let list = cao_world::RoomList {
+            rooms: rooms
+                .iter()
+                .filter_map(|room_id| cache.room_msg(*room_id, self.room_radius))
+                .collect(),
+        };
+        drop(cache);
+
+        if self.updates.receiver_count() > 0 && self.updates.send(list).is_err() {
+            warn!("Lost all room update subscribers");
+        }
+    }
+}
+
 #[derive(Default, Debug)]
 pub struct Payload {
     pub payload_by_room: HashMap<Axial, cao_world::RoomEntities>,
+    /// Changes since the previous payload, see [Payload::update_deltas]
+    pub deltas_by_room: HashMap<Axial, cao_world::RoomEntitiesDelta>,
+    pub keyframe: bool,
+    /// World time the payload was built at
+    pub time: u64,
+    /// Room of every serialized entity
+    entity_rooms: HashMap<EntityId, Axial>,
+    /// Rooms holding data of the last tick only, like events and logs
+    transient_rooms: HashSet<Axial>,
 }
 
 impl WorldService {
     pub fn new(
+        world: crate::WorldContainer,
         entities: WorldPayloadSender,
         room_bounds: Hexagon,
-        terrain: Arc<HashMap<Axial, Vec<TerrainComponent>>>,
-        rooms: Arc<HashMap<Axial, RoomComponent>>,
+        map: MapHandle,
         span: tracing::Span,
     ) -> Self {
         Self {
+            world,
             entities,
             room_bounds,
-            terrain,
+            map,
             tracing_span: span,
-            rooms,
         }
     }
 }
 
 impl Payload {
-    /// Transform the usual json serialized world into Payload
-    pub fn update(&mut self, world: &World) {
+    /// Transform the world into Payload.
+    ///
+    /// Only the rooms that changed since the `prev` payload are serialized, the rest are taken
+    /// from `prev`. If `prev` is None every room is serialized.
+    ///
+    /// Requires change tracking on the tables of the payload, see [enable_change_tracking].
+    pub fn update(&mut self, world: &World, prev: Option<&Payload>) {
         self.payload_by_room.clear();
+        self.entity_rooms.clear();
+        self.transient_rooms.clear();
+        self.time = world.time();
+
+        let changed = prev.map(|prev| changed_rooms(world, prev));
+        if let Some((prev, changed)) = prev.zip(changed.as_ref()) {
+            let time = self.time as i64;
+            self.payload_by_room.extend(
+                prev.payload_by_room
+                    .iter()
+                    .filter(|(room_id, _)| !changed.contains(room_id))
+                    .map(|(room_id, pl)| {
+                        let mut pl = pl.clone();
+                        pl.world_time = time;
+                        (*room_id, pl)
+                    }),
+            );
+            self.entity_rooms.extend(
+                prev.entity_rooms
+                    .iter()
+                    .filter(|(_, room_id)| !changed.contains(room_id)),
+            );
+        }
+        let filter = |room_id: Axial| {
+            changed
+                .as_ref()
+                .map(|changed| changed.contains(&room_id))
+                .unwrap_or(true)
+        };
+
         ser_bots::bot_payload(
             &mut self.payload_by_room,
+            filter,
             caolo_sim::prelude::FromWorld::from_world(world),
         );
         ser_structures::structure_payload(
             &mut self.payload_by_room,
+            filter,
             caolo_sim::prelude::FromWorld::from_world(world),
         );
         ser_resources::resource_payload(
             &mut self.payload_by_room,
+            filter,
             caolo_sim::prelude::FromWorld::from_world(world),
         );
         world_events::events_payload(
             &mut self.payload_by_room,
+            filter,
             caolo_sim::prelude::FromWorld::from_world(world),
+        );
+
+        for (room_id, pl) in self
+            .payload_by_room
+            .iter()
+            .filter(|(room_id, _)| filter(**room_id))
+        {
+            let room_id = *room_id;
+            self.entity_rooms.extend(
+                pl.bots
+                    .iter()
+                    .map(|b| b.id)
+                    .chain(pl.structures.iter().map(|s| s.id))
+                    .chain(pl.resources.iter().map(|r| r.id))
+                    .map(|id| (EntityId::from(id), room_id)),
+            );
+            if is_transient(pl) {
+                self.transient_rooms.insert(room_id);
+            }
+        }
+    }
+
+    /// Compute the changes since the `prev` payload.
+    /// If `prev` is None or `keyframe` is set every room is sent in full.
+    pub fn update_deltas(&mut self, prev: Option<&Payload>, keyframe: bool) {
+        self.deltas_by_room.clear();
+        let prev = match prev {
+            Some(prev) if !keyframe => prev,
+            _ => {
+                self.keyframe = true;
+                self.deltas_by_room.extend(
+                    self.payload_by_room
+                        .iter()
+                        .map(|(room_id, pl)| (*room_id, delta::keyframe(pl))),
+                );
+                return;
+            }
+        };
+        self.keyframe = false;
+        for (room_id, pl) in self.payload_by_room.iter() {
+            if let Some(d) = delta::room_delta(prev.payload_by_room.get(room_id), pl) {
+                self.deltas_by_room.insert(*room_id, d);
+            }
+        }
+        let world_time = self
+            .payload_by_room
+            .values()
+            .map(|pl| pl.world_time)
+            .next()
+            .unwrap_or_default();
+        for (room_id, pl) in prev.payload_by_room.iter() {
+            if !self.payload_by_room.contains_key(room_id) {
+                self.deltas_by_room
+                    .insert(*room_id, delta::emptied_room(*room_id, world_time, pl));
+            }
+        }
+    }
+}
+
+/// Enable change tracking on the tables read by [Payload::update]
+pub fn enable_change_tracking(world: &mut World) {
+    fn track<Id: TableId, C: Component<Id>>(world: &mut World)
+    where
+        World: HasTable<Id, C>,
+        C::Table: ChangedRows<Id = Id>,
+    {
+        world.unsafe_view::<Id, C>().set_change_tracking(true);
+    }
+    track::<EntityId, PositionComponent>(world);
+    track::<EntityId, CarryComponent>(world);
+    track::<EntityId, HpComponent>(world);
+    track::<EntityId, MeleeAttackComponent>(world);
+    track::<EntityId, DecayComponent>(world);
+    track::<EntityId, OwnedEntity>(world);
+    track::<EntityId, EntityScript>(world);
+    track::<EntityId, EnergyComponent>(world);
+    track::<EntityId, EnergyRegenComponent>(world);
+    track::<EntityId, SpawnComponent>(world);
+    track::<EntityId, SpawnQueueComponent>(world);
+    track::<EntityId, RoomControllerComponent>(world);
+    track::<EntityId, ResourceComponent>(world);
+    track::<EntityTime, LogEntry>(world);
+}
+
+/// Rooms whose payload may differ from `prev`
+fn changed_rooms(world: &World, prev: &Payload) -> HashSet<Axial> {
+    fn changed<C: Component<EntityId>>(
+        world: &World,
+        since: u64,
+    ) -> impl Iterator<Item = EntityId> + '_
+    where
+        World: HasTable<EntityId, C>,
+        C::Table: ChangedRows<Id = EntityId>,
+    {
+        world
+            .view::<EntityId, C>()
+            .reborrow()
+            .changes_since(since)
+            .map(|(id, _)| id)
+    }
+
+    let since = prev.time;
+    let ids = changed::<PositionComponent>(world, since)
+        .chain(changed::<CarryComponent>(world, since))
+        .chain(changed::<HpComponent>(world, since))
+        .chain(changed::<MeleeAttackComponent>(world, since))
+        .chain(changed::<DecayComponent>(world, since))
+        .chain(changed::<OwnedEntity>(world, since))
+        .chain(changed::<EntityScript>(world, since))
+        .chain(changed::<EnergyComponent>(world, since))
+        .chain(changed::<EnergyRegenComponent>(world, since))
+        .chain(changed::<SpawnComponent>(world, since))
+        .chain(changed::<SpawnQueueComponent>(world, since))
+        .chain(changed::<RoomControllerComponent>(world, since))
+        .chain(changed::<ResourceComponent>(world, since))
+        .chain(
+            world
+                .view::<EntityTime, LogEntry>()
+                .reborrow()
+                .changes_since(since)
+                .map(|(EntityTime(id, _), _)| id),
+        )
+        // the tables of the intents have no change tracking, but only hold the last tick
+        .chain(
+            world
+                .view::<EntityId, SayComponent>()
+                .reborrow()
+                .iter()
+                .map(|(id, _)| id),
         )
+        .chain(
+            world
+                .view::<EntityId, MineEventComponent>()
+                .reborrow()
+                .iter()
+                .map(|(id, _)| id),
+        )
+        .chain(
+            world
+                .view::<EntityId, DropoffEventComponent>()
+                .reborrow()
+                .iter()
+                .map(|(id, _)| id),
+        );
+
+    let positions = world.view::<EntityId, PositionComponent>();
+    let mut rooms = prev.transient_rooms.clone();
+    for id in ids {
+        // the entity may have moved, or was removed, since the previous payload
+        rooms.extend(prev.entity_rooms.get(&id).copied());
+        rooms.extend(positions.get(id).map(|PositionComponent(pos)| pos.room));
     }
+    rooms.extend(
+        world
+            .view::<EmptyKey, WorldEvents>()
+            .unwrap_value()
+            .iter()
+            .map(|event| event.pos().room),
+    );
+    rooms
+}
+
+/// The room has data of the last tick only, so it has to be rebuilt by the next update
+fn is_transient(pl: &cao_world::RoomEntities) -> bool {
+    !pl.events.is_empty()
+        || !pl.dead_entities.is_empty()
+        || pl.bots.iter().any(|bot| {
+            !bot.logs.is_empty()
+                || !bot.say.is_empty()
+                || bot.dropoff_intent.is_some()
+                || bot.mine_intent.is_some()
+        })
 }
 
 #[tonic::async_trait]
 impl cao_world::world_server::World for WorldService {
     type EntitiesStream = ReceiverStream<Result<cao_world::RoomEntities, Status>>;
+    type EntitiesDeltaStream = ReceiverStream<Result<cao_world::RoomEntitiesDelta, Status>>;
+    type SubscribeEntitiesStream = ReceiverStream<Result<cao_world::RoomEntities, Status>>;
+    type RoomUpdatesStream = ReceiverStream<Result<cao_world::RoomList, Status>>;
+    type GetEntityLogsStream = ReceiverStream<Result<cao_world::EntityLog, Status>>;
 
     async fn entities(
         &self,
@@ -127,6 +457,135 @@ impl cao_world::world_server::World for WorldService {
         Ok(tonic::Response::new(ReceiverStream::new(rx)))
     }
 
+    async fn subscribe_entities(
+        &self,
+        r: tonic::Request<tonic::Streaming<cao_world::EntitiesSubscription>>,
+    ) -> Result<tonic::Response<Self::SubscribeEntitiesStream>, tonic::Status> {
+        let addr = r.remote_addr();
+
+        info!(
+            "Subscribing new client to filtered world entities. Addr: {:?}",
+            addr
+        );
+
+        let mut subscriptions = r.into_inner();
+        let (filter_tx, filter_rx) = watch::channel(subscription::RoomFilter::default());
+        tokio::spawn(
+            async move {
+                while let Some(msg) = subscriptions.next().await {
+                    match msg {
+                        Ok(msg) => {
+                            if filter_tx
+                                .send(subscription::RoomFilter::from_msg(&msg))
+                                .is_err()
+                            {
+                                break;
+                            }
+                        }
+                        Err(err) => {
+                            warn!("Failed to receive entities subscription {:?}", err);
+                            break;
+                        }
+                    }
+                }
+            }
+            .instrument(self.tracing_span.clone()),
+        );
+
+        let (tx, rx) = mpsc::channel(4);
+
+        let mut entities_rx = self.entities.subscribe();
+        tokio::spawn(
+            async move {
+                'main_send: loop {
+                    let w = match entities_rx.recv().await {
+                        Ok(w) => w,
+                        Err(RecvError::Lagged(l)) => {
+                            warn!("Entities stream is lagging behind by {} messages", l);
+                            continue 'main_send;
+                        }
+                        Err(RecvError::Closed) => {
+                            warn!("Entities channel was closed");
+                            break 'main_send;
+                        }
+                    };
+                    let payloads: Vec<_> = {
+                        let filter = filter_rx.borrow();
+                        w.payload_by_room
+                            .iter()
+                            .filter(|(room_id, _)| filter.contains(**room_id))
+                            .map(|(_, pl)| pl.clone())
+                            .collect()
+                    };
+                    for pl in payloads {
+                        if tx.send(Ok(pl)).await.is_err() {
+                            info!("World entities client lost {:?}", addr);
+                            break 'main_send;
+                        }
+                    }
+                }
+            }
+            .instrument(self.tracing_span.clone()),
+        );
+
+        Ok(tonic::Response::new(ReceiverStream::new(rx)))
+    }
+
+    async fn entities_delta(
+        &self,
+        r: tonic::Request<cao_common::Empty>,
+    ) -> Result<tonic::Response<Self::EntitiesDeltaStream>, tonic::Status> {
+        let addr = r.remote_addr();
+
+        info!(
+            "Subscribing new client to world entity deltas. Addr: {:?}",
+            addr
+        );
+
+        let (tx, rx) = mpsc::channel(4);
+
+        let mut entities_rx = self.entities.subscribe();
+        tokio::spawn(
+            async move {
+                // new and lagging clients have to start from a keyframe
+                let mut needs_keyframe = true;
+                'main_send: loop {
+                    let w = match entities_rx.recv().await {
+                        Ok(w) => w,
+                        Err(RecvError::Lagged(l)) => {
+                            warn!("Entities stream is lagging behind by {} messages", l);
+                            needs_keyframe = true;
+                            continue 'main_send;
+                        }
+                        Err(RecvError::Closed) => {
+                            warn!("Entities channel was closed");
+                            break 'main_send;
+                        }
+                    };
+                    if needs_keyframe && !w.keyframe {
+                        for (_, pl) in w.payload_by_room.iter() {
+                            if tx.send(Ok(delta::keyframe(pl))).await.is_err() {
+                                info!("World entities client lost {:?}", addr);
+                                break 'main_send;
+                            }
+                        }
+                    } else {
+                        for (_, pl) in w.deltas_by_room.iter() {
+                            if tx.send(Ok(pl.clone())).await.is_err() {
+                                info!("World entities client lost {:?}", addr);
+                                break 'main_send;
+                            }
+                        }
+                    }
+                    needs_keyframe = false;
+                }
+            }
+            .instrument(self.tracing_span.clone()),
+        );
+
+        Ok(tonic::Response::new(ReceiverStream::new(rx)))
+    }
+
     async fn get_room_layout(
         &self,
         r: tonic::Request<cao_world::GetRoomLayoutMsg>,
@@ -151,33 +610,52 @@ impl cao_world::world_server::World for WorldService {
         &self,
         _: tonic::Request<cao_common::Empty>,
     ) -> Result<tonic::Response<cao_world::RoomList>, tonic::Status> {
-        let rooms = self
-            .terrain
+        let cache = self.map.cache.read().await;
+        let rooms = cache
+            .rooms
             .keys()
-            .map(|point| {
-                let room_id = cao_common::Axial {
-                    q: point.q,
-                    r: point.r,
-                };
-
-                let room = self.rooms[point];
-                let offset = room.offset;
-                let offset = cao_common::Axial {
-                    q: offset.q,
-                    r: offset.r,
-                };
-
-                cao_world::Room {
-                    room_id: Some(room_id),
-                    radius: self.room_bounds.radius,
-                    offset: Some(offset),
-                    seed: room.seed,
-                }
-            })
+            .filter_map(|room_id| cache.room_msg(*room_id, self.room_bounds.radius))
             .collect();
         Ok(tonic::Response::new(cao_world::RoomList { rooms }))
     }
 
+    async fn room_updates(
+        &self,
+        r: tonic::Request<cao_common::Empty>,
+    ) -> Result<tonic::Response<Self::RoomUpdatesStream>, tonic::Status> {
+        let addr = r.remote_addr();
+
+        info!("Subscribing new client to room updates. Addr: {:?}", addr);
+
+        let (tx, rx) = mpsc::channel(4);
+
+        let mut updates_rx = self.map.updates.subscribe();
+        tokio::spawn(
+            async move {
+                loop {
+                    let list = match updates_rx.recv().await {
+                        Ok(list) => list,
+                        Err(RecvError::Lagged(l)) => {
+                            warn!("Room updates stream is lagging behind by {} messages", l);
+                            continue;
+                        }
+                        Err(RecvError::Closed) => {
+                            warn!("Room updates channel was closed");
+                            break;
+                        }
+                    };
+                    if tx.send(Ok(list)).await.is_err() {
+                        info!("Room updates client lost {:?}", addr);
+                        break;
+                    }
+                }
+            }
+            .instrument(self.tracing_span.clone()),
+        );
+
+        Ok(tonic::Response::new(ReceiverStream::new(rx)))
+    }
+
     async fn get_room_terrain(
         &self,
         request: tonic::Request<cao_common::Axial>,
@@ -185,12 +663,13 @@ impl cao_world::world_server::World for WorldService {
         let q = request.get_ref().q;
         let r = request.get_ref().r;
         let p = Axial::new(q, r);
-        let room = self
+        let cache = self.map.cache.read().await;
+        let room = cache
             .terrain
             .get(&p)
             .ok_or_else(|| tonic::Status::not_found("Room does not exist"))?;
 
-        let center = self.rooms[&p].offset;
+        let center = cache.rooms[&p].offset;
         Ok(tonic::Response::new(cao_world::RoomTerrain {
             room_id: Some(cao_common::Axial { q, r }),
             offset: Some(cao_common::Axial {
@@ -209,6 +688,66 @@ impl cao_world::world_server::World for WorldService {
                 .collect(),
         }))
     }
+
+    async fn get_entity(
+        &self,
+        request: tonic::Request<cao_world::GetEntityMsg>,
+    ) -> Result<tonic::Response<cao_world::EntityComponents>, tonic::Status> {
+        let id: EntityId = request
+            .get_ref()
+            .entity_id
+            .try_into()
+            .map_err(|_| tonic::Status::invalid_argument("invalid entity id"))?;
+        let w = self.world.read().await;
+        if !w.is_valid_entity(id) {
+            return Err(tonic::Status::not_found("Entity id is invalid"));
+        }
+        entity::entity_components(&w, id)
+            .map(tonic::Response::new)
+            .ok_or_else(|| tonic::Status::not_found("Entity not found"))
+    }
+
+    async fn get_entity_logs(
+        &self,
+        request: tonic::Request<cao_world::GetEntityLogsMsg>,
+    ) -> Result<tonic::Response<Self::GetEntityLogsStream>, tonic::Status> {
+        let msg = request.get_ref();
+        let id: EntityId = msg
+            .entity_id
+            .try_into()
+            .map_err(|_| tonic::Status::invalid_argument("invalid entity id"))?;
+        let from = msg.from_tick.max(0) as u64;
+        let to = msg.to_tick.max(0) as u64;
+        if to < from {
+            return Err(tonic::Status::invalid_argument(
+                "toTick must not be less than fromTick",
+            ));
+        }
+        let logs = {
+            let w = self.world.read().await;
+            // deleted entities may have archived logs
+            let logs = entity::entity_logs(&w, id, from, to);
+            if logs.is_empty() && !w.is_valid_entity(id) {
+                return Err(tonic::Status::not_found("Entity id is invalid"));
+            }
+            logs
+            // free the world lock asap
+        };
+
+        let (tx, rx) = mpsc::channel(16);
+        tokio::spawn(
+            async move {
+                for log in logs {
+                    if tx.send(Ok(log)).await.is_err() {
+                        break;
+                    }
+                }
+            }
+            .instrument(self.tracing_span.clone()),
+        );
+
+        Ok(tonic::Response::new(ReceiverStream::new(rx)))
+    }
 }
 
 #[cfg(test)]
@@ -220,7 +759,7 @@ mod tests {
     fn can_update_payload() {
         let mut pl = Payload::default();
 
-        let mut exc = caolo_sim::prelude::SimpleExecutor;
+        let mut exc = caolo_sim::prelude::SimpleExecutor::default();
         let mut w =
             futures_lite::future::block_on(exc.initialize(caolo_sim::executor::GameConfig {
                 world_radius: 2,
@@ -229,8 +768,82 @@ mod tests {
             }));
         caolo_sim::init::init_world_entities(&mut w, 12);
 
-        pl.update(&w);
+        pl.update(&w, None);
 
         assert!(!pl.payload_by_room.is_empty());
     }
+
+    #[test]
+    fn update_serializes_the_changed_rooms_only() {
+        let mut exc = caolo_sim::prelude::SimpleExecutor::default();
+        let mut w =
+            futures_lite::future::block_on(exc.initialize(caolo_sim::executor::GameConfig {
+                world_radius: 2,
+                room_radius: 10,
+                ..Default::default()
+            }));
+        caolo_sim::init::init_world_entities(&mut w, 12);
+        enable_change_tracking(&mut w);
+
+        let mut prev = Payload::default();
+        prev.update(&w, None);
+
+        let mut resource_rooms = prev
+            .payload_by_room
+            .iter()
+            .filter(|(_, pl)| !pl.resources.is_empty())
+            .map(|(room_id, _)| *room_id);
+        let changed_room = resource_rooms.next().expect("a room with resources");
+        let unchanged_room = resource_rooms.next().expect("another room with resources");
+
+        let resource = EntityId::from(prev.payload_by_room[&changed_room].resources[0].id);
+        w.unsafe_view::<EntityId, EnergyComponent>().insert(
+            resource,
+            EnergyComponent {
+                energy: 42,
+                energy_max: 100,
+            },
+        );
+        // rooms taken from the previous payload are not serialized again
+        prev.payload_by_room
+            .get_mut(&unchanged_room)
+            .unwrap()
+            .resources
+            .clear();
+
+        let mut pl = Payload::default();
+        pl.update(&w, Some(&prev));
+
+        let energy = pl.payload_by_room[&changed_room]
+            .resources
+            .iter()
+            .find(|r| r.id == u64::from(resource))
+            .and_then(|r| r.resource_type.clone());
+        assert_eq!(
+            energy,
+            Some(cao_world::resource::ResourceType::Energy(
+                cao_world::Bounded {
+                    value: 42,
+                    value_max: 100,
+                }
+            ))
+        );
+        assert!(pl.payload_by_room[&unchanged_room].resources.is_empty());
+    }
+
+    #[test]
+    fn map_cache_contains_every_room() {
+        let mut exc = caolo_sim::prelude::SimpleExecutor::default();
+        let w = futures_lite::future::block_on(exc.initialize(caolo_sim::executor::GameConfig {
+            world_radius: 2,
+            room_radius: 10,
+            ..Default::default()
+        }));
+
+        let cache = MapCache::from_world(&w);
+
+        let n_rooms = w.view::<Axial, RoomComponent>().iter().count();
+        assert_eq!(cache.rooms.len(), n_rooms);
+        assert_eq!(cache.terrain.len(), n_rooms);
+    }
 }
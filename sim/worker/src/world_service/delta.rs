@@ -0,0 +1,152 @@
+//! Compute the changes of the room payloads between ticks
+//!
+//! Entities are compared by their ids, changed entities are sent in full.
+use std::collections::{HashMap, HashSet};
+
+use caolo_sim::prelude::Axial;
+
+use crate::protos::{cao_common, cao_world};
+
+/// Delta holding every entity of the room
+pub fn keyframe(room: &cao_world::RoomEntities) -> cao_world::RoomEntitiesDelta {
+    cao_world::RoomEntitiesDelta {
+        world_time: room.world_time,
+        room_id: room.room_id.clone(),
+        keyframe: true,
+        bots: room.bots.clone(),
+        structures: room.structures.clone(),
+        resources: room.resources.clone(),
+        removed: vec![],
+        dead_entities: room.dead_entities.clone(),
+        events: room.events.clone(),
+    }
+}
+
+/// Returns None if nothing changed in the room
+pub fn room_delta(
+    prev: Option<&cao_world::RoomEntities>,
+    next: &cao_world::RoomEntities,
+) -> Option<cao_world::RoomEntitiesDelta> {
+    let prev = match prev {
+        Some(prev) => prev,
+        None => return Some(keyframe(next)),
+    };
+    let mut removed = Vec::new();
+    let bots = diff(&prev.bots, &next.bots, |b| b.id, &mut removed);
+    let structures = diff(&prev.structures, &next.structures, |s| s.id, &mut removed);
+    let resources = diff(&prev.resources, &next.resources, |r| r.id, &mut removed);
+
+    if bots.is_empty()
+        && structures.is_empty()
+        && resources.is_empty()
+        && removed.is_empty()
+        && next.dead_entities.is_empty()
+        && next.events.is_empty()
+    {
+        return None;
+    }
+    Some(cao_world::RoomEntitiesDelta {
+        world_time: next.world_time,
+        room_id: next.room_id.clone(),
+        keyframe: false,
+        bots,
+        structures,
+        resources,
+        removed,
+        dead_entities: next.dead_entities.clone(),
+        events: next.events.clone(),
+    })
+}
+
+/// Delta of a room that has no entities left
+pub fn emptied_room(
+    room_id: Axial,
+    world_time: i64,
+    prev: &cao_world::RoomEntities,
+) -> cao_world::RoomEntitiesDelta {
+    let removed = prev
+        .bots
+        .iter()
+        .map(|b| b.id)
+        .chain(prev.structures.iter().map(|s| s.id))
+        .chain(prev.resources.iter().map(|r| r.id))
+        .collect();
+    cao_world::RoomEntitiesDelta {
+        world_time,
+        room_id: Some(cao_common::Axial {
+            q: room_id.q,
+            r: room_id.r,
+        }),
+        keyframe: false,
+        removed,
+        ..Default::default()
+    }
+}
+
+/// Returns the new and changed items. Pushes the ids of the missing items into `removed`
+fn diff<T: PartialEq + Clone>(
+    prev: &[T],
+    next: &[T],
+    id: impl Fn(&T) -> u64,
+    removed: &mut Vec<u64>,
+) -> Vec<T> {
+    let prev_by_id: HashMap<u64, &T> = prev.iter().map(|item| (id(item), item)).collect();
+    let next_ids: HashSet<u64> = next.iter().map(|item| id(item)).collect();
+
+    removed.extend(
+        prev.iter()
+            .map(|item| id(item))
+            .filter(|item_id| !next_ids.contains(item_id)),
+    );
+    next.iter()
+        .filter(|item| prev_by_id.get(&id(item)).map_or(true, |p| *p != *item))
+        .cloned()
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bot(id: u64, hp: i64) -> cao_world::Bot {
+        cao_world::Bot {
+            id,
+            hp: Some(cao_world::Bounded {
+                value: hp,
+                value_max: 100,
+            }),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn unchanged_rooms_produce_no_delta() {
+        let room = cao_world::RoomEntities {
+            bots: vec![bot(1, 100), bot(2, 50)],
+            ..Default::default()
+        };
+
+        assert!(room_delta(Some(&room), &room).is_none());
+    }
+
+    #[test]
+    fn delta_holds_changed_and_removed_entities() {
+        let prev = cao_world::RoomEntities {
+            world_time: 1,
+            bots: vec![bot(1, 100), bot(2, 50), bot(3, 10)],
+            ..Default::default()
+        };
+        let next = cao_world::RoomEntities {
+            world_time: 2,
+            bots: vec![bot(1, 100), bot(2, 40), bot(4, 100)],
+            ..Default::default()
+        };
+
+        let delta = room_delta(Some(&prev), &next).expect("delta");
+
+        assert!(!delta.keyframe);
+        assert_eq!(delta.world_time, 2);
+        assert_eq!(delta.bots, vec![bot(2, 40), bot(4, 100)]);
+        assert_eq!(delta.removed, vec![3]);
+    }
+}
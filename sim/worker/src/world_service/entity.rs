@@ -0,0 +1,117 @@
+use caolo_sim::prelude::*;
+
+use crate::protos::{cao_common, cao_world};
+
+/// Collect every component of the entity. Returns `None` if the entity is not a bot, structure or
+/// resource.
+pub fn entity_components(world: &World, id: EntityId) -> Option<cao_world::EntityComponents> {
+    let entities = &world.entities;
+    let components = if let Some(row) = entities.getby_bot(id) {
+        serde_json::to_vec(&row)
+    } else if let Some(row) = entities.getby_structure(id) {
+        serde_json::to_vec(&row)
+    } else if let Some(row) = entities.getby_resource(id) {
+        serde_json::to_vec(&row)
+    } else {
+        return None;
+    }
+    .expect("Failed to serialize entity components");
+
+    let logs = world
+        .view::<EntityTime, LogEntry>()
+        .range(EntityTime(id, 0)..=EntityTime(id, u64::MAX))
+        .map(|(EntityTime(_, time), entry)| cao_world::EntityLog {
+            time: time as i64,
+            payload: entry.payload.clone(),
+        })
+        .collect();
+
+    Some(cao_world::EntityComponents {
+        id: id.into(),
+        components: Some(cao_common::Json { value: components }),
+        logs,
+    })
+}
+
+/// Logs of the entity between the ticks `from` and `to`, inclusive.
+/// Archived logs are available after the entity was deleted.
+pub fn entity_logs(world: &World, id: EntityId, from: u64, to: u64) -> Vec<cao_world::EntityLog> {
+    if let Some(archive) = world.view::<EmptyKey, LogArchives>().unwrap_value().get(id) {
+        return archive
+            .range(from, to)
+            .map(|log| cao_world::EntityLog {
+                time: log.time as i64,
+                payload: log.payload.clone(),
+            })
+            .collect();
+    }
+    // archiving may be disabled, fall back to the retained logs
+    world
+        .view::<EntityTime, LogEntry>()
+        .range(EntityTime(id, from)..=EntityTime(id, to))
+        .map(|(EntityTime(_, time), entry)| cao_world::EntityLog {
+            time: time as i64,
+            payload: entry.payload.clone(),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn can_inspect_bots() {
+        let mut exc = caolo_sim::prelude::SimpleExecutor::default();
+        let mut w =
+            futures_lite::future::block_on(exc.initialize(caolo_sim::executor::GameConfig {
+                world_radius: 1,
+                room_radius: 10,
+                ..Default::default()
+            }));
+
+        let id = w.insert_entity();
+        caolo_sim::query!(
+            mutate
+            w
+            {
+                EntityId, Bot, .insert(id);
+                EntityId, HpComponent, .insert(id, HpComponent { hp: 42, hp_max: 100 });
+                EntityTime, LogEntry, .insert(EntityTime(id, 3), LogEntry { payload: "winnie".to_string() });
+            }
+        );
+
+        let res = entity_components(&w, id).expect("Failed to get the entity");
+
+        let components: serde_json::Value =
+            serde_json::from_slice(res.components.unwrap().value.as_slice()).unwrap();
+        assert_eq!(components["hp"]["hp"], 42);
+        assert_eq!(res.logs.len(), 1);
+        assert_eq!(res.logs[0].time, 3);
+
+        let missing = w.insert_entity();
+        assert!(entity_components(&w, missing).is_none());
+    }
+
+    #[test]
+    fn logs_of_deleted_entities_are_served_from_the_archive() {
+        let mut exc = caolo_sim::prelude::SimpleExecutor::default();
+        let mut w =
+            futures_lite::future::block_on(exc.initialize(caolo_sim::executor::GameConfig {
+                world_radius: 1,
+                room_radius: 10,
+                ..Default::default()
+            }));
+
+        // never allocated, like the id of a deleted entity
+        let id = EntityId::new(1234, 1);
+        assert!(!w.is_valid_entity(id));
+        w.unsafe_view::<EmptyKey, LogArchives>()
+            .unwrap_mut()
+            .push(id, 3, "winnie", 10);
+
+        let logs = entity_logs(&w, id, 0, 10);
+        assert_eq!(logs.len(), 1);
+        assert_eq!(logs[0].payload, "winnie");
+    }
+}
@@ -26,6 +26,7 @@ type BotTables<'a> = (
 
 pub fn bot_payload(
     out: &mut HashMap<Axial, cao_world::RoomEntities>,
+    filter: impl Fn(Axial) -> bool,
     (
         room_entities,
         rooms,
@@ -50,6 +51,9 @@ pub fn bot_payload(
     let mut accumulator = Vec::with_capacity(128);
 
     for (next_room, entities) in room_entities {
+        if !filter(next_room.0) {
+            continue;
+        }
         // push the accumulator
         if Some(next_room) != room {
             if !accumulator.is_empty() {
@@ -15,6 +15,7 @@ type ResourceTables<'a> = (
 
 pub fn resource_payload(
     out: &mut HashMap<Axial, cao_world::RoomEntities>,
+    filter: impl Fn(Axial) -> bool,
     (room_entities, rooms, resource, energy, WorldTime(time)): ResourceTables,
 ) {
     let room_entities = room_entities.iter_rooms();
@@ -24,6 +25,9 @@ pub fn resource_payload(
     let mut accumulator = Vec::with_capacity(128);
 
     for (next_room, entities) in room_entities {
+        if !filter(next_room.0) {
+            continue;
+        }
         // push the accumulator
         if Some(next_room) != room {
             if !accumulator.is_empty() {
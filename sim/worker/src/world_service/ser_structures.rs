@@ -15,11 +15,13 @@ type StructureTables<'a> = (
     View<'a, EntityId, EnergyRegenComponent>,
     View<'a, EntityId, SpawnComponent>,
     View<'a, EntityId, SpawnQueueComponent>,
+    View<'a, EntityId, RoomControllerComponent>,
     WorldTime,
 );
 
 pub fn structure_payload(
     out: &mut HashMap<Axial, cao_world::RoomEntities>,
+    filter: impl Fn(Axial) -> bool,
     (
         room_entities,
         rooms,
@@ -30,6 +32,7 @@ pub fn structure_payload(
         energy_regen,
         spawn,
         spawn_q,
+        controllers,
         WorldTime(time),
     ): StructureTables,
 ) {
@@ -40,6 +43,9 @@ pub fn structure_payload(
     let mut accumulator = Vec::with_capacity(128);
 
     for (next_room, entities) in room_entities {
+        if !filter(next_room.0) {
+            continue;
+        }
         // push the accumulator
         if Some(next_room) != room {
             if !accumulator.is_empty() {
@@ -111,6 +117,17 @@ pub fn structure_payload(
                                         .unwrap_or_default(),
                                 },
                             ))
+                        } else if let Some(controller) = controllers.get(entity_id) {
+                            Some(cao_world::structure::StructureBody::Controller(
+                                cao_world::structure::Controller {
+                                    level: controller.level.into(),
+                                    progress: Some(cao_world::Bounded {
+                                        value: controller.progress.into(),
+                                        value_max: controller.progress_max().into(),
+                                    }),
+                                    downgrade_timer: controller.downgrade_timer.into(),
+                                },
+                            ))
                         } else {
                             None
                         }
@@ -0,0 +1,67 @@
+use std::collections::HashSet;
+
+use caolo_sim::prelude::{Axial, Hexagon};
+
+use crate::protos::cao_world;
+
+/// The rooms a client is subscribed to
+#[derive(Debug, Clone, Default)]
+pub struct RoomFilter {
+    rooms: HashSet<Axial>,
+    range: Option<Hexagon>,
+}
+
+impl RoomFilter {
+    pub fn from_msg(msg: &cao_world::EntitiesSubscription) -> Self {
+        let rooms = msg
+            .rooms
+            .iter()
+            .map(|room| Axial::new(room.q, room.r))
+            .collect();
+        let range = msg.range.as_ref().and_then(|range| {
+            let center = range.center.as_ref()?;
+            Some(Hexagon {
+                center: Axial::new(center.q, center.r),
+                radius: range.radius as i32,
+            })
+        });
+        Self { rooms, range }
+    }
+
+    pub fn contains(&self, room: Axial) -> bool {
+        self.rooms.contains(&room)
+            || self
+                .range
+                .map(|range| range.contains(room))
+                .unwrap_or(false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protos::cao_common;
+
+    #[test]
+    fn filter_contains_listed_rooms_and_range() {
+        let filter = RoomFilter::from_msg(&cao_world::EntitiesSubscription {
+            rooms: vec![cao_common::Axial { q: 10, r: 10 }],
+            range: Some(cao_world::RoomRange {
+                center: Some(cao_common::Axial { q: 2, r: 2 }),
+                radius: 1,
+            }),
+        });
+
+        assert!(filter.contains(Axial::new(10, 10)));
+        assert!(filter.contains(Axial::new(2, 2)));
+        assert!(filter.contains(Axial::new(3, 2)));
+        assert!(!filter.contains(Axial::new(4, 2)));
+        assert!(!filter.contains(Axial::new(10, 11)));
+    }
+
+    #[test]
+    fn empty_filter_contains_nothing() {
+        let filter = RoomFilter::default();
+        assert!(!filter.contains(Axial::new(0, 0)));
+    }
+}
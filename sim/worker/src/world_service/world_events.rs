@@ -2,13 +2,107 @@ use std::collections::HashMap;
 
 use caolo_sim::prelude::*;
 
+use super::util::push_room_pl;
+use crate::protos::cao_common;
 use crate::protos::cao_world;
 
-type EventsTables<'a> = (View<'a, EntityId, PositionComponent>,);
+type EventsTables<'a> = (
+    UnwrapView<'a, EmptyKey, WorldEvents>,
+    View<'a, Axial, RoomComponent>,
+    WorldTime,
+);
+
+#[derive(Default)]
+struct RoomEvents {
+    dead_entities: Vec<cao_world::DeadEntity>,
+    events: Vec<cao_world::WorldEvent>,
+}
 
 pub fn events_payload(
-    _out: &mut HashMap<Axial, cao_world::RoomEntities>,
-    (_entity_positions,): EventsTables,
+    out: &mut HashMap<Axial, cao_world::RoomEntities>,
+    filter: impl Fn(Axial) -> bool,
+    (events, rooms, WorldTime(time)): EventsTables,
 ) {
-    // TODO
+    let mut by_room: HashMap<Axial, RoomEvents> = HashMap::new();
+    for event in events.iter() {
+        let pos = event.pos();
+        if !filter(pos.room) {
+            continue;
+        }
+        let room_events = by_room.entry(pos.room).or_default();
+        let pos = Some(cao_common::WorldPosition {
+            pos: Some(pos.pos.into()),
+            room: Some(pos.room.into()),
+            offset: rooms.get(pos.room).map(|room| room.offset.into()),
+        });
+        let event = match *event {
+            WorldEvent::Death { entity, .. } => {
+                room_events.dead_entities.push(cao_world::DeadEntity {
+                    id: entity.into(),
+                    pos,
+                });
+                continue;
+            }
+            WorldEvent::Attack {
+                attacker,
+                defender,
+                damage,
+                ..
+            } => cao_world::world_event::Event::Attack(cao_world::world_event::Attack {
+                attacker: attacker.into(),
+                defender: defender.into(),
+                damage: damage.into(),
+                pos,
+            }),
+            WorldEvent::Spawn { spawn, bot, .. } => {
+                cao_world::world_event::Event::Spawn(cao_world::world_event::Spawn {
+                    spawn: spawn.into(),
+                    bot: bot.into(),
+                    pos,
+                })
+            }
+            WorldEvent::Mine {
+                bot,
+                resource,
+                amount,
+                ..
+            } => cao_world::world_event::Event::Mine(cao_world::world_event::Mine {
+                bot: bot.into(),
+                resource: resource.into(),
+                amount: amount.into(),
+                pos,
+            }),
+            WorldEvent::Dropoff {
+                bot,
+                structure,
+                amount,
+                ..
+            } => cao_world::world_event::Event::Dropoff(cao_world::world_event::Dropoff {
+                bot: bot.into(),
+                structure: structure.into(),
+                amount: amount.into(),
+                pos,
+            }),
+        };
+        room_events
+            .events
+            .push(cao_world::WorldEvent { event: Some(event) });
+    }
+
+    for (room, room_events) in by_room {
+        push_room_pl(
+            out,
+            room,
+            |pl| &mut pl.dead_entities,
+            room_events.dead_entities,
+            time as i64,
+        );
+        push_room_pl(
+            out,
+            room,
+            |pl| &mut pl.events,
+            room_events.events,
+            time as i64,
+        );
+    }
 }